@@ -0,0 +1,850 @@
+//! # `cli` module
+//!
+//!  This module parses the command line arguments and runs the commands that
+//!  don't need the full-screen interface.
+//!
+//!  Without a command, the application starts the interactive interface.
+//!  Every other command works on the model directly and never touches the
+//!  terminal, so that the vault can be driven from shell scripts, cron jobs
+//!  and editor integrations: results go to standard output, problems to
+//!  standard error, and the exit code tells whether the command succeeded.
+
+use chrono::{Local, NaiveDate};
+use vannevar::{
+    all_pages, check_vault, export_rollup, export_trail, journal_name, load_journal_page,
+    load_note, load_trail, migrate, reindex, search, CompileOptions, ExportFormat, FileError,
+    Folder, Journal, Note, PageRef, Period, Resolver, Rollup, Trail, TrailError, Vault,
+};
+
+use std::io::{self, Read};
+
+/// Usage message printed when the arguments can't be parsed.
+pub const USAGE: &str = "\
+Usage: vannevar [--vault <path>] [command]
+
+Commands:
+  reindex    Rebuild the search index of the vault
+  migrate    Upgrade the pages of the vault to the current file format
+  check [--strict]
+             Look for broken links, missing trail hops and orphan notes;
+             exits with 1 if errors are found (or warnings, with --strict)
+  new-note <title> [text]
+             Create a note (`-` reads the text from standard input)
+  append-journal [--date <yyyy-mm-dd>] [--page <note>]... [text]
+             Append a line to a journal page (today's by default) and
+             list notes in it
+  add-hop <trail> <note> [description] [--at <n>]
+             Add a hop to a trail, at the end or at position n, creating
+             the trail if needed
+  list [notes|journal|trails]
+             List the pages of the vault
+  show <page>
+             Print a page, given as `trails/<name>`, `journal/<date>` or
+             `notes/<title>` (or just the title of a note)
+  search <query>
+             Search the vault
+  export <trail> [--format md|txt|html]... [--no-headings] [--no-sources]
+         [--strip-links] [--stdout]
+             Compile a trail into the exports folder, or print it
+  rollup week|month [--date <yyyy-mm-dd>] [--stdout]
+             Review the journal pages of this week or month (or of the one
+             including the date) as Markdown, in the exports folder or
+             printed
+
+Without a command, the interactive interface is started.";
+
+/// ## Command
+///
+///  The commands accepted on the command line.
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Interactive,
+    Reindex,
+    Migrate,
+    Check {
+        strict: bool,
+    },
+    NewNote {
+        title: String,
+        text: String,
+    },
+    AppendJournal {
+        date: Option<String>,
+        text: String,
+        pages: Vec<String>,
+    },
+    AddHop {
+        trail: String,
+        note: String,
+        description: String,
+        at: Option<usize>,
+    },
+    List {
+        folder: Option<Folder>,
+    },
+    Show {
+        page: String,
+    },
+    Search {
+        query: String,
+    },
+    Export {
+        trail: String,
+        options: CompileOptions,
+        stdout: bool,
+    },
+    Rollup {
+        period: Period,
+        date: Option<NaiveDate>,
+        stdout: bool,
+    },
+}
+
+/// ## Args
+///
+///  The parsed command line: the vault location given with `--vault`, if
+///  any, and the command to run.
+#[derive(Debug, PartialEq)]
+pub struct Args {
+    pub vault: Option<String>,
+    pub command: Command,
+}
+
+/// Options that are followed by a value.
+const VALUE_OPTIONS: [&str; 4] = ["--date", "--page", "--at", "--format"];
+
+/// Parses the command line arguments (without the name of the program).
+pub fn parse_args<I: Iterator<Item = String>>(args: I) -> Result<Args, String> {
+    let mut vault = None;
+    let mut name = None;
+    let mut rest = Vec::new();
+    let mut args = args;
+
+    while let Some(arg) = args.next() {
+        if arg == "--vault" {
+            match args.next() {
+                Some(path) => vault = Some(path),
+                None => return Err(String::from("--vault needs a path")),
+            }
+        } else if let Some(path) = arg.strip_prefix("--vault=") {
+            vault = Some(String::from(path));
+        } else if name.is_none() {
+            name = Some(arg);
+        } else {
+            rest.push(arg);
+        }
+    }
+
+    let command = match name {
+        Some(name) => parse_command(&name, rest)?,
+        None => Command::Interactive,
+    };
+
+    Ok(Args { vault, command })
+}
+
+/// The arguments following a command: its positional arguments and its
+/// `--options`, with their values. Everything after `--` is positional.
+struct CommandArgs {
+    positional: Vec<String>,
+    options: Vec<(String, String)>,
+}
+
+impl CommandArgs {
+    fn split(args: Vec<String>) -> Result<CommandArgs, String> {
+        let mut positional = Vec::new();
+        let mut options = Vec::new();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            if arg == "--" {
+                positional.extend(args.by_ref());
+            } else if arg.starts_with("--") {
+                let (option, value) = match arg.split_once('=') {
+                    Some((o, v)) => (String::from(o), String::from(v)),
+                    None if VALUE_OPTIONS.contains(&arg.as_str()) => match args.next() {
+                        Some(v) => (arg, v),
+                        None => return Err(format!("{} needs a value", arg)),
+                    },
+                    None => (arg, String::new()),
+                };
+                options.push((option, value));
+            } else {
+                positional.push(arg);
+            }
+        }
+
+        Ok(CommandArgs {
+            positional,
+            options,
+        })
+    }
+    /// Fails on options other than `accepted`.
+    fn accept(&self, accepted: &[&str]) -> Result<(), String> {
+        match self
+            .options
+            .iter()
+            .find(|(o, _)| !accepted.contains(&o.as_str()))
+        {
+            Some((o, _)) => Err(format!("unexpected argument: {}", o)),
+            None => Ok(()),
+        }
+    }
+    /// Fails unless there are between `min` and `max` positional arguments.
+    fn expect(&self, min: usize, max: usize, what: &str) -> Result<(), String> {
+        match self.positional.len() {
+            n if n < min => Err(format!("missing {}", what)),
+            n if n > max => Err(format!("unexpected argument: {}", self.positional[max])),
+            _ => Ok(()),
+        }
+    }
+    fn has(&self, option: &str) -> bool {
+        self.options.iter().any(|(o, _)| o == option)
+    }
+    fn values(&self, option: &str) -> Vec<String> {
+        self.options
+            .iter()
+            .filter(|(o, _)| o == option)
+            .map(|(_, v)| v.clone())
+            .collect()
+    }
+    fn value(&self, option: &str) -> Option<String> {
+        self.values(option).pop()
+    }
+    fn arg(&self, index: usize) -> String {
+        self.positional.get(index).cloned().unwrap_or_default()
+    }
+    /// The value of `--date`, if given, checked to be a `yyyy-mm-dd` date.
+    fn date(&self) -> Result<Option<NaiveDate>, String> {
+        match self.value("--date") {
+            Some(d) => match NaiveDate::parse_from_str(&d, "%Y-%m-%d") {
+                Ok(date) => Ok(Some(date)),
+                Err(_) => Err(format!("not a date: {}", d)),
+            },
+            None => Ok(None),
+        }
+    }
+}
+
+/// Parses a command and the arguments following it.
+fn parse_command(name: &str, args: Vec<String>) -> Result<Command, String> {
+    let args = CommandArgs::split(args)?;
+
+    let command = match name {
+        "reindex" | "migrate" => {
+            args.accept(&[])?;
+            args.expect(0, 0, "")?;
+            match name {
+                "reindex" => Command::Reindex,
+                _ => Command::Migrate,
+            }
+        }
+        "check" => {
+            args.accept(&["--strict"])?;
+            args.expect(0, 0, "")?;
+            Command::Check {
+                strict: args.has("--strict"),
+            }
+        }
+        "new-note" => {
+            args.accept(&[])?;
+            args.expect(1, 2, "the title of the note")?;
+            Command::NewNote {
+                title: args.arg(0),
+                text: args.arg(1),
+            }
+        }
+        "append-journal" => {
+            args.accept(&["--date", "--page"])?;
+            args.expect(0, 1, "")?;
+            let date = args.date()?.map(journal_name);
+            let pages = args.values("--page");
+            if args.positional.is_empty() && pages.is_empty() {
+                return Err(String::from("missing the text to append or a --page"));
+            }
+            Command::AppendJournal {
+                date,
+                text: args.arg(0),
+                pages,
+            }
+        }
+        "add-hop" => {
+            args.accept(&["--at"])?;
+            args.expect(2, 3, "the trail and the note of the hop")?;
+            let at = match args.value("--at") {
+                Some(n) => match n.parse::<usize>() {
+                    Ok(n) if n > 0 => Some(n),
+                    _ => return Err(format!("not a hop position: {}", n)),
+                },
+                None => None,
+            };
+            Command::AddHop {
+                trail: args.arg(0),
+                note: args.arg(1),
+                description: args.arg(2),
+                at,
+            }
+        }
+        "list" => {
+            args.accept(&[])?;
+            args.expect(0, 1, "")?;
+            let folder = match args.positional.first() {
+                Some(f) => match Folder::ALL
+                    .iter()
+                    .find(|folder| folder.name() == f || folder.kind() == f)
+                {
+                    Some(folder) => Some(*folder),
+                    None => return Err(format!("unknown folder: {}", f)),
+                },
+                None => None,
+            };
+            Command::List { folder }
+        }
+        "show" => {
+            args.accept(&[])?;
+            args.expect(1, 1, "the page to show")?;
+            Command::Show { page: args.arg(0) }
+        }
+        "search" => {
+            args.accept(&[])?;
+            args.expect(1, usize::MAX, "the query")?;
+            Command::Search {
+                query: args.positional.join(" "),
+            }
+        }
+        "export" => {
+            args.accept(&[
+                "--format",
+                "--no-headings",
+                "--no-sources",
+                "--strip-links",
+                "--stdout",
+            ])?;
+            args.expect(1, 1, "the trail to export")?;
+            let mut options = CompileOptions {
+                headings: !args.has("--no-headings"),
+                sources: !args.has("--no-sources"),
+                strip_links: args.has("--strip-links"),
+                ..CompileOptions::default()
+            };
+            let formats = args.values("--format");
+            if !formats.is_empty() {
+                options.formats.clear();
+                for f in formats {
+                    let format = match f.as_str() {
+                        "md" | "markdown" => ExportFormat::Markdown,
+                        "txt" | "text" => ExportFormat::Text,
+                        "html" => ExportFormat::Html,
+                        _ => return Err(format!("unknown format: {}", f)),
+                    };
+                    if !options.formats.contains(&format) {
+                        options.formats.push(format);
+                    }
+                }
+            }
+            let stdout = args.has("--stdout");
+            if stdout && options.formats.len() > 1 {
+                return Err(String::from("--stdout prints a single format"));
+            }
+            Command::Export {
+                trail: args.arg(0),
+                options,
+                stdout,
+            }
+        }
+        "rollup" => {
+            args.accept(&["--date", "--stdout"])?;
+            args.expect(1, 1, "the period of the rollup (week or month)")?;
+            let period = match args.arg(0).as_str() {
+                "week" => Period::Week,
+                "month" => Period::Month,
+                p => return Err(format!("unknown period: {}", p)),
+            };
+            Command::Rollup {
+                period,
+                date: args.date()?,
+                stdout: args.has("--stdout"),
+            }
+        }
+        _ => return Err(format!("unknown command: {}", name)),
+    };
+
+    Ok(command)
+}
+
+/// Returns `text`, or what's written on standard input when `text` is `-`.
+fn read_text(text: &str) -> Option<String> {
+    match text {
+        "-" => {
+            let mut buffer = String::new();
+            match io::stdin().read_to_string(&mut buffer) {
+                Ok(_) => Some(String::from(buffer.trim_end_matches('\n'))),
+                Err(_) => None,
+            }
+        }
+        _ => Some(String::from(text)),
+    }
+}
+
+/// Finds the page named on the command line: `<folder>/<name>`, or the
+/// title or id of a note.
+fn parse_page(page: &str) -> PageRef {
+    match page.split_once('/') {
+        Some((folder, name)) => match Folder::ALL.iter().find(|f| f.name() == folder) {
+            Some(f) => PageRef::new(*f, name),
+            None => PageRef::new(Folder::Notes, page),
+        },
+        None => PageRef::new(Folder::Notes, page),
+    }
+}
+
+/// Runs a command that doesn't need the interactive interface, and returns
+/// the exit code of the process.
+pub fn run(command: &Command, vault: &Vault) -> i32 {
+    match command {
+        Command::Interactive => 0,
+        Command::Reindex => match reindex(vault) {
+            Ok(n) => {
+                println!("Indexed {} pages.", n);
+                0
+            }
+            Err(e) => {
+                eprintln!("Could not write the search index: {}", e);
+                1
+            }
+        },
+        Command::Migrate => match migrate(vault) {
+            Ok(report) => {
+                for (_, e) in &report.failed {
+                    eprintln!("{}", e);
+                }
+                match report.migrated.len() {
+                    0 => println!("No pages to migrate."),
+                    n => println!(
+                        "Migrated {} pages; the original files were copied to {}.",
+                        n,
+                        report.backup.display()
+                    ),
+                }
+                match report.failed.is_empty() {
+                    true => 0,
+                    false => {
+                        eprintln!(
+                            "{} pages could not be migrated and were left untouched.",
+                            report.failed.len()
+                        );
+                        1
+                    }
+                }
+            }
+            Err(e) => {
+                eprintln!("Could not migrate the vault: {}", e);
+                1
+            }
+        },
+        Command::Check { strict } => {
+            let report = check_vault(vault);
+            for problem in &report.problems {
+                println!("{}", problem);
+            }
+            match report.problems.len() {
+                0 => println!("No problems found in {} pages.", report.pages),
+                _ => println!(
+                    "Checked {} pages: {} errors, {} warnings.",
+                    report.pages,
+                    report.errors(),
+                    report.warnings()
+                ),
+            }
+            match report.errors() > 0 || (*strict && report.warnings() > 0) {
+                true => 1,
+                false => 0,
+            }
+        }
+        Command::NewNote { title, text } => new_note(vault, title, text),
+        Command::AppendJournal { date, text, pages } => append_journal(vault, date, text, pages),
+        Command::AddHop {
+            trail,
+            note,
+            description,
+            at,
+        } => add_hop(vault, trail, note, description, *at),
+        Command::List { folder } => {
+            let resolver = Resolver::build(vault);
+            for page in all_pages(vault) {
+                if folder.is_none_or(|f| f == page.folder) {
+                    let name = match page.folder {
+                        Folder::Notes => resolver.title(&page.name),
+                        _ => &page.name,
+                    };
+                    println!("{}/{}", page.folder.name(), name);
+                }
+            }
+            0
+        }
+        Command::Show { page } => show(vault, &parse_page(page)),
+        Command::Search { query } => {
+            let hits = search(vault, query);
+            let resolver = Resolver::build(vault);
+            for hit in &hits {
+                let name = match hit.page.folder {
+                    Folder::Notes => resolver.title(&hit.page.name),
+                    _ => &hit.page.name,
+                };
+                println!("{}/{}: {}", hit.page.folder.name(), name, hit.snippet);
+            }
+            match hits.is_empty() {
+                true => 1,
+                false => 0,
+            }
+        }
+        Command::Export {
+            trail,
+            options,
+            stdout,
+        } => export(vault, trail, options, *stdout),
+        Command::Rollup {
+            period,
+            date,
+            stdout,
+        } => rollup(vault, *period, *date, *stdout),
+    }
+}
+
+/// Tells whether `title` (already trimmed) can be the title of a note, and
+/// so the target of a `[link]`.
+fn is_valid_title(title: &str) -> bool {
+    !title.is_empty() && !title.contains(['[', ']', '\n'])
+}
+
+fn new_note(vault: &Vault, title: &str, text: &str) -> i32 {
+    let title = title.trim();
+    if !is_valid_title(title) {
+        eprintln!("Not a valid title: {}", title);
+        return 2;
+    }
+    if Resolver::build(vault).resolve(title).is_some() {
+        eprintln!("A note titled {} already exists.", title);
+        return 1;
+    }
+    let text = match read_text(text) {
+        Some(t) => t,
+        None => {
+            eprintln!("Could not read the text of the note.");
+            return 1;
+        }
+    };
+
+    let mut note = Note::from_str(title, text);
+    match note.save(vault) {
+        Ok(()) => {
+            println!("{}", vault.path(Folder::Notes, &note.file).display());
+            0
+        }
+        Err(e) => {
+            eprintln!("Could not save the note: {}", e);
+            1
+        }
+    }
+}
+
+fn append_journal(vault: &Vault, date: &Option<String>, text: &str, pages: &[String]) -> i32 {
+    let pages: Vec<_> = pages.iter().map(|p| p.trim()).collect();
+    if let Some(page) = pages.iter().find(|p| !is_valid_title(p)) {
+        eprintln!("Not a valid page title: {}", page);
+        return 2;
+    }
+    let date = match date {
+        Some(d) => d.clone(),
+        None => journal_name(Local::now().date_naive()),
+    };
+    let mut journal = match load_journal_page(vault, &date) {
+        Ok(j) => j,
+        Err(e) if e.is_not_found() => Journal::from_template(vault, &date),
+        Err(e) => {
+            eprintln!("Could not load the journal page: {}", e);
+            return 1;
+        }
+    };
+    let text = match read_text(text) {
+        Some(t) => t,
+        None => {
+            eprintln!("Could not read the text to append.");
+            return 1;
+        }
+    };
+
+    if !text.is_empty() {
+        if !journal.description.is_empty() {
+            journal.description.push('\n');
+        }
+        journal.description.push_str(&text);
+    }
+    for page in pages {
+        if !journal.pages.iter().any(|p| p == page) {
+            journal.pages.push(String::from(page));
+        }
+    }
+
+    match journal.save(vault) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("Could not save the journal page: {}", e);
+            1
+        }
+    }
+}
+
+fn add_hop(vault: &Vault, name: &str, note: &str, description: &str, at: Option<usize>) -> i32 {
+    let mut trail = match load_trail(vault, name) {
+        Ok(t) => t,
+        Err(TrailError::FileError(e))
+            if e.is_not_found() || matches!(e, FileError::EmptyFileError(_)) =>
+        {
+            Trail::from_template(vault, name)
+        }
+        Err(e) => {
+            eprintln!("Could not load the trail {}: {}", name, e);
+            return 1;
+        }
+    };
+
+    let index = at.map_or(trail.hops.len(), |n| n - 1);
+    trail.insert_hop(index, note, description);
+    match trail.save(vault) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("Could not save the trail: {}", e);
+            1
+        }
+    }
+}
+
+fn show(vault: &Vault, page: &PageRef) -> i32 {
+    let text = match page.folder {
+        Folder::Notes => load_note(vault, &page.name).map(|n| n.text),
+        Folder::Journal => load_journal_page(vault, &page.name).map(|j| j.to_str()),
+        Folder::Trails => load_trail(vault, &page.name)
+            .map(|t| t.to_str())
+            .map_err(|e| match e {
+                TrailError::FileError(e) => e,
+                TrailError::DescriptionError(e) | TrailError::BodyFormatError(e) => {
+                    FileError::FormatError(vault.path(Folder::Trails, &page.name), e)
+                }
+            }),
+    };
+
+    match text {
+        Ok(t) => {
+            println!("{}", t.trim_end_matches('\n'));
+            0
+        }
+        Err(e) if e.is_not_found() => {
+            eprintln!("There's no {} named {}.", page.kind(), page.name);
+            1
+        }
+        Err(e) => {
+            eprintln!("Could not load the {} {}: {}", page.kind(), page.name, e);
+            1
+        }
+    }
+}
+
+fn export(vault: &Vault, name: &str, options: &CompileOptions, stdout: bool) -> i32 {
+    let trail = match load_trail(vault, name) {
+        Ok(t) => t,
+        Err(TrailError::FileError(e)) if e.is_not_found() => {
+            eprintln!("There's no trail named {}.", name);
+            return 1;
+        }
+        Err(e) => {
+            eprintln!("Could not load the trail {}: {}", name, e);
+            return 1;
+        }
+    };
+
+    if stdout {
+        print!("{}", trail.compile(vault, options, options.formats[0]));
+        return 0;
+    }
+    match export_trail(vault, &trail, options) {
+        Ok(paths) => {
+            for path in paths {
+                println!("{}", path.display());
+            }
+            0
+        }
+        Err(e) => {
+            eprintln!("Could not export the trail: {}", e);
+            1
+        }
+    }
+}
+
+fn rollup(vault: &Vault, period: Period, date: Option<NaiveDate>, stdout: bool) -> i32 {
+    let date = date.unwrap_or_else(|| Local::now().date_naive());
+    let rollup = Rollup::build(vault, period, date);
+
+    if stdout {
+        print!("{}", rollup.compile(ExportFormat::Markdown));
+        return 0;
+    }
+    match export_rollup(vault, &rollup) {
+        Ok(path) => {
+            println!("{}", path.display());
+            0
+        }
+        Err(e) => {
+            eprintln!("Could not export the rollup: {}", e);
+            1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parses a command line, split on spaces.
+    fn parse(line: &str) -> Result<Args, String> {
+        parse_args(line.split_whitespace().map(String::from))
+    }
+
+    fn command(line: &str) -> Command {
+        parse(line).unwrap().command
+    }
+
+    #[test]
+    fn no_command_starts_the_interface() {
+        assert_eq!(
+            parse("").unwrap(),
+            Args {
+                vault: None,
+                command: Command::Interactive
+            }
+        );
+        assert_eq!(parse("--vault /tmp/v").unwrap().vault.unwrap(), "/tmp/v");
+        assert_eq!(parse("--vault=/tmp/v").unwrap().vault.unwrap(), "/tmp/v");
+        assert_eq!(parse("--vault").unwrap_err(), "--vault needs a path");
+    }
+
+    #[test]
+    fn commands_are_parsed_with_their_arguments() {
+        assert_eq!(command("reindex --vault /tmp/v"), Command::Reindex);
+        assert_eq!(command("check --strict"), Command::Check { strict: true });
+        assert_eq!(
+            command("new-note Memex -"),
+            Command::NewNote {
+                title: String::from("Memex"),
+                text: String::from("-"),
+            }
+        );
+        assert_eq!(
+            command("append-journal --date 2024-03-11 --page Memex --page=Bush"),
+            Command::AppendJournal {
+                date: Some(String::from("2024-03-11")),
+                text: String::new(),
+                pages: vec![String::from("Memex"), String::from("Bush")],
+            }
+        );
+        assert_eq!(
+            command("add-hop Tour Memex --at 2"),
+            Command::AddHop {
+                trail: String::from("Tour"),
+                note: String::from("Memex"),
+                description: String::new(),
+                at: Some(2),
+            }
+        );
+        assert_eq!(
+            command("list journal"),
+            Command::List {
+                folder: Some(Folder::Journal)
+            }
+        );
+        assert_eq!(
+            command("search as we -- --may"),
+            Command::Search {
+                query: String::from("as we --may")
+            }
+        );
+        assert_eq!(
+            command("rollup month --stdout"),
+            Command::Rollup {
+                period: Period::Month,
+                date: None,
+                stdout: true,
+            }
+        );
+    }
+
+    #[test]
+    fn export_options_are_collected() {
+        let Command::Export {
+            trail,
+            options,
+            stdout,
+        } = command("export Tour --format html --format=txt --format html --strip-links")
+        else {
+            panic!("not an export");
+        };
+
+        assert_eq!(trail, "Tour");
+        assert_eq!(options.formats, [ExportFormat::Html, ExportFormat::Text]);
+        assert!(options.headings && options.sources && options.strip_links);
+        assert!(!stdout);
+    }
+
+    #[test]
+    fn unknown_commands_and_options_are_rejected() {
+        for (line, error) in [
+            ("publish Tour", "unknown command: publish"),
+            ("reindex now", "unexpected argument: now"),
+            ("check --fix", "unexpected argument: --fix"),
+            ("list drafts", "unknown folder: drafts"),
+            ("export Tour --format pdf", "unknown format: pdf"),
+            (
+                "export Tour --format md --format txt --stdout",
+                "--stdout prints a single format",
+            ),
+            ("rollup year", "unknown period: year"),
+            ("rollup week --date 2024-13-01", "not a date: 2024-13-01"),
+            ("add-hop Tour Memex --at 0", "not a hop position: 0"),
+        ] {
+            assert_eq!(parse(line).unwrap_err(), error, "{}", line);
+        }
+    }
+
+    #[test]
+    fn missing_operands_are_reported() {
+        for (line, error) in [
+            ("new-note", "missing the title of the note"),
+            ("append-journal", "missing the text to append or a --page"),
+            ("append-journal --date", "--date needs a value"),
+            ("add-hop Tour", "missing the trail and the note of the hop"),
+            ("show", "missing the page to show"),
+            ("search", "missing the query"),
+            ("export", "missing the trail to export"),
+            ("rollup", "missing the period of the rollup (week or month)"),
+        ] {
+            assert_eq!(parse(line).unwrap_err(), error, "{}", line);
+        }
+    }
+
+    #[test]
+    fn pages_are_named_by_folder_or_by_note() {
+        assert_eq!(
+            parse_page("journal/2024-03-11"),
+            PageRef::new(Folder::Journal, "2024-03-11")
+        );
+        assert_eq!(
+            parse_page("trails/Tour"),
+            PageRef::new(Folder::Trails, "Tour")
+        );
+        assert_eq!(parse_page("Memex"), PageRef::new(Folder::Notes, "Memex"));
+        assert_eq!(
+            parse_page("Input/output"),
+            PageRef::new(Folder::Notes, "Input/output")
+        );
+    }
+}
@@ -1,414 +1,939 @@
-/// # `controller` module
-///
-/// This program is built on an MVC pattern, and this module hosts the structs
-/// and functions that make up its controller.
+//! # `controller` module
+//!
+//! This program is built on an MVC pattern, and this module hosts the structs
+//! and functions that make up its controller.
+use chrono::{Local, NaiveDate};
 
-use crate::lib::{
-    list_files, load_journal_page, load_note, FileError, Journal, Model, Note, Trail, load_trail, TrailError
-};
+use crate::editor::History;
 use crate::view::*;
+use crate::{
+    all_pages, check_vault, export_rollup, export_trail, journal_date, journal_days, journal_name,
+    journal_neighbours, list_files, load_journal_page, load_meta, load_note, load_trail,
+    rename_note, save_meta, search, CompileOptions, FileError, Folder, Journal, Model, Note,
+    PageRef, Period, RenameError, RenameReport, Resolver, Rollup, SearchHit, Trail, TrailError,
+    Vault,
+};
+
+/// Section of the vault metadata folder where the undo history of each note
+/// is kept.
+const HISTORY_SECTION: &str = "history";
+
+/// Section of the vault metadata folder where the last hop read of each
+/// trail is kept, so that walking a trail resumes where it was left.
+const WALK_SECTION: &str = "walk";
+
+fn rename_error_page(e: RenameError) -> CurrentPage {
+    let message = String::from(match e {
+        RenameError::InvalidTitle => "The new title is not valid.",
+        RenameError::NotFound => "The note must be saved before it can be renamed.",
+        RenameError::AlreadyExists => "A note with the new title already exists.",
+        RenameError::FileError(_) => "Could not rename the note: no file was changed.",
+    });
+    match e {
+        RenameError::FileError(e) => CurrentPage::UnexpectedError(message, Some(e.to_string())),
+        _ => CurrentPage::UnexpectedError(message, None),
+    }
+}
+
+fn trail_error_page(e: TrailError) -> CurrentPage {
+    let message = String::from(match e {
+        TrailError::BodyFormatError(_) => {
+            "The body of the selected trail is formatted incorrectly."
+        }
+        TrailError::DescriptionError(_) => {
+            "The description of the selected trail is formatted incorrectly."
+        }
+        TrailError::FileError(FileError::FormatError(..)) => "The trail file is corrupted.",
+        TrailError::FileError(_) => "Could not load trail from memory.",
+    });
+    let details = match e {
+        TrailError::BodyFormatError(e) | TrailError::DescriptionError(e) => e.to_string(),
+        TrailError::FileError(e) => e.to_string(),
+    };
+    CurrentPage::UnexpectedError(message, Some(details))
+}
 
 /// ## CurrentPage
 ///
 ///  This `enum` is a list of all possible pages that can be displayed to the
 ///  user. Since the view can be seen as a state machine, these are all of its
 ///  possible states.
-
-
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum CurrentPage {
-    StartPage,  // Initial page of the application
-    MainMenu,   // Main menu
-    CreateNewJournal,   // Menu to choose whether or not to create a new journal page
-    JournalView,    // View mode for the journal page of the current day
-    JournalViewReadOnly,    // View mode (read-only) for old journal pages
-    JournalEditDescription, // Editor for the journal description
-    JournalAddLink, // Interface to create a new note and add it to the journal
-    SelectLink(Vec<String>),    // Menu to select a linked page from a list
-    NoteView,   // View mode for note pages
-    NoteEdit,   // Editor for the text in notes
-    SelectCreateTrail,  // Trail menu: select whether to create a new one or open an old one
-    CreateNewTrail, // Interface to create a new trail and give it a name
-    LoadTrail,  // Menu to select a loadable trail from a list
-    TrailView,  // View mode for trail pages
-    TrailEditDescription,   // Editor for the description of the currently opened trail
-    TrailAddHop,    // Interface to add a link to a note inside of a trail
-    SaveError(Box<CurrentPage>),    // Display an error message for failed saving procedures
-    UnexpectedError(String),    // Display an error message
+    /// Initial page of the application
+    StartPage,
+    /// Main menu
+    MainMenu,
+    /// Menu to choose whether or not to create a new journal page
+    CreateNewJournal,
+    /// Menu to choose whether or not to create the missing journal page of a past day
+    CreatePastJournal(NaiveDate),
+    /// Month grid of the journal pages, with the selected day
+    Calendar(NaiveDate),
+    /// View mode for the journal page being written (today's, or a past one just created)
+    JournalView,
+    /// View mode (read-only) for old journal pages
+    JournalViewReadOnly,
+    /// Opens the journal page of the given day, once the page being left is saved
+    JournalGoto(NaiveDate),
+    /// Review of the week or month including the given day, with the page to go back to
+    Rollup(Period, NaiveDate, Box<CurrentPage>),
+    /// Editor for the journal description
+    JournalEditDescription,
+    /// Interface to create a new note and add it to the journal
+    JournalAddLink,
+    /// Prompt for a timestamped line to add to the log of the day
+    JournalLog,
+    /// Menu to select a linked page from a list, with the page to go back to
+    SelectLink(Vec<String>, Box<CurrentPage>),
+    /// Menu to select a page linking to the current note
+    SelectBacklink(Vec<PageRef>),
+    /// Interface to type the title of a note to open, or to create if it's missing
+    LoadCreateNote,
+    /// View mode for note pages
+    NoteView,
+    /// Editor for the text in notes
+    NoteEdit,
+    /// Interface to choose a new title for the current note
+    NoteRename,
+    /// Preview of the links rewritten by a rename
+    NoteRenamePreview(RenameReport),
+    /// Trail menu: select whether to create a new one or open an old one
+    SelectCreateTrail,
+    /// Interface to create a new trail and give it a name
+    CreateNewTrail,
+    /// Menu to select a loadable trail from a list
+    LoadTrail,
+    /// View mode for trail pages
+    TrailView,
+    /// Editor for the description of the currently opened trail
+    TrailEditDescription,
+    /// Interface to add a link to a note inside of a trail
+    TrailAddHop,
+    /// Editor to remove and rearrange the hops of a trail, with the selected hop
+    TrailEditHops(usize),
+    /// Interface to insert a new hop at the given position
+    TrailInsertHop(usize),
+    /// Playback of the current trail, showing the note of the given hop
+    TrailWalk(usize),
+    /// Interface to compile the current trail into a document
+    TrailExport,
+    /// Interface to type a query to search the vault
+    Search,
+    /// Results of a search, with the query that produced them
+    SearchResults(String, Vec<SearchHit>),
+    /// Fuzzy finder over every page of the vault, with the page to go back to
+    Finder(Box<CurrentPage>),
+    /// List of the broken links, missing hops and orphan notes of the vault
+    HealthCheck,
+    /// Display an error message for failed saving procedures, with the error
+    SaveError(Box<CurrentPage>, FileError),
+    /// Display an error message, with the details of its cause (if any)
+    UnexpectedError(String, Option<String>),
 }
-pub struct Controller {
+pub struct Controller<V: View> {
     model: Model,
     current_page: CurrentPage,
+    view: V,
+    /// The page notes go back to: the journal page, trail or menu the last
+    /// note was opened from.
+    note_back: CurrentPage,
 }
 
 /// ## Controller
 ///
 ///  This is the state machine that controls the application. It runs on a loop
 ///  and controls the branching between the possible states, described by the
-///  [CurrentPage] `enum`.
-
-impl Controller {
-    pub fn new() -> Controller {
+///  [CurrentPage] `enum`, showing each of them through the [View] it was
+///  given.
+impl<V: View> Controller<V> {
+    pub fn new(vault: Vault, view: V) -> Controller<V> {
         Controller {
-            model: Model::new(),
+            model: Model::new(vault),
             current_page: CurrentPage::StartPage,
+            view,
+            note_back: CurrentPage::JournalView,
         }
     }
+    pub fn current_page(&self) -> &CurrentPage {
+        &self.current_page
+    }
+    pub fn model(&self) -> &Model {
+        &self.model
+    }
+    pub fn view(&self) -> &V {
+        &self.view
+    }
+    /// Runs the application until the user quits.
     pub fn execute(&mut self) {
-        loop {
-            match &self.current_page {
-                CurrentPage::StartPage => {
-                    start_page();
-                    self.current_page = match self.model.journal_page.date.len() {
-                        0 => CurrentPage::CreateNewJournal,
-                        _ => CurrentPage::JournalView,
-                    }
-                }
-                CurrentPage::MainMenu => {
-                    match display_menu() {
-                        MenuOption::Journal => match self.model.journal_page.date.len() {
-                            0 => self.current_page = CurrentPage::CreateNewJournal,
-                            _ => self.current_page = CurrentPage::JournalView,
-                        },
-                        MenuOption::LoadJournal => match list_files("../journal") {
-                            Ok(l) => match link_menu(&l) {
-                                LinkMessage::Exit => break,
-                                LinkMessage::Back => self.current_page = CurrentPage::JournalView,
-                                LinkMessage::GotoLink(link) => {
-                                    match str::parse::<usize>(&link) {
-                                            Ok(i) => match l.get(i) {
-                                                Some(path) => match load_journal_page(path) {
-                                                    Ok(j) => {
-                                                        self.model.journal_page = j;
-                                                        self.current_page = CurrentPage::JournalViewReadOnly
-                                                    }
-                                                    Err(e) => match e {
-                                                        FileError::ReadError => {
-                                                            self.current_page = CurrentPage::UnexpectedError("The selected journal page does not exist.".to_string())
-                                                        },
-                                                        FileError::FormatError => {}
-                                                        FileError::EmptyFileError => {}
-                                                    },
-                                                },
-                                                None => {}
-                                            },
-                                            Err(_) => {}
-                                        };
-                                }
-                            },
-                            Err(_) => {
-                                self.current_page = CurrentPage::UnexpectedError(
-                                    "Could not find any journal pages.".to_string(),
-                                )
-                            }
-                        },
-                        MenuOption::Notes => {
-                            todo!()
-                        }
-                        MenuOption::LoadCreateNote => {
-                            todo!()
-                        }
-                        MenuOption::Trails => {
-                            self.current_page = CurrentPage::TrailView;
-                        }
-                        MenuOption::LoadCreateTrail => {
-                            todo!()
+        while self.step() {}
+
+        self.view.reset_cursor();
+    }
+    /// Shows the current page and moves to the next one according to the
+    /// answer of the user. Returns `false` when the user quits.
+    pub fn step(&mut self) -> bool {
+        // Remember the page notes are opened from
+        if matches!(
+            self.current_page,
+            CurrentPage::MainMenu
+                | CurrentPage::JournalView
+                | CurrentPage::JournalViewReadOnly
+                | CurrentPage::TrailView
+        ) {
+            self.note_back = self.current_page.clone();
+        }
+        match &self.current_page {
+            CurrentPage::StartPage => {
+                self.view.start_page();
+                self.current_page = match self.model.journal_page.date.len() {
+                    0 => CurrentPage::CreateNewJournal,
+                    _ => CurrentPage::JournalView,
+                }
+            }
+            CurrentPage::MainMenu => match self.view.display_menu() {
+                MenuOption::Journal => {
+                    match load_journal_page(&self.model.vault, &self.model.current_date) {
+                        Ok(j) => {
+                            self.model.journal_page = j;
+                            self.current_page = CurrentPage::JournalView
                         }
-                        MenuOption::Quit => return,
+                        Err(_) => self.current_page = CurrentPage::CreateNewJournal,
                     }
                 }
-                CurrentPage::CreateNewJournal => match select_create_journal() {
+                MenuOption::LoadJournal => self.current_page = CurrentPage::Calendar(self.today()),
+                MenuOption::Notes => {
+                    self.current_page = match self.model.note.title.len() {
+                        0 => CurrentPage::LoadCreateNote,
+                        _ => CurrentPage::NoteView,
+                    }
+                }
+                MenuOption::LoadCreateNote => self.current_page = CurrentPage::LoadCreateNote,
+                MenuOption::Trails => {
+                    self.current_page = CurrentPage::TrailView;
+                }
+                MenuOption::LoadCreateTrail => self.current_page = CurrentPage::SelectCreateTrail,
+                MenuOption::Search => self.current_page = CurrentPage::Search,
+                MenuOption::Check => self.current_page = CurrentPage::HealthCheck,
+                MenuOption::Find => {
+                    self.current_page = CurrentPage::Finder(Box::new(CurrentPage::MainMenu))
+                }
+                MenuOption::Quit => return false,
+            },
+            CurrentPage::CreateNewJournal => {
+                match self.view.select_create_journal(&self.model.current_date) {
                     true => {
-                        self.model.journal_page = Journal::todays_journal();
+                        self.model.journal_page = Journal::todays_journal(&self.model.vault);
                         self.current_page = CurrentPage::JournalView;
                     }
                     false => self.current_page = CurrentPage::MainMenu,
-                },
-                CurrentPage::JournalView => {
-                    match display_journal(&self.model.journal_page) {
-                        JournalMessage::EditDescription => {
-                            self.current_page = CurrentPage::JournalEditDescription
-                        }
-                        JournalMessage::EditLinks => {
-                            self.current_page = CurrentPage::JournalAddLink
-                        }
-                        JournalMessage::Menu => self.current_page = CurrentPage::MainMenu,
-                        JournalMessage::SelectLinks => {
-                            self.current_page =
-                                CurrentPage::SelectLink(self.model.journal_page.pages.to_owned())
+                }
+            }
+            CurrentPage::CreatePastJournal(date) => {
+                let date = *date;
+                self.current_page = match self.view.select_create_journal(&journal_name(date)) {
+                    true => {
+                        self.model.journal_page =
+                            Journal::from_template(&self.model.vault, &journal_name(date));
+                        CurrentPage::JournalView
+                    }
+                    false => CurrentPage::Calendar(date),
+                }
+            }
+            CurrentPage::Calendar(date) => {
+                let days = journal_days(&self.model.vault);
+                match self.view.calendar(*date, &days) {
+                    CalendarMessage::Open(day) => {
+                        self.current_page = match days.contains_key(&day) {
+                            true => {
+                                self.open_page(&PageRef::new(Folder::Journal, &journal_name(day)))
+                            }
+                            false => CurrentPage::CreatePastJournal(day),
                         }
-                        JournalMessage::Exit => break,
-                    };
-                    match self.model.journal_page.save() {
-                        true => {}
-                        false => {
-                            self.current_page =
-                                CurrentPage::SaveError(Box::new(self.current_page.clone()))
+                    }
+                    CalendarMessage::Review(day) => {
+                        self.current_page = CurrentPage::Rollup(
+                            Period::Week,
+                            day,
+                            Box::new(CurrentPage::Calendar(day)),
+                        )
+                    }
+                    CalendarMessage::Back => self.current_page = CurrentPage::MainMenu,
+                    CalendarMessage::Exit => return false,
+                }
+            }
+            CurrentPage::JournalView => {
+                match self.view.display_journal(&self.model.journal_page) {
+                    JournalMessage::EditDescription => {
+                        self.current_page = CurrentPage::JournalEditDescription
+                    }
+                    JournalMessage::EditLinks => self.current_page = CurrentPage::JournalAddLink,
+                    JournalMessage::Menu => self.current_page = CurrentPage::MainMenu,
+                    JournalMessage::SelectLinks => {
+                        self.current_page = CurrentPage::SelectLink(
+                            self.model.journal_page.pages.to_owned(),
+                            Box::new(CurrentPage::JournalView),
+                        )
+                    }
+                    JournalMessage::OpenLink(i) => {
+                        if let Some(target) = self.model.journal_page.pages.get(i).cloned() {
+                            self.current_page = self.open_link(&target, CurrentPage::JournalView)
                         }
                     }
+                    JournalMessage::Find => {
+                        self.current_page = CurrentPage::Finder(Box::new(CurrentPage::JournalView))
+                    }
+                    JournalMessage::Previous => {
+                        self.current_page = self.journal_neighbour(false, CurrentPage::JournalView)
+                    }
+                    JournalMessage::Next => {
+                        self.current_page = self.journal_neighbour(true, CurrentPage::JournalView)
+                    }
+                    JournalMessage::Today => {
+                        self.current_page = CurrentPage::JournalGoto(self.today())
+                    }
+                    JournalMessage::Review => self.current_page = self.journal_review(),
+                    JournalMessage::Log => self.current_page = CurrentPage::JournalLog,
+                    JournalMessage::Exit => return false,
+                };
+                match self.model.journal_page.save(&self.model.vault) {
+                    Ok(()) => {
+                        let page = PageRef::new(Folder::Journal, &self.model.journal_page.date);
+                        self.model.backlinks.reload(&self.model.vault, &page);
+                    }
+                    Err(e) => {
+                        self.current_page =
+                            CurrentPage::SaveError(Box::new(self.current_page.clone()), e)
+                    }
                 }
-                CurrentPage::JournalViewReadOnly => {
-                    match display_journal(&self.model.journal_page) {
-                        JournalMessage::EditDescription => {}
-                        JournalMessage::EditLinks => {}
-                        JournalMessage::Menu => match load_journal_page(&self.model.current_date) {
+            }
+            CurrentPage::JournalViewReadOnly => {
+                match self.view.display_journal(&self.model.journal_page) {
+                    JournalMessage::EditDescription => {}
+                    JournalMessage::EditLinks => {}
+                    JournalMessage::Log => {}
+                    JournalMessage::Menu => {
+                        match load_journal_page(&self.model.vault, &self.model.current_date) {
                             Ok(j) => {
                                 self.model.journal_page = j;
                                 self.current_page = CurrentPage::MainMenu;
                             }
                             Err(_) => self.current_page = CurrentPage::CreateNewJournal,
-                        },
-                        JournalMessage::SelectLinks => {
-                            match load_journal_page(&self.model.current_date) {
-                                Ok(j) => {
-                                    self.model.journal_page = j;
-                                    self.current_page = CurrentPage::SelectLink(
-                                        self.model.journal_page.pages.to_owned(),
-                                    );
-                                }
-                                Err(_) => self.current_page = CurrentPage::CreateNewJournal,
-                            }
                         }
-                        JournalMessage::Exit => break,
-                    };
-                    match self.model.journal_page.save() {
-                        true => {}
-                        false => {
+                    }
+                    JournalMessage::SelectLinks => {
+                        self.current_page = CurrentPage::SelectLink(
+                            self.model.journal_page.pages.to_owned(),
+                            Box::new(CurrentPage::JournalViewReadOnly),
+                        )
+                    }
+                    JournalMessage::OpenLink(i) => {
+                        if let Some(target) = self.model.journal_page.pages.get(i).cloned() {
                             self.current_page =
-                                CurrentPage::SaveError(Box::new(self.current_page.clone()))
+                                self.open_link(&target, CurrentPage::JournalViewReadOnly)
+                        }
+                    }
+                    JournalMessage::Find => {
+                        self.current_page =
+                            CurrentPage::Finder(Box::new(CurrentPage::JournalViewReadOnly))
+                    }
+                    JournalMessage::Previous => {
+                        self.current_page =
+                            self.journal_neighbour(false, CurrentPage::JournalViewReadOnly)
+                    }
+                    JournalMessage::Next => {
+                        self.current_page =
+                            self.journal_neighbour(true, CurrentPage::JournalViewReadOnly)
+                    }
+                    JournalMessage::Today => {
+                        self.current_page = CurrentPage::JournalGoto(self.today())
+                    }
+                    JournalMessage::Review => self.current_page = self.journal_review(),
+                    JournalMessage::Exit => return false,
+                }
+            }
+            CurrentPage::JournalGoto(date) => {
+                let name = journal_name(*date);
+                let exists = self.model.vault.path(Folder::Journal, &name).exists();
+                self.current_page = match !exists && name == self.model.current_date {
+                    true => CurrentPage::CreateNewJournal,
+                    false => self.open_page(&PageRef::new(Folder::Journal, &name)),
+                }
+            }
+            CurrentPage::Rollup(period, date, back) => {
+                let rollup = Rollup::build(&self.model.vault, *period, *date);
+                let back = back.clone();
+                self.current_page = match self.view.display_rollup(&rollup) {
+                    RollupMessage::Previous => match rollup.start.pred_opt() {
+                        Some(day) => CurrentPage::Rollup(rollup.period, day, back),
+                        None => self.current_page.clone(),
+                    },
+                    RollupMessage::Next => match rollup.end.succ_opt() {
+                        Some(day) => CurrentPage::Rollup(rollup.period, day, back),
+                        None => self.current_page.clone(),
+                    },
+                    RollupMessage::Week => CurrentPage::Rollup(Period::Week, rollup.start, back),
+                    RollupMessage::Month => CurrentPage::Rollup(Period::Month, rollup.start, back),
+                    RollupMessage::Export => match export_rollup(&self.model.vault, &rollup) {
+                        Ok(path) => {
+                            self.view.export_done(&[path]);
+                            self.current_page.clone()
+                        }
+                        Err(e) => CurrentPage::UnexpectedError(
+                            String::from("Could not write the rollup."),
+                            Some(e.to_string()),
+                        ),
+                    },
+                    RollupMessage::Back => *back,
+                    RollupMessage::Exit => return false,
+                }
+            }
+            CurrentPage::JournalEditDescription => {
+                self.model.journal_page.description = self.view.edit_journal_description(
+                    &self.model.journal_page.description,
+                    self.model.config.editor,
+                );
+                self.current_page = CurrentPage::JournalView;
+            }
+            CurrentPage::JournalAddLink => {
+                let s = self.view.add_journal_link();
+                if !s.is_empty() && !self.model.journal_page.pages.contains(&s) {
+                    self.model.journal_page.pages.push(s);
+                }
+                self.current_page = CurrentPage::JournalView
+            }
+            CurrentPage::JournalLog => {
+                let s = self.view.log_journal_line();
+                if !s.trim().is_empty() {
+                    self.model.journal_page.log(Local::now().time(), &s);
+                }
+                self.current_page = CurrentPage::JournalView
+            }
+            CurrentPage::SelectLink(v, back) => {
+                let resolver = Resolver::build(&self.model.vault);
+                let pages: Vec<_> = v
+                    .iter()
+                    .map(|t| PageRef::new(Folder::Notes, resolver.title(t)))
+                    .collect();
+                match self.view.fuzzy_finder("LINKS", &pages) {
+                    FinderMessage::Back => self.current_page = *back.clone(),
+                    FinderMessage::Select(i) => {
+                        let (target, back) = (v[i].clone(), *back.clone());
+                        self.current_page = self.open_link(&target, back);
+                    }
+                }
+            }
+            CurrentPage::SelectBacklink(v) => {
+                let labels: Vec<_> = v.iter().map(|p| p.label()).collect();
+                match self.view.link_menu("REFERENCED BY", &labels) {
+                    LinkMessage::Exit => return false,
+                    LinkMessage::Back => self.current_page = CurrentPage::NoteView,
+                    LinkMessage::GotoLink(i) => {
+                        if let Some(page) = v.get(i) {
+                            let page = page.clone();
+                            self.current_page = self.open_page(&page);
                         }
                     }
                 }
-                CurrentPage::JournalEditDescription => {
-                    self.model.journal_page.description =
-                        edit_journal_description(&self.model.journal_page.description);
-                    self.current_page = CurrentPage::JournalView;
+            }
+            CurrentPage::LoadCreateNote => {
+                let title = self.view.load_create_note();
+                self.current_page = match title.trim() {
+                    "" => CurrentPage::MainMenu,
+                    title => self.open_link(title, CurrentPage::MainMenu),
                 }
-                CurrentPage::JournalAddLink => {
-                    let s = add_journal_link();
-                    match s.len() {
-                        0 => {}
-                        _ => match self.model.journal_page.pages.binary_search(&s) {
-                            Ok(_) => {}
-                            Err(_) => {
-                                self.model.journal_page.pages.push(s);
-                            }
-                        },
-                    }
-                    self.current_page = CurrentPage::JournalView
-                }
-                CurrentPage::SelectLink(v) => match link_menu(v) {
-                    LinkMessage::Exit => break,
-                    LinkMessage::Back => self.current_page = CurrentPage::JournalView,
-                    LinkMessage::GotoLink(l) => {
-                        match str::parse::<usize>(&l) {
-                            Ok(i) => match v.get(i) {
-                                Some(path) => match load_note(path) {
-                                    Ok(n) => {
-                                        self.model.note = n;
-                                        self.current_page = CurrentPage::NoteView
-                                    }
-                                    Err(e) => match e {
-                                        FileError::ReadError => match select_create_note(path) {
-                                            true => {
-                                                self.model.note =
-                                                    Note::from_str(path, String::new());
-                                                self.current_page = CurrentPage::NoteView;
-                                            }
-                                            false => self.current_page = CurrentPage::JournalView,
-                                        },
-                                        FileError::FormatError => {}
-                                        FileError::EmptyFileError => {}
-                                    },
-                                },
-                                None => {}
-                            },
-                            Err(_) => {}
-                        };
+            }
+            CurrentPage::NoteView => {
+                let referenced_by = self.model.backlinks.to(&self.model.note.file);
+                // Backlinks are opened once the note is saved
+                let mut backlink = None;
+                match self.view.display_note(&self.model.note, &referenced_by) {
+                    NoteMessage::Edit => self.current_page = CurrentPage::NoteEdit,
+                    NoteMessage::Rename => self.current_page = CurrentPage::NoteRename,
+                    NoteMessage::SelectLinks => {
+                        self.current_page = CurrentPage::SelectLink(
+                            self.model.note.links.to_owned(),
+                            Box::new(CurrentPage::NoteView),
+                        )
+                    }
+                    NoteMessage::SelectBacklinks => {
+                        self.current_page = CurrentPage::SelectBacklink(referenced_by.clone())
+                    }
+                    NoteMessage::OpenBacklink(i) => backlink = referenced_by.get(i).cloned(),
+                    NoteMessage::Find => {
+                        self.current_page = CurrentPage::Finder(Box::new(CurrentPage::NoteView))
+                    }
+                    NoteMessage::Menu => self.current_page = CurrentPage::MainMenu,
+                    NoteMessage::Back => self.current_page = self.note_back.clone(),
+                    NoteMessage::Exit => {
+                        self.current_page = CurrentPage::MainMenu;
+                        return false;
                     }
+                }
+                match self.model.note.save(&self.model.vault) {
+                    Ok(()) => {
+                        // Only today's page lists the notes: past pages stay
+                        // as they were written
+                        let title = self.model.note.title.clone();
+                        let journal = &mut self.model.journal_page;
+                        if journal.date == self.model.current_date
+                            && !journal.pages.contains(&title)
+                        {
+                            journal.pages.push(title);
+                        }
+                        self.model.note.parse_links();
+                        let page = PageRef::new(Folder::Notes, &self.model.note.file);
+                        self.model.backlinks.reload(&self.model.vault, &page);
+                        if let Some(page) = backlink {
+                            self.current_page = self.open_page(&page);
+                        }
+                    }
+                    Err(e) => {
+                        self.current_page =
+                            CurrentPage::SaveError(Box::new(self.current_page.clone()), e)
+                    }
+                }
+            }
+            CurrentPage::NoteEdit => {
+                let file = self.model.note.file.clone();
+                let mut history = match self.model.config.undo_history {
+                    true => match load_meta(&self.model.vault, HISTORY_SECTION, &file) {
+                        Ok(s) => History::parse(&s),
+                        Err(_) => History::new(),
+                    },
+                    false => History::new(),
+                };
+                let note_text = self.view.edit_note(
+                    &self.model.note.text,
+                    &mut history,
+                    self.model.config.editor,
+                );
+                if self.model.config.undo_history {
+                    let _ = save_meta(&self.model.vault, HISTORY_SECTION, &file, &history.to_str());
+                }
+                self.model.note.text = note_text;
+                self.model.note.parse_links();
+                self.current_page = CurrentPage::NoteView;
+            }
+            CurrentPage::NoteRename => {
+                let title = self.view.rename_note_prompt(&self.model.note.title);
+                self.current_page = match title.is_empty() {
+                    true => CurrentPage::NoteView,
+                    false => {
+                        match rename_note(&self.model.vault, &self.model.note.file, &title, true) {
+                            Ok(report) => CurrentPage::NoteRenamePreview(report),
+                            Err(e) => rename_error_page(e),
+                        }
+                    }
+                }
+            }
+            CurrentPage::NoteRenamePreview(report) => match self.view.confirm_rename(report) {
+                true => match rename_note(&self.model.vault, &report.file, &report.new, false) {
+                    Ok(r) => {
+                        self.view.rename_done(&r);
+                        self.reload_after_rename(&r);
+                        self.current_page = CurrentPage::NoteView;
+                    }
+                    Err(e) => self.current_page = rename_error_page(e),
                 },
-                CurrentPage::NoteView => {
-                    match display_note(&self.model.note) {
-                        NoteMessage::Edit => self.current_page = CurrentPage::NoteEdit,
-                        NoteMessage::SelectLinks => {
+                false => self.current_page = CurrentPage::NoteView,
+            },
+            CurrentPage::SelectCreateTrail => match self.view.select_create_trail() {
+                CreateTrailMessage::CreateTrail => self.current_page = CurrentPage::CreateNewTrail,
+                CreateTrailMessage::LoadTrail => self.current_page = CurrentPage::LoadTrail,
+                CreateTrailMessage::ReturnToJournal => self.current_page = CurrentPage::JournalView,
+            },
+            CurrentPage::CreateNewTrail => {
+                let s = self.view.create_new_trail();
+                if !s.is_empty() {
+                    self.model.trail = Trail::from_template(&self.model.vault, &s);
+                    self.current_page = CurrentPage::TrailView;
+                }
+            }
+            CurrentPage::LoadTrail => match list_files(&self.model.vault, Folder::Trails) {
+                Ok(list) => {
+                    let pages: Vec<_> = list
+                        .iter()
+                        .map(|n| PageRef::new(Folder::Trails, n))
+                        .collect();
+                    match self.view.fuzzy_finder("TRAILS", &pages) {
+                        FinderMessage::Back => self.current_page = CurrentPage::MainMenu,
+                        FinderMessage::Select(i) => self.current_page = self.open_trail(&list[i]),
+                    }
+                }
+                Err(e) => {
+                    self.current_page = CurrentPage::UnexpectedError(
+                        String::from("Trail loading error."),
+                        Some(e.to_string()),
+                    )
+                }
+            },
+            CurrentPage::TrailView => match self.model.trail.name.len() {
+                0 => self.current_page = CurrentPage::SelectCreateTrail,
+                _ => {
+                    match self.view.display_trail(&self.model.trail) {
+                        TrailMessage::AddLink => self.current_page = CurrentPage::TrailAddHop,
+                        TrailMessage::SelectLink => {
+                            let names = self
+                                .model
+                                .trail
+                                .hops
+                                .clone()
+                                .into_iter()
+                                .map(|x| x.0)
+                                .collect();
                             self.current_page =
-                                CurrentPage::SelectLink(self.model.note.links.to_owned())
+                                CurrentPage::SelectLink(names, Box::new(CurrentPage::TrailView))
                         }
-                        NoteMessage::Menu => self.current_page = CurrentPage::MainMenu,
-                        NoteMessage::Back => self.current_page = CurrentPage::JournalView,
-                        NoteMessage::Exit => {
-                            self.current_page = CurrentPage::MainMenu;
-                            return;
+                        TrailMessage::OpenHop(i) => {
+                            if let Some((target, _)) = self.model.trail.hops.get(i).cloned() {
+                                self.current_page = self.open_link(&target, CurrentPage::TrailView)
+                            }
                         }
-                    }
-                    match self.model.note.save() {
-                        true => {
-                            let title = self.model.note.title.clone();
-                            match self.model.journal_page.pages.binary_search(&title) {
-                                Ok(_) => {}
-                                Err(_) => {
-                                    self.model.journal_page.pages.push(title);
+                        TrailMessage::Quit => {
+                            return false;
+                        }
+                        TrailMessage::MainMenu => self.current_page = CurrentPage::MainMenu,
+                        TrailMessage::EditHops => self.current_page = CurrentPage::TrailEditHops(0),
+                        TrailMessage::EditDescription => {
+                            self.current_page = CurrentPage::TrailEditDescription
+                        }
+                        TrailMessage::Walk => {
+                            self.current_page = match self.model.trail.hops.len() {
+                                0 => CurrentPage::UnexpectedError(
+                                    String::from("The trail has no hops to walk through yet."),
+                                    None,
+                                ),
+                                n => {
+                                    let hop = load_meta(
+                                        &self.model.vault,
+                                        WALK_SECTION,
+                                        &self.model.trail.name,
+                                    )
+                                    .ok()
+                                    .and_then(|s| s.trim().parse::<usize>().ok())
+                                    .unwrap_or(0);
+                                    CurrentPage::TrailWalk(hop.min(n - 1))
                                 }
                             }
-                            self.model.note.parse_links();
                         }
-                        false => {
+                        TrailMessage::Export => self.current_page = CurrentPage::TrailExport,
+                        TrailMessage::Find => {
                             self.current_page =
-                                CurrentPage::SaveError(Box::new(self.current_page.clone()))
+                                CurrentPage::Finder(Box::new(CurrentPage::TrailView))
+                        }
+                    }
+                    match self.model.trail.save(&self.model.vault) {
+                        Ok(()) => self.reload_trail_backlinks(),
+                        Err(e) => {
+                            let boxed_page = Box::new(self.current_page.clone());
+                            self.current_page = CurrentPage::SaveError(boxed_page, e)
                         }
                     }
                 }
-                CurrentPage::NoteEdit => {
-                    let note_text = edit_note(&self.model.note.text);
-                    self.model.note = Note::from_str(&self.model.note.title, note_text);
-                    self.current_page = CurrentPage::NoteView;
+            },
+            CurrentPage::TrailEditDescription => {
+                self.model.trail.description = self.view.edit_trail_description(
+                    &self.model.trail.description,
+                    self.model.config.editor,
+                );
+                self.current_page = CurrentPage::TrailView;
+            }
+            CurrentPage::TrailAddHop => {
+                let (name, desc) = self.view.add_trail_hop();
+                let exists = self
+                    .model
+                    .trail
+                    .hops
+                    .iter()
+                    .any(|h| h.0 == name && h.1 == desc);
+                if !name.is_empty() && !exists {
+                    let end = self.model.trail.hops.len();
+                    self.model.trail.insert_hop(end, &name, &desc);
                 }
-                CurrentPage::SelectCreateTrail => match select_create_trail() {
-                    CreateTrailMessage::CreateTrail => {
-                        self.current_page = CurrentPage::CreateNewTrail
+                self.current_page = CurrentPage::TrailView;
+            }
+            CurrentPage::TrailEditHops(selected) => {
+                self.current_page = match self.view.edit_hops(&self.model.trail, *selected) {
+                    HopMessage::Remove(i) => {
+                        self.model.trail.remove_hop(i);
+                        CurrentPage::TrailEditHops(
+                            i.min(self.model.trail.hops.len().saturating_sub(1)),
+                        )
                     }
-                    CreateTrailMessage::LoadTrail => self.current_page = CurrentPage::LoadTrail,
-                    CreateTrailMessage::ReturnToJournal => {
-                        self.current_page = CurrentPage::JournalView
+                    HopMessage::MoveUp(i) => {
+                        CurrentPage::TrailEditHops(self.model.trail.move_hop(i, true).unwrap_or(i))
                     }
-                },
-                CurrentPage::CreateNewTrail => {
-                    let s = create_new_trail();
-                    match s.len() {
-                        0 => {}
-                        _ => {
-                            self.model.trail = Trail::new();
-                            self.model.trail.name = s;
-                            self.current_page = CurrentPage::TrailView;
+                    HopMessage::MoveDown(i) => {
+                        CurrentPage::TrailEditHops(self.model.trail.move_hop(i, false).unwrap_or(i))
+                    }
+                    HopMessage::Insert(i) => CurrentPage::TrailInsertHop(i),
+                    HopMessage::EditDescription(i) => {
+                        if let Some(d) = self.view.edit_hop_description(&self.model.trail.hops[i].1)
+                        {
+                            self.model.trail.set_hop_description(i, &d);
                         }
+                        CurrentPage::TrailEditHops(i)
+                    }
+                    HopMessage::Back => CurrentPage::TrailView,
+                };
+                match self.model.trail.save(&self.model.vault) {
+                    Ok(()) => self.reload_trail_backlinks(),
+                    Err(e) => {
+                        self.current_page =
+                            CurrentPage::SaveError(Box::new(CurrentPage::TrailView), e)
                     }
                 }
-                CurrentPage::LoadTrail => {
-                    match list_files("../journal") {
-                        Ok(list) => {
-                            match link_menu(&list) {
-                                LinkMessage::Exit => {},
-                                LinkMessage::Back => {},
-                                LinkMessage::GotoLink(link) => {
-                                    match str::parse::<usize>(&link) {
-                                        Ok(i) => match list.get(i) {
-                                            Some(path) => match load_trail(path) {
-                                                Ok(t) => {
-                                                    self.model.trail = t;
-                                                    self.current_page = CurrentPage::TrailView
-                                                }
-                                                Err(e) => match e {
-                                                    TrailError::BodyFormatError => {
-                                                        self.current_page = CurrentPage::UnexpectedError(String::from("The body of the selected trail is formatted incorrectly."))
-                                                    },
-                                                    TrailError::DescriptionError => {
-                                                        self.current_page = CurrentPage::UnexpectedError(String::from("The description of the selected trail is formatted incorrectly."))
-                                                    },
-                                                    TrailError::FileError(fe) => match fe {
-                                                        FileError::EmptyFileError => {
-                                                            self.model.trail = Trail::new();
-                                                            self.model.trail.name = String::from(path);
-                                                        },
-                                                        FileError::ReadError => {
-                                                            self.current_page = CurrentPage::UnexpectedError(String::from("Could not load trail from memory."))
-                                                        },
-                                                        FileError::FormatError => {
-                                                            self.current_page = CurrentPage::UnexpectedError(String::from("The trail file is corrupted."))
-                                                        }
-                                                    },
-                                                },
-                                            },
-                                            None => {
-                                                self.current_page = CurrentPage::UnexpectedError(String::from("The number you entered does not correspond to any valid option."))
-                                            }
-                                        },
-                                        Err(_) => {
-                                            self.current_page = CurrentPage::UnexpectedError(String::from("The input you entered is not valid."))
-                                        }
-                                    };
-                                }
-                            }
-                        },
-                        Err(_) => { self.current_page = CurrentPage::UnexpectedError(String::from("Trail loading error."))},
-                    }
-                }
-                CurrentPage::TrailView => match self.model.trail.name.len() {
-                    0 => self.current_page = CurrentPage::SelectCreateTrail,
-                    _ => {
-                        match display_trail(&self.model.trail) {
-                            TrailMessage::AddLink => self.current_page = CurrentPage::TrailAddHop,
-                            TrailMessage::SelectLink => {
-                                let names = self
-                                    .model
-                                    .trail
-                                    .hops
-                                    .clone()
-                                    .into_iter()
-                                    .map(|x| x.0)
-                                    .collect();
-                                self.current_page = CurrentPage::SelectLink(names)
-                            }
-                            TrailMessage::Quit => {
-                                break;
-                            }
-                            TrailMessage::MainMenu => self.current_page = CurrentPage::MainMenu,
-                            TrailMessage::RemoveLink => {
-                                todo!()
-                            }
-                            TrailMessage::EditDescription => {
-                                self.current_page = CurrentPage::TrailEditDescription
-                            }
+            }
+            CurrentPage::TrailInsertHop(i) => {
+                let i = *i;
+                let (name, desc) = self.view.add_trail_hop();
+                let exists = self
+                    .model
+                    .trail
+                    .hops
+                    .iter()
+                    .any(|h| h.0 == name && h.1 == desc);
+                if !name.is_empty() && !exists {
+                    self.model.trail.insert_hop(i, &name, &desc);
+                }
+                self.current_page = CurrentPage::TrailEditHops(i);
+            }
+            CurrentPage::TrailWalk(hop) => {
+                let hop = *hop;
+                let note = load_note(&self.model.vault, &self.model.trail.hops[hop].0).ok();
+                let _ = save_meta(
+                    &self.model.vault,
+                    WALK_SECTION,
+                    &self.model.trail.name,
+                    &hop.to_string(),
+                );
+                match self.view.walk_trail(&self.model.trail, hop, note.as_ref()) {
+                    WalkMessage::Next => {
+                        if hop + 1 < self.model.trail.hops.len() {
+                            self.current_page = CurrentPage::TrailWalk(hop + 1)
                         }
-                        match self.model.trail.save() {
-                            true => {}
-                            false => {
-                                let boxed_page = Box::new(self.current_page.clone());
-                                self.current_page =
-                                    CurrentPage::SaveError(boxed_page)
-                            }
+                    }
+                    WalkMessage::Previous => {
+                        self.current_page = CurrentPage::TrailWalk(hop.saturating_sub(1))
+                    }
+                    WalkMessage::Overview => self.current_page = CurrentPage::TrailView,
+                    WalkMessage::Find => {
+                        self.current_page =
+                            CurrentPage::Finder(Box::new(CurrentPage::TrailWalk(hop)))
+                    }
+                    WalkMessage::Menu => self.current_page = CurrentPage::MainMenu,
+                    WalkMessage::Exit => return false,
+                }
+            }
+            CurrentPage::TrailExport => {
+                let mut options = CompileOptions::default();
+                self.current_page = match self
+                    .view
+                    .export_options(&self.model.trail.name, &mut options)
+                {
+                    true => match export_trail(&self.model.vault, &self.model.trail, &options) {
+                        Ok(paths) => {
+                            self.view.export_done(&paths);
+                            CurrentPage::TrailView
                         }
+                        Err(e) => CurrentPage::UnexpectedError(
+                            String::from("Could not write the compiled trail."),
+                            Some(e.to_string()),
+                        ),
+                    },
+                    false => CurrentPage::TrailView,
+                }
+            }
+            CurrentPage::Search => {
+                let query = self.view.search_prompt();
+                self.current_page = match query.trim().is_empty() {
+                    true => CurrentPage::MainMenu,
+                    false => {
+                        let hits = search(&self.model.vault, &query);
+                        CurrentPage::SearchResults(query, hits)
                     }
-                },
-                CurrentPage::TrailEditDescription => {
-                    self.model.trail.description =
-                        edit_trail_description(&self.model.trail.description);
-                    self.current_page = CurrentPage::TrailView;
                 }
-                CurrentPage::TrailAddHop => {
-                    let (name, desc) = add_trail_hop();
-                    match name.len() {
-                        0 => {}
-                        _ => match self
-                            .model
-                            .trail
-                            .hops
-                            .binary_search(&(name.clone(), desc.clone()))
-                        {
-                            Ok(_) => {}
-                            Err(_) => {
-                                self.model.trail.hops.push((name, desc));
-                            }
-                        },
+            }
+            CurrentPage::SearchResults(query, hits) => {
+                match self.view.search_results(query, hits) {
+                    LinkMessage::Exit => return false,
+                    LinkMessage::Back => self.current_page = CurrentPage::Search,
+                    LinkMessage::GotoLink(i) => {
+                        if let Some(hit) = hits.get(i) {
+                            let page = hit.page.clone();
+                            self.current_page = self.open_page(&page);
+                        }
+                    }
+                }
+            }
+            CurrentPage::Finder(back) => {
+                let pages = all_pages(&self.model.vault);
+                // Show notes by title, even when they're stored under their id
+                let resolver = Resolver::build(&self.model.vault);
+                let shown: Vec<_> = pages
+                    .iter()
+                    .map(|p| match p.folder {
+                        Folder::Notes => PageRef::new(p.folder, resolver.title(&p.name)),
+                        _ => p.clone(),
+                    })
+                    .collect();
+                match self.view.fuzzy_finder("FIND", &shown) {
+                    FinderMessage::Back => self.current_page = *back.clone(),
+                    FinderMessage::Select(i) => self.current_page = self.open_page(&pages[i]),
+                }
+            }
+            CurrentPage::HealthCheck => {
+                let report = check_vault(&self.model.vault);
+                match self.view.health_check(&report) {
+                    LinkMessage::Exit => return false,
+                    LinkMessage::Back => self.current_page = CurrentPage::MainMenu,
+                    LinkMessage::GotoLink(i) => {
+                        let page = report.problems[i].page().clone();
+                        self.current_page = self.open_page(&page);
                     }
-                    self.current_page = CurrentPage::TrailView;
                 }
-                CurrentPage::SaveError(cp) => {
-                    let err = match **cp {
-                        CurrentPage::NoteView => "note",
-                        CurrentPage::JournalView => "journal page",
-                        CurrentPage::TrailView => "trail",
-                        _ => " ",
-                    };
-                    save_error(err);
-                    self.current_page = *cp.clone();
-                }
-                CurrentPage::UnexpectedError(s) => match display_error(s) {
+            }
+            CurrentPage::SaveError(cp, e) => {
+                let err = match **cp {
+                    CurrentPage::NoteView => "note",
+                    CurrentPage::JournalView => "journal page",
+                    CurrentPage::TrailView => "trail",
+                    _ => "page",
+                };
+                self.view.save_error(err, e);
+                self.current_page = *cp.clone();
+            }
+            CurrentPage::UnexpectedError(s, details) => {
+                match self.view.display_error(s, details.as_deref()) {
                     DisplayErrorMessage::Menu => self.current_page = CurrentPage::MainMenu,
-                    DisplayErrorMessage::Exit => break,
-                },
+                    DisplayErrorMessage::Exit => return false,
+                }
             }
         }
 
-        reset_cursor();
+        true
+    }
+    /// The current day, as picked when the session started.
+    fn today(&self) -> NaiveDate {
+        journal_date(&self.model.current_date).unwrap_or_else(|| Local::now().date_naive())
+    }
+    /// The page showing the closest journal page after (or before) the one
+    /// in the model, or `stay` if there's none.
+    fn journal_neighbour(&self, forward: bool, stay: CurrentPage) -> CurrentPage {
+        let (previous, next) = journal_date(&self.model.journal_page.date)
+            .map(|date| journal_neighbours(&self.model.vault, date))
+            .unwrap_or_default();
+        let target = match forward {
+            true => next,
+            false => previous,
+        };
+        match target {
+            Some(date) => CurrentPage::JournalGoto(date),
+            None => stay,
+        }
+    }
+    /// The rollup of the week of the journal page in the model, going back to
+    /// the page afterwards.
+    fn journal_review(&self) -> CurrentPage {
+        let date = journal_date(&self.model.journal_page.date).unwrap_or_else(|| self.today());
+        CurrentPage::Rollup(Period::Week, date, Box::new(self.current_page.clone()))
+    }
+    /// Brings the pages held by the model up to date after a rename.
+    fn reload_after_rename(&mut self, report: &RenameReport) {
+        let vault = &self.model.vault;
+        let renamed =
+            [&report.file, &report.destination].map(|file| PageRef::new(Folder::Notes, file));
+        for page in renamed.iter().chain(report.pages.iter().map(|(p, _)| p)) {
+            self.model.backlinks.reload(vault, page);
+        }
+        if let Ok(n) = load_note(vault, &report.destination) {
+            self.model.note = n;
+        }
+        if let Ok(j) = load_journal_page(vault, &self.model.journal_page.date) {
+            self.model.journal_page = j;
+        }
+        if let Ok(t) = load_trail(vault, &self.model.trail.name) {
+            self.model.trail = t;
+        }
+    }
+    /// Reads the current trail again into the backlinks, once it's saved.
+    fn reload_trail_backlinks(&mut self) {
+        let page = PageRef::new(Folder::Trails, &self.model.trail.name);
+        self.model.backlinks.reload(&self.model.vault, &page);
+    }
+    /// Loads the note a link points to into the model, and returns the page
+    /// that displays it. If the note doesn't exist, the user is asked
+    /// whether to create it, and sent `back` if they don't.
+    fn open_link(&mut self, target: &str, back: CurrentPage) -> CurrentPage {
+        match load_note(&self.model.vault, target) {
+            Ok(n) => {
+                self.model.note = n;
+                CurrentPage::NoteView
+            }
+            Err(e) if e.is_not_found() => match self.view.select_create_note(target) {
+                true => {
+                    self.model.note = Note::from_template(&self.model.vault, target);
+                    CurrentPage::NoteView
+                }
+                false => back,
+            },
+            Err(e) => CurrentPage::UnexpectedError(
+                format!("Could not load the note {}.", target),
+                Some(e.to_string()),
+            ),
+        }
+    }
+    /// Loads the trail `name` into the model, and returns the page that
+    /// displays it. An empty trail file is opened as a new trail.
+    fn open_trail(&mut self, name: &str) -> CurrentPage {
+        match load_trail(&self.model.vault, name) {
+            Ok(t) => self.model.trail = t,
+            Err(TrailError::FileError(FileError::EmptyFileError(_))) => {
+                self.model.trail = Trail::from_template(&self.model.vault, name)
+            }
+            Err(e) => return trail_error_page(e),
+        }
+        CurrentPage::TrailView
+    }
+    /// Loads a page of any kind into the model, and returns the page that
+    /// displays it.
+    fn open_page(&mut self, page: &PageRef) -> CurrentPage {
+        let vault = &self.model.vault;
+        match page.folder {
+            Folder::Notes => match load_note(vault, &page.name) {
+                Ok(n) => {
+                    self.model.note = n;
+                    CurrentPage::NoteView
+                }
+                Err(e) => CurrentPage::UnexpectedError(
+                    format!("Could not load the note {}.", page.name),
+                    Some(e.to_string()),
+                ),
+            },
+            Folder::Journal => match load_journal_page(vault, &page.name) {
+                Ok(j) => {
+                    self.model.journal_page = j;
+                    match page.name == self.model.current_date {
+                        true => CurrentPage::JournalView,
+                        false => CurrentPage::JournalViewReadOnly,
+                    }
+                }
+                Err(e) => CurrentPage::UnexpectedError(
+                    format!("Could not load the journal page {}.", page.name),
+                    Some(e.to_string()),
+                ),
+            },
+            Folder::Trails => match load_trail(vault, &page.name) {
+                Ok(t) => {
+                    self.model.trail = t;
+                    CurrentPage::TrailView
+                }
+                Err(e) => CurrentPage::UnexpectedError(
+                    format!("Could not load the trail {}.", page.name),
+                    Some(e.to_string()),
+                ),
+            },
+        }
     }
 }
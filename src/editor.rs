@@ -0,0 +1,692 @@
+//! # `editor` module
+//!
+//!  This module hosts the text buffer behind the built-in text editor of the
+//!  terminal view. It knows nothing about the terminal: it stores the text as
+//!  a list of lines, moves the cursor around and lays the lines out in
+//!  soft-wrapped screen rows, so that the view only has to draw them.
+//!
+//!  Columns are counted in `char`s, while widths on screen are measured with
+//!  the Unicode display width of each character, so that wide characters
+//!  (e.g. CJK ideographs) take up two cells.
+//!
+//!  It also hosts the undo/redo [History] of an editing session.
+use unicode_width::UnicodeWidthChar;
+
+/// Number of spaces inserted when the user presses `Tab`.
+const TAB_WIDTH: usize = 4;
+
+/// Maximum number of steps kept in each stack of a [History].
+const HISTORY_LIMIT: usize = 200;
+
+/// ## Row
+///
+///  A single screen row of the soft-wrapped layout: the slice of line
+///  `line` going from char `start` (included) to char `end` (excluded).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Row {
+    pub line: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// ## Buffer
+///
+///  The text being edited, along with the cursor position and the first
+///  screen row currently visible in the viewport.
+pub struct Buffer {
+    lines: Vec<Vec<char>>,
+    line: usize,
+    col: usize,
+    scroll: usize,
+}
+
+fn char_width(c: char) -> usize {
+    c.width().unwrap_or(0)
+}
+
+/// Splits a single line into soft-wrapped rows no wider than `width` cells,
+/// breaking after the last space that fits when possible.
+fn wrap_line(line: &[char], width: usize) -> Vec<(usize, usize)> {
+    let width = width.max(2);
+    let mut rows = Vec::new();
+    let mut start = 0;
+
+    if line.is_empty() {
+        return vec![(0, 0)];
+    }
+
+    while start < line.len() {
+        let mut used = 0;
+        let mut end = start;
+        let mut last_break = None;
+
+        while end < line.len() {
+            let w = char_width(line[end]);
+            if used + w > width {
+                break;
+            }
+            used += w;
+            end += 1;
+            if line[end - 1] == ' ' {
+                last_break = Some(end);
+            }
+        }
+
+        if end == line.len() {
+            rows.push((start, end));
+            break;
+        }
+
+        let split = match last_break {
+            Some(b) if b > start => b,
+            _ => end.max(start + 1),
+        };
+        rows.push((start, split));
+        start = split;
+    }
+
+    rows
+}
+
+impl Buffer {
+    /// Creates a buffer holding `text`, with the cursor at its very end.
+    pub fn new(text: &str) -> Buffer {
+        let lines: Vec<Vec<char>> = text.split('\n').map(|l| l.chars().collect()).collect();
+        let line = lines.len() - 1;
+        let col = lines[line].len();
+
+        Buffer {
+            lines,
+            line,
+            col,
+            scroll: 0,
+        }
+    }
+    pub fn text(&self) -> String {
+        self.lines
+            .iter()
+            .map(|l| l.iter().collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+    /// Cursor position as `(line, column)`, both counted from zero.
+    pub fn cursor(&self) -> (usize, usize) {
+        (self.line, self.col)
+    }
+    pub fn line_count(&self) -> usize {
+        self.lines.len()
+    }
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            text: self.text(),
+            line: self.line,
+            col: self.col,
+        }
+    }
+    /// Replaces the content of the buffer with a snapshot, clamping the
+    /// cursor to the restored text.
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        let scroll = self.scroll;
+        *self = Buffer::new(&snapshot.text);
+        self.line = snapshot.line.min(self.lines.len() - 1);
+        self.col = snapshot.col.min(self.lines[self.line].len());
+        self.scroll = scroll;
+    }
+
+    // EDITING
+
+    pub fn insert(&mut self, c: char) {
+        match c {
+            '\n' => self.newline(),
+            '\t' => {
+                for _ in 0..TAB_WIDTH {
+                    self.insert(' ');
+                }
+            }
+            _ => {
+                self.lines[self.line].insert(self.col, c);
+                self.col += 1;
+            }
+        }
+    }
+    fn newline(&mut self) {
+        let rest = self.lines[self.line].split_off(self.col);
+        self.lines.insert(self.line + 1, rest);
+        self.line += 1;
+        self.col = 0;
+    }
+    /// Deletes the character before the cursor, joining the current line
+    /// with the previous one when the cursor is at the start of a line.
+    pub fn backspace(&mut self) {
+        if self.col > 0 {
+            self.col -= 1;
+            self.lines[self.line].remove(self.col);
+        } else if self.line > 0 {
+            let current = self.lines.remove(self.line);
+            self.line -= 1;
+            self.col = self.lines[self.line].len();
+            self.lines[self.line].extend(current);
+        }
+    }
+    /// Deletes the character under the cursor, joining the next line with
+    /// the current one when the cursor is at the end of a line.
+    pub fn delete(&mut self) {
+        if self.col < self.lines[self.line].len() {
+            self.lines[self.line].remove(self.col);
+        } else if self.line + 1 < self.lines.len() {
+            let next = self.lines.remove(self.line + 1);
+            self.lines[self.line].extend(next);
+        }
+    }
+
+    // MOVEMENT
+
+    pub fn left(&mut self) {
+        if self.col > 0 {
+            self.col -= 1;
+        } else if self.line > 0 {
+            self.line -= 1;
+            self.col = self.lines[self.line].len();
+        }
+    }
+    pub fn right(&mut self) {
+        if self.col < self.lines[self.line].len() {
+            self.col += 1;
+        } else if self.line + 1 < self.lines.len() {
+            self.line += 1;
+            self.col = 0;
+        }
+    }
+    pub fn home(&mut self) {
+        self.col = 0;
+    }
+    pub fn end(&mut self) {
+        self.col = self.lines[self.line].len();
+    }
+    /// Moves the cursor by `delta` screen rows, keeping it as close as
+    /// possible to its current column on screen.
+    pub fn move_rows(&mut self, delta: isize, width: usize) {
+        let rows = self.layout(width);
+        let (current, x) = self.cursor_on_screen(&rows);
+        let target = (current as isize + delta).clamp(0, rows.len() as isize - 1) as usize;
+        let row = rows[target];
+        let line = &self.lines[row.line];
+
+        let mut used = 0;
+        let mut col = row.start;
+        while col < row.end {
+            let w = char_width(line[col]);
+            if used + w > x {
+                break;
+            }
+            used += w;
+            col += 1;
+        }
+        // Stay on the target row when it was wrapped right after `col`.
+        if col == row.end && row.end < line.len() && col > row.start {
+            col -= 1;
+        }
+
+        self.line = row.line;
+        self.col = col;
+    }
+
+    // LAYOUT
+
+    /// Soft-wraps the whole buffer into screen rows of at most `width` cells.
+    pub fn layout(&self, width: usize) -> Vec<Row> {
+        self.lines
+            .iter()
+            .enumerate()
+            .flat_map(|(i, l)| {
+                wrap_line(l, width)
+                    .into_iter()
+                    .map(move |(start, end)| Row {
+                        line: i,
+                        start,
+                        end,
+                    })
+            })
+            .collect()
+    }
+    /// Index of the screen row holding the cursor, and the cursor's display
+    /// column inside of it.
+    pub fn cursor_on_screen(&self, rows: &[Row]) -> (usize, usize) {
+        let index = rows
+            .iter()
+            .enumerate()
+            .filter(|(_, r)| r.line == self.line && r.start <= self.col)
+            .map(|(i, _)| i)
+            .next_back()
+            .unwrap_or(0);
+        let row = rows[index];
+        let x = self.lines[row.line][row.start..self.col]
+            .iter()
+            .map(|c| char_width(*c))
+            .sum();
+
+        (index, x)
+    }
+    /// Scrolls the viewport so that the cursor is visible in a window of
+    /// `height` rows, and returns the index of the first visible row.
+    pub fn scroll_to_cursor(&mut self, rows: &[Row], height: usize) -> usize {
+        let height = height.max(1);
+        let (current, _) = self.cursor_on_screen(rows);
+        if current < self.scroll {
+            self.scroll = current;
+        } else if current >= self.scroll + height {
+            self.scroll = current + 1 - height;
+        }
+        self.scroll
+    }
+    /// The text of a screen row.
+    pub fn row_text(&self, row: &Row) -> String {
+        self.lines[row.line][row.start..row.end].iter().collect()
+    }
+}
+
+/// ## Snapshot
+///
+///  The state of a [Buffer] at a given point of the editing session.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snapshot {
+    text: String,
+    line: usize,
+    col: usize,
+}
+
+/// ## Edit
+///
+///  The kinds of edit recorded in a [History]. Consecutive edits of the same
+///  kind are coalesced into a single undo step, so that undoing a sentence
+///  doesn't take one keystroke per character.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Edit {
+    Typing,
+    Deletion,
+    Newline,
+}
+
+/// ## History
+///
+///  The undo and redo stacks of an editing session. The history only stores
+///  the states *before* each step: the current state always lives in the
+///  [Buffer].
+///
+///  A history can be turned into a string with [History::to_str] and read
+///  back with [History::parse], so that it can be kept on disk between
+///  sessions.
+#[derive(Default)]
+pub struct History {
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+    last: Option<Edit>,
+}
+
+impl History {
+    pub fn new() -> History {
+        History::default()
+    }
+    /// Records an edit that turned the `before` state into the current state
+    /// of `buffer`. Edits that didn't change the text are ignored.
+    pub fn commit(&mut self, before: Snapshot, edit: Edit, buffer: &Buffer) {
+        if before.text == buffer.text() {
+            return;
+        }
+        let coalesce = edit != Edit::Newline && self.last == Some(edit);
+        if !coalesce {
+            self.undo.push(before);
+            if self.undo.len() > HISTORY_LIMIT {
+                self.undo.remove(0);
+            }
+        }
+        self.redo.clear();
+        self.last = Some(edit);
+    }
+    /// Starts a new undo step at the next edit, e.g. after the cursor moved.
+    pub fn break_group(&mut self) {
+        self.last = None;
+    }
+    /// Reverts the last step. Returns `false` if there was nothing to undo.
+    pub fn undo(&mut self, buffer: &mut Buffer) -> bool {
+        match self.undo.pop() {
+            Some(snapshot) => {
+                self.redo.push(buffer.snapshot());
+                buffer.restore(&snapshot);
+                self.last = None;
+                true
+            }
+            None => false,
+        }
+    }
+    /// Re-applies the last undone step. Returns `false` if there was
+    /// nothing to redo.
+    pub fn redo(&mut self, buffer: &mut Buffer) -> bool {
+        match self.redo.pop() {
+            Some(snapshot) => {
+                self.undo.push(buffer.snapshot());
+                buffer.restore(&snapshot);
+                self.last = None;
+                true
+            }
+            None => false,
+        }
+    }
+    /// Serializes the history. Every snapshot is written as a header line
+    /// (`undo` or `redo`, cursor line, cursor column and length of the text
+    /// in bytes) followed by the text itself.
+    pub fn to_str(&self) -> String {
+        let mut buffer = String::new();
+        let stacks = [("undo", &self.undo), ("redo", &self.redo)];
+        for (name, stack) in stacks {
+            for s in stack.iter() {
+                buffer.push_str(&format!("{} {} {} {}\n", name, s.line, s.col, s.text.len()));
+                buffer.push_str(&s.text);
+                buffer.push('\n');
+            }
+        }
+        buffer
+    }
+    /// Reads back a history written by [History::to_str]. Parsing stops at
+    /// the first malformed entry, keeping the steps read until then.
+    pub fn parse(text: &str) -> History {
+        let mut history = History::new();
+        let mut rest = text;
+
+        while let Some((header, body)) = rest.split_once('\n') {
+            let fields: Vec<_> = header.split(' ').collect();
+            let (name, snapshot, len) = match fields[..] {
+                [name, line, col, len] => match (line.parse(), col.parse(), len.parse::<usize>()) {
+                    (Ok(line), Ok(col), Ok(len))
+                        if body.len() > len && body.is_char_boundary(len) =>
+                    {
+                        let text = String::from(&body[..len]);
+                        (name, Snapshot { text, line, col }, len)
+                    }
+                    _ => break,
+                },
+                _ => break,
+            };
+            match name {
+                "undo" => history.undo.push(snapshot),
+                "redo" => history.redo.push(snapshot),
+                _ => break,
+            }
+            rest = &body[len + 1..];
+        }
+
+        history
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The rows of `text` soft-wrapped at `width`.
+    fn rows(text: &str, width: usize) -> Vec<String> {
+        let buffer = Buffer::new(text);
+        buffer
+            .layout(width)
+            .iter()
+            .map(|r| buffer.row_text(r))
+            .collect()
+    }
+
+    #[test]
+    fn lines_wrap_after_the_last_space_that_fits() {
+        assert_eq!(rows("as we may think", 6), ["as we ", "may ", "think"]);
+        assert_eq!(rows("as we may think", 15), ["as we may think"]);
+        assert_eq!(rows("a memex", 3), ["a ", "mem", "ex"]);
+        // Narrower widths still fit a wide character on each row
+        assert_eq!(rows("memex", 0), ["me", "me", "x"]);
+    }
+
+    #[test]
+    fn every_line_starts_a_new_row() {
+        let buffer = Buffer::new("as we\n\nmay think");
+
+        let rows: Vec<_> = buffer
+            .layout(5)
+            .iter()
+            .map(|r| (r.line, r.start, r.end))
+            .collect();
+
+        assert_eq!(rows, [(0, 0, 5), (1, 0, 0), (2, 0, 4), (2, 4, 9)]);
+    }
+
+    #[test]
+    fn wide_characters_take_two_cells() {
+        assert_eq!(rows("漢字漢字", 5), ["漢字", "漢字"]);
+        assert_eq!(rows("a漢字", 4), ["a漢", "字"]);
+
+        let buffer = Buffer::new("漢字漢字");
+        assert_eq!(buffer.cursor_on_screen(&buffer.layout(5)), (1, 4));
+    }
+
+    #[test]
+    fn the_cursor_is_placed_on_its_row() {
+        let mut buffer = Buffer::new("as we may think");
+        let rows = buffer.layout(6);
+        assert_eq!(buffer.cursor_on_screen(&rows), (2, 5));
+
+        // At a wrapping point, the cursor starts the next row
+        for _ in 0..5 {
+            buffer.left();
+        }
+        assert_eq!(buffer.cursor(), (0, 10));
+        assert_eq!(buffer.cursor_on_screen(&rows), (2, 0));
+    }
+
+    #[test]
+    fn the_cursor_moves_across_wrapped_rows() {
+        let mut buffer = Buffer::new("as we may think");
+
+        buffer.move_rows(-1, 6);
+        // Stays on "may " rather than jumping to the start of "think"
+        assert_eq!(buffer.cursor(), (0, 9));
+        buffer.move_rows(-1, 6);
+        assert_eq!(buffer.cursor(), (0, 3));
+        buffer.move_rows(-5, 6);
+        assert_eq!(buffer.cursor(), (0, 3));
+        buffer.move_rows(10, 6);
+        assert_eq!(buffer.cursor(), (0, 13));
+    }
+
+    #[test]
+    fn the_cursor_keeps_its_column_on_screen() {
+        let mut buffer = Buffer::new("abcd\n漢字");
+
+        buffer.move_rows(-1, 10);
+        assert_eq!(buffer.cursor(), (0, 4));
+        buffer.left();
+        // Column 3 falls in the middle of 字, which the cursor stops before
+        buffer.move_rows(1, 10);
+        assert_eq!(buffer.cursor(), (1, 1));
+        buffer.move_rows(-1, 10);
+        assert_eq!(buffer.cursor(), (0, 2));
+    }
+
+    #[test]
+    fn editing_joins_and_splits_lines() {
+        let mut buffer = Buffer::new("memex");
+        buffer.home();
+        buffer.insert('\t');
+        assert_eq!(buffer.text(), "    memex");
+        buffer.right();
+        buffer.right();
+        buffer.insert('\n');
+        assert_eq!(
+            (buffer.text().as_str(), buffer.cursor()),
+            ("    me\nmex", (1, 0))
+        );
+
+        buffer.backspace();
+        assert_eq!(
+            (buffer.text().as_str(), buffer.cursor()),
+            ("    memex", (0, 6))
+        );
+        buffer.end();
+        buffer.insert('\n');
+        buffer.insert('!');
+        buffer.left();
+        buffer.left();
+        buffer.delete();
+        assert_eq!(
+            (buffer.text().as_str(), buffer.cursor()),
+            ("    memex!", (0, 9))
+        );
+        buffer.right();
+        buffer.right();
+        assert_eq!(buffer.cursor(), (0, 10));
+    }
+
+    #[test]
+    fn the_viewport_follows_the_cursor() {
+        let mut buffer = Buffer::new("1\n2\n3\n4\n5");
+        let rows = buffer.layout(10);
+
+        assert_eq!(buffer.scroll_to_cursor(&rows, 2), 3);
+        buffer.move_rows(-2, 10);
+        assert_eq!(buffer.scroll_to_cursor(&rows, 2), 2);
+        buffer.move_rows(1, 10);
+        assert_eq!(buffer.scroll_to_cursor(&rows, 2), 2);
+    }
+
+    /// Types `text` into the buffer, recording every keystroke.
+    fn type_text(buffer: &mut Buffer, history: &mut History, text: &str) {
+        for c in text.chars() {
+            let before = buffer.snapshot();
+            buffer.insert(c);
+            let edit = match c {
+                '\n' => Edit::Newline,
+                _ => Edit::Typing,
+            };
+            history.commit(before, edit, buffer);
+        }
+    }
+
+    /// Deletes `n` characters before the cursor, recording every keystroke.
+    fn erase(buffer: &mut Buffer, history: &mut History, n: usize) {
+        for _ in 0..n {
+            let before = buffer.snapshot();
+            buffer.backspace();
+            history.commit(before, Edit::Deletion, buffer);
+        }
+    }
+
+    #[test]
+    fn edits_of_the_same_kind_are_undone_together() {
+        let mut buffer = Buffer::new("");
+        let mut history = History::new();
+
+        type_text(&mut buffer, &mut history, "as we\nmay");
+        erase(&mut buffer, &mut history, 2);
+        type_text(&mut buffer, &mut history, "ight");
+
+        let mut states = vec![buffer.text()];
+        while history.undo(&mut buffer) {
+            states.push(buffer.text());
+        }
+        assert_eq!(
+            states,
+            [
+                "as we\nmight",
+                "as we\nm",
+                "as we\nmay",
+                "as we\n",
+                "as we",
+                ""
+            ]
+        );
+    }
+
+    #[test]
+    fn moving_the_cursor_starts_a_new_step() {
+        let mut buffer = Buffer::new("");
+        let mut history = History::new();
+
+        type_text(&mut buffer, &mut history, "memex");
+        buffer.home();
+        history.break_group();
+        type_text(&mut buffer, &mut history, "a ");
+
+        assert!(history.undo(&mut buffer));
+        assert_eq!((buffer.text().as_str(), buffer.cursor()), ("memex", (0, 0)));
+        assert!(history.undo(&mut buffer));
+        assert_eq!(buffer.text(), "");
+        assert!(!history.undo(&mut buffer));
+    }
+
+    #[test]
+    fn undone_steps_are_redone_until_the_next_edit() {
+        let mut buffer = Buffer::new("");
+        let mut history = History::new();
+        type_text(&mut buffer, &mut history, "as\nwe");
+
+        history.undo(&mut buffer);
+        history.undo(&mut buffer);
+        assert!(history.redo(&mut buffer));
+        assert_eq!((buffer.text().as_str(), buffer.cursor()), ("as\n", (1, 0)));
+
+        // Moving without editing keeps the redo stack
+        let before = buffer.snapshot();
+        buffer.left();
+        history.commit(before, Edit::Typing, &buffer);
+        assert!(history.redo(&mut buffer));
+        assert_eq!(buffer.text(), "as\nwe");
+
+        history.undo(&mut buffer);
+        type_text(&mut buffer, &mut history, "I");
+        assert!(!history.redo(&mut buffer));
+    }
+
+    #[test]
+    fn only_the_latest_steps_are_kept() {
+        let mut buffer = Buffer::new("");
+        let mut history = History::new();
+        type_text(&mut buffer, &mut history, &"\n".repeat(HISTORY_LIMIT + 10));
+
+        let mut steps = 0;
+        while history.undo(&mut buffer) {
+            steps += 1;
+        }
+        assert_eq!(steps, HISTORY_LIMIT);
+        assert_eq!(buffer.line_count(), 11);
+    }
+
+    #[test]
+    fn histories_are_read_back_from_their_text() {
+        let mut buffer = Buffer::new("");
+        let mut history = History::new();
+        type_text(&mut buffer, &mut history, "漢字\n\nundo 0 0 4");
+        history.break_group();
+        erase(&mut buffer, &mut history, 3);
+        history.undo(&mut buffer);
+
+        let text = history.to_str();
+        let mut parsed = History::parse(&text);
+
+        assert_eq!(parsed.to_str(), text);
+        assert!(text.starts_with("undo 0 0 0\n\nundo 0 2 6\n漢字\nundo 1 0 7\n漢字\n\n"));
+        let mut copy = Buffer::new(&buffer.text());
+        assert!(parsed.redo(&mut copy));
+        assert_eq!(copy.text(), "漢字\n\nundo 0 ");
+        while parsed.undo(&mut copy) {}
+        assert_eq!(copy.text(), "");
+    }
+
+    #[test]
+    fn malformed_histories_keep_the_steps_read_so_far() {
+        for text in [
+            "undo 0 0 0\n\nundo 1 1\nmemex\n",
+            "undo 0 0 0\n\nundo 0 0 9\nmemex\n",
+            "undo 0 0 0\n\nundo 0 0 2\n漢\n",
+            "undo 0 0 0\n\nlater 0 0 5\nmemex\n",
+        ] {
+            let history = History::parse(text);
+            assert_eq!(history.to_str(), "undo 0 0 0\n\n", "{:?}", text);
+        }
+        assert_eq!(History::parse("").to_str(), "");
+    }
+}
@@ -1,119 +1,481 @@
-use chrono::Datelike;
+//! # vannevar
+//!
+//!  The storage layer of vannevar, a memex made of notes, journal pages and
+//!  trails. Everything the interactive application does to a vault goes
+//!  through this crate, so that other tools (importers, bots, web
+//!  frontends, ...) can read and write the same vaults safely.
+//!
+//!  A [Vault] is a directory with a subfolder per kind of page ([Folder]):
+//!
+//!  - a [Note] is a piece of text, linking to other notes with the `[title]`
+//!    (or `[id]`) syntax;
+//!  - a [Journal] page collects the description of a day and the notes
+//!    created during it;
+//!  - a [Trail] is an ordered path through some notes, with a description of
+//!    every hop.
+//!
+//!  Every page is a plain text file with a [Header] (see [parse_document]).
+//!  Pages are read with [load_note], [load_journal_page] and [load_trail],
+//!  and written with their `save` method, which also keeps the search index
+//!  up to date. On top of that, the crate can [search] the vault, find the
+//!  [Backlinks] of a note, [rename_note]s (rewriting the links to them),
+//!  compile trails into documents ([export_trail]), [migrate] old vaults,
+//!  look for broken links ([check_vault]), list the days that have a
+//!  journal page ([journal_days]) and review a week or a month of them
+//!  ([Rollup]).
+//!
+//!  Failures are reported with a [FileError] (or a more specific error
+//!  built on it), telling which file couldn't be read or written and why,
+//!  down to the line and column of formatting errors.
+//!
+//!  The interactive application lives in the [controller], [editor] and
+//!  [view] modules. Its screens are drawn by whatever implements [View]:
+//!  the `termion` backend ([terminal]) is built with the `tui` feature,
+//!  which is enabled by default.
+//!
+//!  [View]: view::View
+//!
+//!  ```no_run
+//!  use vannevar::{load_note, Note, Vault};
+//!
+//!  # fn main() -> Result<(), vannevar::FileError> {
+//!  let vault = Vault::open(Vault::locate(None))?;
+//!  let mut note = Note::from_str("Memex", String::from("A device imagined by [Vannevar Bush]."));
+//!  note.save(&vault)?;
+//!
+//!  let note = load_note(&vault, "Memex")?;
+//!  assert_eq!(note.links, vec!["Vannevar Bush"]);
+//!  # Ok(())
+//!  # }
+//!  ```
+use chrono::{Datelike, NaiveDate, NaiveTime};
 use lazy_regex::regex;
+use rust_stemmers::{Algorithm, Stemmer};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::env;
+use std::error::Error;
+use std::fmt;
 use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
 
+pub mod controller;
+pub mod editor;
+#[cfg(feature = "tui")]
+pub mod terminal;
+pub mod view;
+
+/// ## Folder
+///
+///  The subfolders of a [Vault]. Every kind of page is stored in its own
+///  folder, so that notes, journal pages and trails never mix.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Folder {
+    Journal,
+    Notes,
+    Trails,
+}
+
+impl Folder {
+    /// Every folder, in the order they are created.
+    pub const ALL: [Folder; 3] = [Folder::Journal, Folder::Notes, Folder::Trails];
+
+    /// The kind of page stored in the folder: `note`, `journal` or `trail`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Folder::Journal => "journal",
+            Folder::Notes => "note",
+            Folder::Trails => "trail",
+        }
+    }
+    /// The folder storing a kind of page, as named by [Folder::kind].
+    pub fn from_kind(kind: &str) -> Option<Folder> {
+        Folder::ALL.into_iter().find(|f| f.kind() == kind)
+    }
+    /// The name of the folder on disk.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Folder::Journal => "journal",
+            Folder::Notes => "notes",
+            Folder::Trails => "trails",
+        }
+    }
+}
+
+/// Name of the hidden folder of the vault where vannevar keeps its own data
+/// (editing history, indexes, ...).
+const META_FOLDER: &str = ".vannevar";
+
+/// Name of the configuration file, stored in the root of the vault.
+const CONFIG_FILE: &str = "config";
+
+/// Name of the folder of the vault where compiled trails are written.
+const EXPORTS_FOLDER: &str = "exports";
+
+/// Name of the folder of the vault holding the templates of new pages.
+const TEMPLATES_FOLDER: &str = "templates";
+
+/// ## Vault
+///
+///  The vault is the root directory of the memex. Every loader and saver
+///  resolves its paths through it, so the application behaves the same no
+///  matter which directory it was launched from.
+///
+///  The root is chosen, in order of precedence, from the `--vault` command
+///  line flag, the `VANNEVAR_VAULT` environment variable, and the XDG data
+///  directory (`$XDG_DATA_HOME/vannevar`, or `~/.local/share/vannevar`).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Vault {
+    root: PathBuf,
+}
+
+impl Vault {
+    /// Opens the vault at `root`, creating the root and its subfolders if
+    /// they don't exist yet.
+    pub fn open(root: PathBuf) -> Result<Vault, FileError> {
+        let vault = Vault { root };
+        for folder in Folder::ALL {
+            let path = vault.folder(folder);
+            fs::create_dir_all(&path).map_err(|e| FileError::write(&path, e))?;
+        }
+        Ok(vault)
+    }
+    /// Picks the vault root from the command line flag (if any), the
+    /// environment or the XDG default.
+    pub fn locate(flag: Option<String>) -> PathBuf {
+        if let Some(path) = flag {
+            return PathBuf::from(path);
+        }
+        if let Some(path) = env::var_os("VANNEVAR_VAULT") {
+            return PathBuf::from(path);
+        }
+        let data_home = match env::var_os("XDG_DATA_HOME") {
+            Some(path) if !path.is_empty() => PathBuf::from(path),
+            _ => match env::var_os("HOME") {
+                Some(home) => Path::new(&home).join(".local").join("share"),
+                None => PathBuf::from("."),
+            },
+        };
+        data_home.join("vannevar")
+    }
+    /// Path of one of the page folders of the vault.
+    pub fn folder(&self, folder: Folder) -> PathBuf {
+        self.root.join(folder.name())
+    }
+    /// Path of the file of a page.
+    pub fn path(&self, folder: Folder, name: &str) -> PathBuf {
+        self.folder(folder).join(name)
+    }
+    /// Path of a file in a section of the hidden metadata folder, e.g.
+    /// `.vannevar/history/<note>`.
+    pub fn meta_path(&self, section: &str, name: &str) -> PathBuf {
+        self.root.join(META_FOLDER).join(section).join(name)
+    }
+    /// Path of a file in the `exports` folder of the vault.
+    pub fn export_path(&self, name: &str) -> PathBuf {
+        self.root.join(EXPORTS_FOLDER).join(name)
+    }
+    /// Path of the template of new pages of a kind, e.g. `templates/note`.
+    pub fn template_path(&self, folder: Folder) -> PathBuf {
+        self.root.join(TEMPLATES_FOLDER).join(folder.kind())
+    }
+}
+
+/// ## Config
+///
+///  User settings, read from the `config` file in the root of the vault. The
+///  file is made of `key = value` lines; empty lines and lines starting with
+///  `#` are ignored, and so are unknown keys. Missing keys keep their default
+///  value.
+///
+///  - `undo_history` (default `true`): keep the undo history of each note on
+///    disk, so that edits can be undone after reopening the note;
+///  - `editor` (default `builtin`): `external` opens notes and descriptions
+///    in `$VISUAL` or `$EDITOR` instead of the built-in editor.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Config {
+    pub undo_history: bool,
+    pub editor: EditorKind,
+}
+
+/// ## EditorKind
+///
+///  The editor used to write notes, journal descriptions and trail
+///  descriptions.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EditorKind {
+    Builtin,
+    External,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            undo_history: true,
+            editor: EditorKind::Builtin,
+        }
+    }
+}
+
+impl Config {
+    /// Reads the settings of a vault, falling back to the defaults when the
+    /// file doesn't exist.
+    pub fn load(vault: &Vault) -> Config {
+        match fs::read_to_string(vault.root.join(CONFIG_FILE)) {
+            Ok(text) => Config::parse(&text),
+            Err(_) => Config::default(),
+        }
+    }
+    /// Reads settings from the content of a `config` file.
+    pub fn parse(text: &str) -> Config {
+        let mut config = Config::default();
+
+        let settings = text
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .filter_map(|l| l.split_once('='))
+            .map(|(k, v)| (k.trim(), v.trim()));
+
+        for (key, value) in settings {
+            match key {
+                "undo_history" => config.undo_history = value != "false",
+                "editor" => {
+                    config.editor = match value {
+                        "external" => EditorKind::External,
+                        _ => EditorKind::Builtin,
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        config
+    }
+}
+
+/// ## Note
+///
+///  A note of the vault. Notes are stored in files named after their title
+///  when the title is a valid file name that isn't taken yet, and after their
+///  id otherwise: `file` is the name of that file, chosen the first time the
+///  note is saved.
 pub struct Note {
     pub title: String,
     pub text: String,
     pub links: Vec<String>,
+    pub header: Header,
+    pub file: String,
+}
+
+impl Default for Note {
+    fn default() -> Self {
+        Note::new()
+    }
 }
 
 impl Note {
-    fn new() -> Note {
+    /// An empty, unsaved note.
+    pub fn new() -> Note {
         Note {
             title: String::new(),
             text: String::new(),
             links: Vec::new(),
+            header: Header::new(Folder::Notes),
+            file: String::new(),
         }
     }
+    /// An unsaved note with the given title and text.
     pub fn from_str(name: &str, text: String) -> Note {
-        let links_matcher = regex!(r#"\[.+?\]"#m);
-
-        let matched_links: Vec<_> = links_matcher
-            .find_iter(text.as_str())
-            .map(|m| String::from(m.as_str()))
-            .map(|s| {
-                let mut q = s;
-                q.remove(0);
-                q.pop();
-                q
-            })
-            .collect();
+        let matched_links = links_in(&text);
 
         Note {
             title: String::from(name),
-            text: text,
+            text,
             links: matched_links,
+            header: Header::new(Folder::Notes),
+            file: String::new(),
         }
     }
+    /// An unsaved note with the given title, and the note template of the
+    /// vault as its text.
+    pub fn from_template(vault: &Vault, name: &str) -> Note {
+        let text = apply_template(
+            vault,
+            Folder::Notes,
+            name,
+            chrono::Local::now().date_naive(),
+        );
+        Note::from_str(name, text)
+    }
+    /// Updates `links` after `text` was changed.
     pub fn parse_links(&mut self) {
-        let link_matcher = regex!(r"\[(.+?)\]");
-        let matches: Vec<_> = link_matcher
-            .find_iter(&self.text)
-            .map(|s| String::from(s.as_str()))
-            .map(|s| {
-                let mut q = s;
-                q.remove(0);
-                q.pop();
-                q
-            })
-            .collect();
-        self.links = matches;
+        self.links = links_in(&self.text);
     }
-    pub fn save(&self) -> bool {
-        match fs::write(&self.title, &self.text) {
-            Ok(_) => true,
-            Err(_) => false,
+    /// Writes the note to the vault, choosing its file and id the first
+    /// time. Fails if the note has no title or can't be written.
+    pub fn save(&mut self, vault: &Vault) -> Result<(), FileError> {
+        if self.title.trim().is_empty() {
+            return Err(FileError::NameError(self.title.clone()));
         }
+        if self.file.is_empty() {
+            assign_id(vault, &mut self.header);
+            self.file = match is_file_name(&self.title)
+                && !vault.path(Folder::Notes, &self.title).exists()
+            {
+                true => self.title.clone(),
+                false => self.header.id.clone(),
+            };
+        }
+        let page = PageRef::new(Folder::Notes, &self.file);
+        save_page(vault, &page, &self.title, &mut self.header, &self.text)
     }
 }
 
+/// Returns the names of all the pages linked in `text` with the `[name]`
+/// syntax, in order of appearance.
+pub fn links_in(text: &str) -> Vec<String> {
+    let link_matcher = regex!(r"\[(.+?)\]");
+
+    link_matcher
+        .captures_iter(text)
+        .map(|c| String::from(&c[1]))
+        .collect()
+}
+
+/// ## Journal
+///
+///  The journal page of a day, named after its date (`yyyy-mm-dd`): a
+///  description of the day, and the list of the notes written during it.
 #[derive(Clone)]
 pub struct Journal {
     pub date: String,
     pub description: String,
     pub pages: Vec<String>,
+    pub header: Header,
+}
+
+impl Default for Journal {
+    fn default() -> Self {
+        Journal::new()
+    }
 }
 
 impl Journal {
+    /// An empty, unsaved journal page without a date.
     pub fn new() -> Journal {
         Journal {
             date: String::new(),
             description: String::new(),
             pages: Vec::new(),
+            header: Header::new(Folder::Journal),
         }
     }
-    pub fn todays_journal() -> Journal {
+    /// An unsaved journal page for the current day, filled in with the
+    /// journal template of the vault.
+    pub fn todays_journal(vault: &Vault) -> Journal {
+        Journal::from_template(vault, &todays_date())
+    }
+    /// An empty, unsaved journal page for `date` (`YYYY-MM-DD`), e.g. to
+    /// fill in a day that was skipped.
+    pub fn for_date(date: &str) -> Journal {
         Journal {
-            date: todays_date(),
+            date: String::from(date),
             description: String::new(),
             pages: Vec::new(),
+            header: Header::new(Folder::Journal),
+        }
+    }
+    /// An unsaved journal page for `date`, with the journal template of the
+    /// vault as its description.
+    pub fn from_template(vault: &Vault, date: &str) -> Journal {
+        let day = journal_date(date).unwrap_or_else(|| chrono::Local::now().date_naive());
+        Journal {
+            description: apply_template(vault, Folder::Journal, date, day),
+            ..Journal::for_date(date)
         }
     }
-    fn from_str(name: &str, text: &str) -> Result<Journal, FileError> {
-        let parts = text.split_once("---");
-        match parts {
-            Some((desc, list)) => {
-                let string_vec: Vec<_> = list
-                    .split('\n')
-                    .map(|s| {
-                        let start_bytes = s.find("[").unwrap_or(0);
-                        let end_bytes = s.find("]").unwrap_or(s.len());
-                        &s[start_bytes..end_bytes]
-                    })
-                    .map(|s| String::from(s))
-                    .collect();
+    /// Reads the body of a journal page: the description, a `---` line and
+    /// the list of the pages created during the day. The body starts at line
+    /// `first_line` of the file.
+    fn parse(name: &str, text: &str, first_line: usize) -> Result<Journal, ParseError> {
+        let (description, pages) = parse_journal_body(text, first_line)?;
 
-                Ok(Journal {
-                    date: String::from(name),
-                    description: String::from(desc),
-                    pages: string_vec,
-                })
-            }
-            None => Err(FileError::FormatError),
+        Ok(Journal {
+            date: String::from(name),
+            description,
+            pages,
+            header: Header::new(Folder::Journal),
+        })
+    }
+    /// Appends a line to the log of the day, stamped with `time`. Log lines
+    /// are kept in the description as `HH:MM text` lines.
+    pub fn log(&mut self, time: NaiveTime, line: &str) {
+        if !self.description.is_empty() && !self.description.ends_with('\n') {
+            self.description.push('\n');
         }
+        let line = line.split_whitespace().collect::<Vec<_>>().join(" ");
+        self.description
+            .push_str(&format!("{} {}", time.format(LOG_TIME_FORMAT), line));
     }
-    pub fn save(&self) -> bool {
-        let mut stringified_body = format!("{}\n---\n", self.description);
-        for l in &self.pages {
-            stringified_body.push_str(&format!("[{}]\n", l));
+    /// The log lines of the description, in chronological order. Lines
+    /// logged at the same minute keep the order they were written in.
+    pub fn entries(&self) -> Vec<(NaiveTime, &str)> {
+        let mut entries: Vec<_> = self.description.lines().filter_map(log_entry).collect();
+        entries.sort_by_key(|(time, _)| *time);
+        entries
+    }
+    /// The description without its log lines.
+    pub fn free_text(&self) -> String {
+        let lines: Vec<_> = self
+            .description
+            .lines()
+            .filter(|l| log_entry(l).is_none())
+            .collect();
+        String::from(lines.join("\n").trim())
+    }
+    /// The description with the log lines moved after the free text, in
+    /// chronological order.
+    pub fn sorted_description(&self) -> String {
+        let mut lines = vec![self.free_text()];
+        lines.retain(|l| !l.is_empty());
+        for (time, text) in self.entries() {
+            lines.push(format!("{} {}", time.format(LOG_TIME_FORMAT), text));
         }
-        let path = format!("journal/{}", &self.date);
-        match fs::write(path, stringified_body) {
-            Ok(_) => true,
-            Err(_) => false,
+        lines.join("\n")
+    }
+    /// The body of the page, as written to its file below the header.
+    pub fn to_str(&self) -> String {
+        let mut buffer = format!("{}\n---\n", self.description);
+        for l in &self.pages {
+            buffer.push_str(&format!("[{}]\n", l));
         }
+        buffer
     }
+    /// Writes the page to the vault.
+    pub fn save(&mut self, vault: &Vault) -> Result<(), FileError> {
+        let body = self.to_str();
+        save_page(
+            vault,
+            &PageRef::new(Folder::Journal, &self.date),
+            &self.date,
+            &mut self.header,
+            &body,
+        )
+    }
+}
+
+/// Format of the time stamp of the log lines of a journal page.
+const LOG_TIME_FORMAT: &str = "%H:%M";
+
+/// Reads a log line of a journal page (`HH:MM text`).
+fn log_entry(line: &str) -> Option<(NaiveTime, &str)> {
+    let (stamp, text) = line.split_once(' ')?;
+    if stamp.len() != 5 {
+        return None;
+    }
+    let time = NaiveTime::parse_from_str(stamp, LOG_TIME_FORMAT).ok()?;
+    Some((time, text.trim()))
 }
 
 fn todays_date() -> String {
@@ -124,75 +486,69 @@ fn todays_date() -> String {
     date
 }
 
+/// ## Trail
+///
+///  A named path through the notes of the vault. Every hop is made of the
+///  link to a note and a description of why the trail goes there.
 #[derive(Debug, PartialEq)]
 pub struct Trail {
     pub name: String,
     pub description: String,
     pub hops: Vec<(String, String)>,
+    pub header: Header,
+}
+
+impl Default for Trail {
+    fn default() -> Self {
+        Trail::new()
+    }
 }
 
 impl Trail {
+    /// An empty, unsaved trail without a name.
     pub fn new() -> Trail {
         Trail {
             name: String::new(),
             description: String::new(),
             hops: Vec::new(),
+            header: Header::new(Folder::Trails),
         }
     }
-    pub fn from_str(title: &str, trail: &str) -> Result<Trail, TrailError> {
-        // Stop execution if file is empty
-        match trail.len() {
-            0 => return Err(TrailError::FileError(FileError::EmptyFileError)),
-            _ => {}
+    /// An unsaved trail with the given name, and the trail template of the
+    /// vault as its description.
+    pub fn from_template(vault: &Vault, name: &str) -> Trail {
+        Trail {
+            name: String::from(name),
+            description: apply_template(
+                vault,
+                Folder::Trails,
+                name,
+                chrono::Local::now().date_naive(),
+            ),
+            ..Trail::new()
         }
+    }
+    /// Reads the body of a trail: the description, a `---` line and the
+    /// hops, each made of a `[link]` line, a `(description)` line and a `->`
+    /// line.
+    pub fn from_str(title: &str, trail: &str) -> Result<Trail, TrailError> {
+        Trail::parse(title, trail, 1)
+    }
+    /// Same as [Trail::from_str], for a body starting at line `first_line`
+    /// of its file.
+    fn parse(title: &str, trail: &str, first_line: usize) -> Result<Trail, TrailError> {
+        let (description, lines) =
+            split_body(trail, first_line).map_err(TrailError::DescriptionError)?;
+        let hops = parse_hops(&lines).map_err(TrailError::BodyFormatError)?;
 
-        // Precompiled regex for trail processing
-        let trail_matcher = regex!(r"(.*?)\n---");
-        let block_matcher = regex!(r#"\[(.*?)\]\n\((.*?)\)\n\->$"#m);
-        let link_matcher = regex!(r"\[(.+?)\]");
-        let description_matcher = regex!(r"\((.+?)\)");
-
-        // Read description. If wrongly formatted, return error.
-        let trail_description = match trail_matcher.find(trail) {
-            // Remove trailing ---. If wrongly formatted, return error.
-            Some(s) => {
-                let buf = s.as_str().strip_suffix("\n---");
-                match buf {
-                    Some(s) => String::from(s),
-                    None => return Err(TrailError::DescriptionError),
-                }
-            }
-            None => return Err(TrailError::DescriptionError),
-        };
-
-        // Capture and process
-        let s: Result<Vec<_>, TrailError> = block_matcher
-            .find_iter(trail)
-            .map(|m| m.as_str())
-            .map(|x| -> Result<(String, String), TrailError> {
-                let link = match link_matcher.find(x) {
-                    Some(s) => s.as_str(),
-                    None => return Err(TrailError::BodyFormatError),
-                };
-                let description = match description_matcher.find(x) {
-                    Some(s) => s.as_str(),
-                    None => return Err(TrailError::BodyFormatError),
-                };
-                let link = &link[1..link.len() - 1];
-                let description = &description[1..description.len() - 1];
-                Ok((String::from(link), String::from(description)))
-            })
-            .collect();
-
-        match s {
-            Ok(t) => Ok(Trail {
-                name: String::from(title),
-                description: trail_description,
-                hops: t,
-            }),
-            Err(e) => Err(e),
-        }
+        Ok(Trail {
+            name: String::from(title),
+            description,
+            hops,
+            header: Header::new(Folder::Trails),
+        })
     }
+    /// The body of the trail, as written to its file below the header.
     pub fn to_str(&self) -> String {
         let mut buffer = String::new();
         buffer.push_str(&format!("{}\n---\n", self.description));
@@ -207,30 +563,91 @@ impl Trail {
 
         buffer
     }
-    pub fn save(&self) -> bool {
-        let path = format!("trails/{}", &self.name);
-        match fs::write(path, &self.to_str()) {
-            Ok(_) => true,
-            Err(_) => false,
+    /// Inserts a hop at position `index` (or at the end, if `index` is past
+    /// the last hop). Newlines in the name and description are replaced by
+    /// spaces, since every hop is stored on exactly three lines.
+    pub fn insert_hop(&mut self, index: usize, name: &str, description: &str) {
+        let hop = (single_line(name), single_line(description));
+        self.hops.insert(index.min(self.hops.len()), hop);
+    }
+    /// Removes the hop at `index`, returning it.
+    pub fn remove_hop(&mut self, index: usize) -> Option<(String, String)> {
+        match index < self.hops.len() {
+            true => Some(self.hops.remove(index)),
+            false => None,
+        }
+    }
+    /// Moves the hop at `index` one position up (towards the start of the
+    /// trail) or down. Returns the new position of the hop, or `None` if it
+    /// couldn't be moved.
+    pub fn move_hop(&mut self, index: usize, up: bool) -> Option<usize> {
+        let target = match up {
+            true => index.checked_sub(1)?,
+            false => index + 1,
+        };
+        if index >= self.hops.len() || target >= self.hops.len() {
+            return None;
+        }
+        self.hops.swap(index, target);
+        Some(target)
+    }
+    /// Replaces the description of the hop at `index`. Returns `false` if
+    /// there's no such hop.
+    pub fn set_hop_description(&mut self, index: usize, description: &str) -> bool {
+        match self.hops.get_mut(index) {
+            Some(hop) => {
+                hop.1 = single_line(description);
+                true
+            }
+            None => false,
+        }
+    }
+    /// Writes the trail to the vault. Fails if the trail has no name or
+    /// can't be written.
+    pub fn save(&mut self, vault: &Vault) -> Result<(), FileError> {
+        if !is_file_name(&self.name) {
+            return Err(FileError::NameError(self.name.clone()));
         }
+        let body = self.to_str();
+        save_page(
+            vault,
+            &PageRef::new(Folder::Trails, &self.name),
+            &self.name,
+            &mut self.header,
+            &body,
+        )
     }
 }
 
+fn single_line(text: &str) -> String {
+    text.replace(['\r', '\n'], " ")
+}
+
+/// ## Model
+///
+///  The state of an interactive session: the vault, its settings, the
+///  note, journal page and trail currently open, and the [Backlinks] of the
+///  vault, which the controller reloads page by page as it saves them.
 pub struct Model {
+    pub vault: Vault,
+    pub config: Config,
     pub current_date: String,
     pub note: Note,
     pub journal_page: Journal,
     pub trail: Trail,
+    pub backlinks: Backlinks,
 }
 
 impl Model {
-    pub fn new() -> Model {
-        let j = match load_journal_page(todays_date().as_str()) {
-            Ok(r) => r,
-            Err(_) => Journal::new(),
-        };
+    /// Starts a session on `vault`, with today's journal page loaded (if it
+    /// exists).
+    pub fn new(vault: Vault) -> Model {
+        let j = load_journal_page(&vault, todays_date().as_str()).unwrap_or_default();
 
         Model {
+            config: Config::load(&vault),
+            backlinks: Backlinks::build(&vault),
+            vault,
             current_date: todays_date(),
             note: Note::new(),
             journal_page: j,
@@ -239,81 +656,2956 @@ impl Model {
     }
 }
 
-#[derive(Debug, PartialEq)]
+/// ## FileError
+///
+///  Why a file couldn't be read or written:
+///
+///  - `ReadError` and `WriteError`: the I/O operation on the file at the
+///    given path failed, with the given kind of error;
+///  - `EmptyFileError`: the file at the given path is empty;
+///  - `FormatError`: the content of the file isn't a valid page, for the
+///    reason and at the position given by the [ParseError];
+///  - `NameError`: a page can't be saved under the given name (e.g. a note
+///    without a title).
+#[allow(clippy::enum_variant_names)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum FileError {
-    ReadError,
-    EmptyFileError,
-    FormatError,
+    ReadError(PathBuf, io::ErrorKind),
+    WriteError(PathBuf, io::ErrorKind),
+    EmptyFileError(PathBuf),
+    FormatError(PathBuf, ParseError),
+    NameError(String),
 }
 
-#[derive(Debug, PartialEq)]
+impl FileError {
+    fn read(path: &Path, e: io::Error) -> FileError {
+        FileError::ReadError(path.to_path_buf(), e.kind())
+    }
+    fn write(path: &Path, e: io::Error) -> FileError {
+        FileError::WriteError(path.to_path_buf(), e.kind())
+    }
+    /// Tells whether the file doesn't exist, e.g. a page that was never
+    /// saved.
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, FileError::ReadError(_, io::ErrorKind::NotFound))
+    }
+}
+
+impl fmt::Display for FileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FileError::ReadError(path, kind) => {
+                write!(f, "could not read {}: {}", path.display(), kind)
+            }
+            FileError::WriteError(path, kind) => {
+                write!(f, "could not write {}: {}", path.display(), kind)
+            }
+            FileError::EmptyFileError(path) => write!(f, "{} is empty", path.display()),
+            FileError::FormatError(path, e) => write!(
+                f,
+                "{}:{}:{}: {}",
+                path.display(),
+                e.line,
+                e.column,
+                e.message
+            ),
+            FileError::NameError(name) => write!(f, "\"{}\" is not a valid name for a page", name),
+        }
+    }
+}
+
+impl Error for FileError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            FileError::FormatError(_, e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// ## TrailError
+///
+///  Why a trail couldn't be loaded: its description or its hops are
+///  formatted incorrectly (at the position given by the [ParseError]), or
+///  its file couldn't be read.
+#[allow(clippy::enum_variant_names)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum TrailError {
-    DescriptionError,
+    DescriptionError(ParseError),
     FileError(FileError),
-    BodyFormatError,
+    BodyFormatError(ParseError),
 }
 
-// SINGLE PAGE LOADERS
+impl fmt::Display for TrailError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TrailError::DescriptionError(e) => write!(
+                f,
+                "the description of the trail is formatted incorrectly: {}",
+                e
+            ),
+            TrailError::FileError(e) => write!(f, "{}", e),
+            TrailError::BodyFormatError(e) => {
+                write!(f, "the hops of the trail are formatted incorrectly: {}", e)
+            }
+        }
+    }
+}
 
-pub fn load_note(path: &str) -> Result<Note, FileError> {
-    let file: Vec<u8> = match fs::read(path) {
-        Ok(f) => f,
-        Err(_) => return Err(FileError::ReadError),
-    };
+impl Error for TrailError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            TrailError::DescriptionError(e) | TrailError::BodyFormatError(e) => Some(e),
+            TrailError::FileError(e) => Some(e),
+        }
+    }
+}
+
+/// ## RenameError
+///
+///  Why a note couldn't be renamed by [rename_note]. Nothing is changed
+///  when a rename fails.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RenameError {
+    InvalidTitle,
+    NotFound,
+    AlreadyExists,
+    FileError(FileError),
+}
+
+impl fmt::Display for RenameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RenameError::InvalidTitle => write!(f, "the new title is not valid"),
+            RenameError::NotFound => write!(f, "the note doesn't exist"),
+            RenameError::AlreadyExists => write!(f, "a note with the new title already exists"),
+            RenameError::FileError(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for RenameError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RenameError::FileError(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// ## PageRef
+///
+///  A reference to a page stored in the vault: the folder it lives in tells
+///  whether it's a note, a journal page or a trail.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PageRef {
+    pub folder: Folder,
+    pub name: String,
+}
+
+impl PageRef {
+    /// A reference to the page stored in `folder` under the file `name`.
+    pub fn new(folder: Folder, name: &str) -> PageRef {
+        PageRef {
+            folder,
+            name: String::from(name),
+        }
+    }
+    /// The kind of page: `note`, `journal` or `trail`.
+    pub fn kind(&self) -> &'static str {
+        self.folder.kind()
+    }
+    /// A short, human readable description of the page, e.g. `trail: Memex`.
+    pub fn label(&self) -> String {
+        format!("{}: {}", self.kind(), self.name)
+    }
+}
+
+/// Lists every page of the vault: notes first, then trails, then journal
+/// pages from the most recent one.
+pub fn all_pages(vault: &Vault) -> Vec<PageRef> {
+    let mut pages = Vec::new();
+    for folder in [Folder::Notes, Folder::Trails, Folder::Journal] {
+        let names = list_files(vault, folder).unwrap_or_default();
+        pages.extend(names.iter().map(|n| PageRef::new(folder, n)));
+    }
+    pages.sort_by(page_order);
+    pages
+}
 
-    let file_string = match String::from_utf8(file) {
-        Ok(f) => f,
-        Err(_) => return Err(FileError::FormatError),
+/// The order of [all_pages]: notes first, then trails, then journal pages
+/// from the most recent one.
+fn page_order(a: &PageRef, b: &PageRef) -> std::cmp::Ordering {
+    let rank = |f: Folder| match f {
+        Folder::Notes => 0,
+        Folder::Trails => 1,
+        Folder::Journal => 2,
     };
+    rank(a.folder)
+        .cmp(&rank(b.folder))
+        .then_with(|| match a.folder {
+            Folder::Journal => b.name.cmp(&a.name),
+            _ => a.name.cmp(&b.name),
+        })
+}
+
+// DOCUMENT FORMAT
+
+/// Version of the document format written by this release. Files without a
+/// header are version 0, and are upgraded by [migrate].
+pub const FORMAT_VERSION: u32 = 1;
+
+/// The keys of a document header, in the order they are written.
+const HEADER_KEYS: [&str; 7] = [
+    "version", "id", "title", "type", "created", "modified", "tags",
+];
+
+/// Format of the dates in a document header.
+const TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+/// Format of the ids of the pages: the creation time, zettelkasten style.
+const ID_FORMAT: &str = "%Y%m%d%H%M%S";
+
+/// ## ParseError
+///
+///  An error found while reading a document, with the position (1-based
+///  line and column) it was found at.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    fn new(line: usize, column: usize, message: &str) -> ParseError {
+        ParseError {
+            line,
+            column,
+            message: String::from(message),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+impl Error for ParseError {}
+
+/// ## Header
+///
+///  The metadata stored at the top of every page, between two `---` lines:
+///
+///  ```text
+///  ---
+///  version: 1
+///  id: 20240311093012
+///  title: Memex
+///  type: note
+///  created: 2024-03-11T09:30:12
+///  modified: 2024-03-12T18:02:45
+///  tags: bush, hypertext
+///  ---
+///  ```
+///
+///  The id and the creation date are given to a page the first time it is
+///  saved. The title and the type always follow the file the page is saved
+///  to.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Header {
+    pub id: String,
+    pub title: String,
+    pub kind: Folder,
+    pub created: String,
+    pub modified: String,
+    pub tags: Vec<String>,
+}
+
+impl Header {
+    /// An empty header for a page of the given kind, not saved yet.
+    pub fn new(kind: Folder) -> Header {
+        Header {
+            id: String::new(),
+            title: String::new(),
+            kind,
+            created: String::new(),
+            modified: String::new(),
+            tags: Vec::new(),
+        }
+    }
+    /// The header as written at the top of a file, `---` lines included.
+    pub fn to_str(&self) -> String {
+        let fields = [
+            ("version", FORMAT_VERSION.to_string()),
+            ("id", self.id.clone()),
+            ("title", self.title.clone()),
+            ("type", String::from(self.kind.kind())),
+            ("created", self.created.clone()),
+            ("modified", self.modified.clone()),
+            ("tags", self.tags.join(", ")),
+        ];
+        let mut buffer = String::from("---\n");
+        for (key, value) in fields {
+            match value.is_empty() {
+                true => buffer.push_str(&format!("{}:\n", key)),
+                false => buffer.push_str(&format!("{}: {}\n", key, value)),
+            }
+        }
+        buffer.push_str("---\n");
+        buffer
+    }
+}
+
+/// ## Document
+///
+///  A page split into its [Header] and its body. `body_line` is the line of
+///  the file the body starts at, so that errors found in the body can be
+///  located in the file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Document {
+    pub header: Header,
+    pub body: String,
+    pub body_line: usize,
+}
+
+/// Tells whether `text` starts with a versioned header, i.e. whether it was
+/// written in the current document format rather than in version 0.
+pub fn has_header(text: &str) -> bool {
+    text.starts_with("---\nversion:")
+}
 
-    Ok(Note::from_str(path, file_string))
+/// Returns the body of a page, without its header (if any).
+pub fn body_of(text: &str) -> String {
+    match has_header(text) {
+        true => match parse_document(text) {
+            Ok(doc) => doc.body,
+            Err(_) => String::from(text),
+        },
+        false => String::from(text),
+    }
 }
 
-pub fn load_journal_page(path: &str) -> Result<Journal, FileError> {
-    let file: Vec<u8> = match fs::read(path) {
-        Ok(f) => f,
-        Err(_) => return Err(FileError::ReadError),
+/// Splits a page into its header and its body, checking every field of the
+/// header.
+pub fn parse_document(text: &str) -> Result<Document, ParseError> {
+    let mut lines = text.split('\n').peekable();
+    if lines.next() != Some("---") {
+        return Err(ParseError::new(
+            1,
+            1,
+            "expected `---` at the start of the header",
+        ));
+    }
+
+    let mut fields: HashMap<&str, (usize, usize, &str)> = HashMap::new();
+    let mut line_number = 1;
+    let mut offset = 4;
+
+    loop {
+        line_number += 1;
+        let line = match lines.next() {
+            Some(l) if !l.is_empty() || lines.peek().is_some() => l,
+            _ => {
+                return Err(ParseError::new(
+                    line_number - 1,
+                    1,
+                    "the header is never closed by `---`",
+                ))
+            }
+        };
+        offset += line.len() + 1;
+        if line == "---" {
+            break;
+        }
+
+        let (key, value) = match line.split_once(':') {
+            Some(kv) => kv,
+            None => {
+                return Err(ParseError::new(
+                    line_number,
+                    line.chars().count() + 1,
+                    "expected `key: value`",
+                ))
+            }
+        };
+        let key = key.trim();
+        if !HEADER_KEYS.contains(&key) {
+            return Err(ParseError::new(
+                line_number,
+                1,
+                &format!("unknown key `{}`", key),
+            ));
+        }
+        if fields.contains_key(key) {
+            return Err(ParseError::new(
+                line_number,
+                1,
+                &format!("duplicate key `{}`", key),
+            ));
+        }
+        let value_start = line.len() - value.trim_start().len();
+        let column = line[..value_start].chars().count() + 1;
+        fields.insert(key, (line_number, column, value.trim()));
+    }
+
+    let closing_line = line_number;
+    let mut get = |key: &str, required: bool| -> Result<(usize, usize, &str), ParseError> {
+        match fields.remove(key) {
+            Some(f) => Ok(f),
+            None if required => Err(ParseError::new(
+                closing_line,
+                1,
+                &format!("missing key `{}`", key),
+            )),
+            None => Ok((closing_line, 1, "")),
+        }
     };
 
-    let file_string = match String::from_utf8(file) {
-        Ok(f) => f,
-        Err(_) => return Err(FileError::FormatError),
+    let (line, column, version) = get("version", true)?;
+    if version.parse::<u32>().ok() != Some(FORMAT_VERSION) {
+        return Err(ParseError::new(
+            line,
+            column,
+            &format!("unsupported version `{}`", version),
+        ));
+    }
+    let (line, column, id) = get("id", true)?;
+    if id.is_empty() {
+        return Err(ParseError::new(line, column, "the id is empty"));
+    }
+    let (line, column, title) = get("title", true)?;
+    if title.is_empty() {
+        return Err(ParseError::new(line, column, "the title is empty"));
+    }
+    let (line, column, kind) = get("type", true)?;
+    let kind = match Folder::from_kind(kind) {
+        Some(k) => k,
+        None => {
+            return Err(ParseError::new(
+                line,
+                column,
+                "the type must be `note`, `journal` or `trail`",
+            ))
+        }
     };
+    let mut dates = Vec::new();
+    for key in ["created", "modified"] {
+        let (line, column, date) = get(key, false)?;
+        if !date.is_empty() && chrono::NaiveDateTime::parse_from_str(date, TIME_FORMAT).is_err() {
+            return Err(ParseError::new(
+                line,
+                column,
+                "expected a date like `2024-03-11T09:30:12`",
+            ));
+        }
+        dates.push(String::from(date));
+    }
+    let (_, _, tags) = get("tags", false)?;
 
-    Journal::from_str(path, &file_string)
+    Ok(Document {
+        header: Header {
+            id: String::from(id),
+            title: String::from(title),
+            kind,
+            created: dates.remove(0),
+            modified: dates.remove(0),
+            tags: tags
+                .split(',')
+                .map(|t| String::from(t.trim()))
+                .filter(|t| !t.is_empty())
+                .collect(),
+        },
+        body: String::from(text.get(offset.min(text.len())..).unwrap_or_default()),
+        body_line: closing_line + 1,
+    })
 }
 
-// ADD EMPTY FILE ERROR TYPE
-pub fn list_files(path: &str) -> Result<Vec<String>, FileError> {
-    let files_list = match fs::read_dir(path) {
-        Err(e) => return Err(FileError::ReadError),
-        Ok(dir) => dir,
+/// Lines of a file, with their (1-based) line numbers.
+type NumberedLines<'a> = Vec<(usize, &'a str)>;
+
+/// Splits the body of a journal page or of a trail at its last `---` line,
+/// returning the description above it and the numbered lines below it.
+/// `first_line` is the line of the file the body starts at.
+fn split_body(body: &str, first_line: usize) -> Result<(String, NumberedLines<'_>), ParseError> {
+    let lines: Vec<&str> = body.split('\n').collect();
+    let separator = match lines.iter().rposition(|l| *l == "---") {
+        Some(i) => i,
+        None => {
+            return Err(ParseError::new(
+                first_line + lines.len() - 1,
+                1,
+                "missing the `---` line after the description",
+            ))
+        }
     };
+    let rest = lines[separator + 1..]
+        .iter()
+        .enumerate()
+        .map(|(i, l)| (first_line + separator + 1 + i, *l))
+        .collect();
 
-    let file_strings: Result<Vec<_>, FileError> = files_list
-        .map(|f| match f {
-            Ok(s) => match s.file_name().to_str() {
-                Some(str) => Ok(String::from(str)),
-                None => Err(FileError::ReadError),
-            },
-            Err(_) => Err(FileError::ReadError),
-        })
+    Ok((lines[..separator].join("\n"), rest))
+}
+
+/// Reads a `[link]` line, returning the linked name.
+fn parse_link_line(number: usize, line: &str) -> Result<String, ParseError> {
+    match line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+        Some(name) if !name.is_empty() => Ok(String::from(name)),
+        _ => Err(ParseError::new(number, 1, "expected a link like `[title]`")),
+    }
+}
+
+fn parse_journal_body(body: &str, first_line: usize) -> Result<(String, Vec<String>), ParseError> {
+    let (description, lines) = split_body(body, first_line)?;
+    let pages: Result<Vec<_>, ParseError> = lines
+        .iter()
+        .filter(|(_, l)| !l.trim().is_empty())
+        .map(|(n, l)| parse_link_line(*n, l))
         .collect();
 
-    file_strings
+    Ok((description, pages?))
 }
 
-// FIX ERROR HANDLING
-pub fn load_trail(path: &str) -> Result<Trail, TrailError> {
-    let file: Vec<u8> = match fs::read(path) {
-        Ok(f) => f,
-        Err(_) => return Err(TrailError::FileError(FileError::ReadError)),
-    };
+fn parse_hops(lines: &[(usize, &str)]) -> Result<Vec<(String, String)>, ParseError> {
+    let mut hops = Vec::new();
+    let mut lines = lines.iter().skip_while(|(_, l)| l.is_empty()).peekable();
 
-    let file_string = match String::from_utf8(file) {
-        Ok(f) => f,
-        Err(_) => return Err(TrailError::FileError(FileError::FormatError)),
-    };
+    while let Some((n, line)) = lines.next() {
+        let name = parse_link_line(*n, line)?;
+        let description = match lines.next() {
+            Some((n, l)) => match l.strip_prefix('(').and_then(|l| l.strip_suffix(')')) {
+                Some(d) => String::from(d),
+                None => {
+                    return Err(ParseError::new(
+                        *n,
+                        1,
+                        "expected a description like `(text)`",
+                    ))
+                }
+            },
+            None => {
+                return Err(ParseError::new(
+                    *n,
+                    line.chars().count() + 1,
+                    "the hop has no description",
+                ))
+            }
+        };
+        match lines.next() {
+            Some((_, "->")) => {}
+            Some((n, _)) => {
+                return Err(ParseError::new(
+                    *n,
+                    1,
+                    "expected `->` at the end of the hop",
+                ))
+            }
+            None => return Err(ParseError::new(*n + 1, 1, "the hop is not closed by `->`")),
+        }
+        hops.push((name, description));
+        while lines.next_if(|(_, l)| l.is_empty()).is_some() {}
+    }
+
+    Ok(hops)
+}
+
+fn parse_trail_body(
+    body: &str,
+    first_line: usize,
+) -> Result<(String, Vec<(String, String)>), ParseError> {
+    let (description, lines) = split_body(body, first_line)?;
+    Ok((description, parse_hops(&lines)?))
+}
+
+/// Reads a page of the vault: pages written in the current format are split
+/// into header and body, while version 0 pages are all body.
+fn read_document(vault: &Vault, folder: Folder, name: &str) -> Result<Document, FileError> {
+    let path = vault.path(folder, name);
+    let text = read_text(&path)?;
+
+    match has_header(&text) {
+        true => match parse_document(&text) {
+            Ok(doc) if doc.header.kind == folder => Ok(doc),
+            Ok(doc) => {
+                let message = format!(
+                    "expected a {}, found a {}",
+                    folder.kind(),
+                    doc.header.kind.kind()
+                );
+                Err(FileError::FormatError(
+                    path,
+                    ParseError::new(2, 1, &message),
+                ))
+            }
+            Err(e) => Err(FileError::FormatError(path, e)),
+        },
+        false => Ok(Document {
+            header: Header::new(folder),
+            body: text,
+            body_line: 1,
+        }),
+    }
+}
 
-    Trail::from_str(path, file_string.as_str())
+/// Reads a file as text. A file that isn't valid UTF-8 is reported as a
+/// formatting error, at the first byte that can't be read.
+fn read_text(path: &Path) -> Result<String, FileError> {
+    let file: Vec<u8> = fs::read(path).map_err(|e| FileError::read(path, e))?;
+
+    String::from_utf8(file).map_err(|e| {
+        let valid = &e.as_bytes()[..e.utf8_error().valid_up_to()];
+        let line = valid.iter().filter(|b| **b == b'\n').count() + 1;
+        let start = valid.iter().rposition(|b| *b == b'\n').map_or(0, |i| i + 1);
+        let column = String::from_utf8_lossy(&valid[start..]).chars().count() + 1;
+        let error = ParseError::new(line, column, "the file is not valid UTF-8 text");
+        FileError::FormatError(path.to_path_buf(), error)
+    })
+}
+
+fn timestamp(time: chrono::NaiveDateTime) -> String {
+    time.format(TIME_FORMAT).to_string()
+}
+
+/// The ids of every page of the vault, as written in the files: only the
+/// headers are read.
+fn all_ids(vault: &Vault) -> HashSet<String> {
+    let mut ids = HashSet::new();
+    for folder in Folder::ALL {
+        for name in list_files(vault, folder).unwrap_or_default() {
+            if let Some(header) = read_header(&vault.path(folder, &name)) {
+                ids.insert(header.id);
+            }
+        }
+    }
+    ids
+}
+
+/// Reads the header of a page, stopping at its closing `---` line. Returns
+/// `None` for version 0 pages and malformed headers.
+fn read_header(path: &Path) -> Option<Header> {
+    let file = fs::File::open(path).ok()?;
+    let mut text = String::new();
+    for line in io::BufReader::new(file).lines().take(HEADER_KEYS.len() + 2) {
+        let line = line.ok()?;
+        text.push_str(&line);
+        text.push('\n');
+        if line == "---" && text.len() > 4 {
+            return parse_document(&text).ok().map(|doc| doc.header);
+        }
+    }
+    None
+}
+
+/// Makes a new id from `time`, moving it forward one second at a time until
+/// it doesn't clash with any of the `taken` ids.
+fn new_id(taken: &HashSet<String>, time: chrono::NaiveDateTime) -> String {
+    let mut time = time;
+    loop {
+        let id = time.format(ID_FORMAT).to_string();
+        if !taken.contains(&id) {
+            return id;
+        }
+        time += chrono::Duration::seconds(1);
+    }
+}
+
+/// Gives a new page an id, unique across the vault.
+fn assign_id(vault: &Vault, header: &mut Header) {
+    if header.id.is_empty() {
+        header.id = new_id(&all_ids(vault), chrono::Local::now().naive_local());
+    }
+}
+
+/// Tells whether a note titled `title` can be stored in a file of the same
+/// name.
+pub fn is_file_name(title: &str) -> bool {
+    !title.is_empty() && !title.contains(['/', '\0']) && !title.starts_with('.')
+}
+
+/// Writes a page with its header, unless neither the header nor the body
+/// changed since the page was last written, so that the modification date
+/// only moves when the page does. A new page is given an id and a creation
+/// date, and a version 0 page is backed up to `.vannevar/backup` before
+/// being rewritten in the current format, as [migrate] does. A file that
+/// can't be read, or isn't valid UTF-8, is left as it is and the error
+/// returned.
+fn save_page(
+    vault: &Vault,
+    page: &PageRef,
+    title: &str,
+    header: &mut Header,
+    body: &str,
+) -> Result<(), FileError> {
+    let now = chrono::Local::now().naive_local();
+    let path = vault.path(page.folder, &page.name);
+
+    header.title = String::from(title);
+    header.kind = page.folder;
+
+    // A version 0 page is upgraded the way [migrate] does it: the original
+    // file is backed up first, and the page is dated from its last
+    // modification rather than from now. A file that can't be read is never
+    // written over.
+    let existing = match read_text(&path) {
+        Ok(text) => Some(text),
+        Err(e) if e.is_not_found() => None,
+        Err(e) => return Err(e),
+    };
+    if existing.as_deref().is_some_and(|t| !has_header(t)) {
+        let backup = vault.meta_path("backup", &now.format(ID_FORMAT).to_string());
+        back_up_page(vault, &backup, page)?;
+        let time = modified_time(&path, now);
+        if header.id.is_empty() {
+            header.id = new_id(&all_ids(vault), time);
+        }
+        if header.created.is_empty() {
+            header.created = timestamp(time);
+        }
+    }
+    assign_id(vault, header);
+    if header.created.is_empty() {
+        header.created = timestamp(now);
+    }
+
+    if let Some(Ok(doc)) = existing.as_deref().map(parse_document) {
+        let unchanged = Header {
+            modified: doc.header.modified.clone(),
+            ..header.clone()
+        };
+        if doc.body == body && doc.header == unchanged {
+            *header = doc.header;
+            return Ok(());
+        }
+    }
+
+    header.modified = timestamp(now);
+    fs::write(&path, header.to_str() + body).map_err(|e| FileError::write(&path, e))?;
+    // The page was saved even if the search index couldn't be updated
+    let _ = update_index(vault, &index_log_entry(page, Some(body)));
+    Ok(())
+}
+
+// SINGLE PAGE LOADERS
+
+/// Loads the note a link points to: `target` is either the id or the title
+/// of the note (see [Resolver]).
+pub fn load_note(vault: &Vault, target: &str) -> Result<Note, FileError> {
+    // Most notes are stored in a file named after their title: only look
+    // through the whole vault when that's not the case.
+    let (file, doc) = match read_document(vault, Folder::Notes, target) {
+        Ok(doc)
+            if doc.header.title.is_empty()
+                || doc.header.title == target
+                || doc.header.id == target =>
+        {
+            (String::from(target), doc)
+        }
+        Err(e @ FileError::FormatError(..)) => return Err(e),
+        _ => match Resolver::build(vault).resolve(target) {
+            Some(file) => (
+                String::from(file),
+                read_document(vault, Folder::Notes, file)?,
+            ),
+            None => {
+                let path = vault.path(Folder::Notes, target);
+                return Err(FileError::ReadError(path, io::ErrorKind::NotFound));
+            }
+        },
+    };
+
+    let mut note = Note::from_str(&note_title(&doc.header, &file), doc.body);
+    note.header = doc.header;
+    note.file = file;
+    Ok(note)
+}
+
+/// Loads the journal page of `date` (`yyyy-mm-dd`).
+pub fn load_journal_page(vault: &Vault, date: &str) -> Result<Journal, FileError> {
+    let doc = read_document(vault, Folder::Journal, date)?;
+
+    match Journal::parse(date, &doc.body, doc.body_line) {
+        Ok(mut journal) => {
+            journal.header = doc.header;
+            Ok(journal)
+        }
+        Err(e) => Err(FileError::FormatError(vault.path(Folder::Journal, date), e)),
+    }
+}
+
+// ADD EMPTY FILE ERROR TYPE
+/// Lists the names of the files in a folder of the vault, in no particular
+/// order.
+pub fn list_files(vault: &Vault, folder: Folder) -> Result<Vec<String>, FileError> {
+    let path = vault.folder(folder);
+    let files_list = fs::read_dir(&path).map_err(|e| FileError::read(&path, e))?;
+
+    let file_strings: Result<Vec<_>, FileError> = files_list
+        .map(|f| match f {
+            Ok(s) => match s.file_name().to_str() {
+                Some(str) => Ok(String::from(str)),
+                None => Err(FileError::ReadError(s.path(), io::ErrorKind::InvalidData)),
+            },
+            Err(e) => Err(FileError::read(&path, e)),
+        })
+        .collect();
+
+    file_strings
+}
+
+// FIX ERROR HANDLING
+/// Loads the trail named `name`.
+pub fn load_trail(vault: &Vault, name: &str) -> Result<Trail, TrailError> {
+    let doc = match read_document(vault, Folder::Trails, name) {
+        Ok(d) => d,
+        Err(e) => return Err(TrailError::FileError(e)),
+    };
+    if doc.body.is_empty() {
+        let path = vault.path(Folder::Trails, name);
+        return Err(TrailError::FileError(FileError::EmptyFileError(path)));
+    }
+
+    let mut trail = Trail::parse(name, &doc.body, doc.body_line)?;
+    trail.header = doc.header;
+    Ok(trail)
+}
+
+// LINK RESOLUTION
+
+/// The title of a note: the one in its header or, for notes written before
+/// headers existed, the name of its file.
+fn note_title(header: &Header, file: &str) -> String {
+    match header.title.is_empty() {
+        true => String::from(file),
+        false => header.title.clone(),
+    }
+}
+
+/// ## Resolver
+///
+///  Maps the targets of links to the files of the notes they point to. A
+///  link names a note either by its stable id (`[20240311093012]`), which
+///  survives renames, or by its title (`[Memex]`). Ids are looked up first,
+///  then titles, then file names.
+///
+///  When several notes share a title, `[title]` links point to the first one
+///  in alphabetical order of file name: link to the others by id.
+#[derive(Default)]
+pub struct Resolver {
+    ids: HashMap<String, String>,
+    titles: HashMap<String, String>,
+    /// The title and the id of every note, by file.
+    files: HashMap<String, (String, String)>,
+}
+
+impl Resolver {
+    /// Reads the header of every note of the vault.
+    pub fn build(vault: &Vault) -> Resolver {
+        let mut resolver = Resolver::default();
+
+        for file in list_files(vault, Folder::Notes).unwrap_or_default() {
+            if let Ok(doc) = read_document(vault, Folder::Notes, &file) {
+                resolver.insert(&file, &doc.header);
+            }
+        }
+
+        resolver
+    }
+    /// Adds the note stored in `file` with the given header, replacing what
+    /// was known about that file.
+    pub fn insert(&mut self, file: &str, header: &Header) {
+        self.remove(file);
+        let title = note_title(header, file);
+        if !header.id.is_empty() {
+            claim(&mut self.ids, &header.id, file);
+        }
+        claim(&mut self.titles, &title, file);
+        self.files
+            .insert(String::from(file), (title, header.id.clone()));
+    }
+    /// Forgets the note stored in `file`. Its id and title go to the next
+    /// note sharing them, if any.
+    pub fn remove(&mut self, file: &str) {
+        let (title, id) = match self.files.remove(file) {
+            Some(names) => names,
+            None => return,
+        };
+        if self.titles.get(&title).is_some_and(|f| f == file) {
+            self.titles.remove(&title);
+            for (other, (t, _)) in &self.files {
+                if *t == title {
+                    claim(&mut self.titles, &title, other);
+                }
+            }
+        }
+        if self.ids.get(&id).is_some_and(|f| f == file) {
+            self.ids.remove(&id);
+            for (other, (_, i)) in &self.files {
+                if *i == id {
+                    claim(&mut self.ids, &id, other);
+                }
+            }
+        }
+    }
+    /// The file of the note `target` points to, if any.
+    pub fn resolve(&self, target: &str) -> Option<&str> {
+        match self.ids.get(target).or_else(|| self.titles.get(target)) {
+            Some(file) => Some(file),
+            None => self
+                .files
+                .get_key_value(target)
+                .map(|(file, _)| file.as_str()),
+        }
+    }
+    /// The title of the note `target` points to, or `target` itself if it
+    /// doesn't point to any note.
+    pub fn title<'a>(&'a self, target: &'a str) -> &'a str {
+        match self.resolve(target).and_then(|file| self.files.get(file)) {
+            Some((title, _)) => title,
+            None => target,
+        }
+    }
+}
+
+/// Points `name` to `file`, unless it already points to a file that comes
+/// first in alphabetical order.
+fn claim(names: &mut HashMap<String, String>, name: &str, file: &str) {
+    match names.get_mut(name) {
+        Some(owner) if owner.as_str() <= file => {}
+        Some(owner) => *owner = String::from(file),
+        None => {
+            names.insert(String::from(name), String::from(file));
+        }
+    }
+}
+
+// METADATA
+
+/// Reads a file from the hidden metadata folder of the vault.
+pub fn load_meta(vault: &Vault, section: &str, name: &str) -> Result<String, FileError> {
+    let path = vault.meta_path(section, name);
+    fs::read_to_string(&path).map_err(|e| FileError::read(&path, e))
+}
+
+/// Writes a file to the hidden metadata folder of the vault, creating the
+/// folder if needed.
+pub fn save_meta(
+    vault: &Vault,
+    section: &str,
+    name: &str,
+    contents: &str,
+) -> Result<(), FileError> {
+    save_meta_path(&vault.meta_path(section, name), contents)
+}
+
+// BACKLINKS
+
+/// ## Backlinks
+///
+///  An index of the pages linking to each note. It's built by scanning every
+///  page of the vault, and kept up to date one page at a time with
+///  [Backlinks::reload]:
+///
+///  - notes link to the pages named in their `[links]`;
+///  - journal pages link to the pages they list, and to the `[links]` in
+///    their description;
+///  - trails link to the notes of their hops, and to the `[links]` in their
+///    description.
+///
+///  Links are kept as they are written and resolved with a [Resolver] when
+///  the index is queried, so that a note is found whether it is linked by id
+///  or by title, and links written before their note existed are found once
+///  it does. Pages that can't be read or parsed are skipped.
+#[derive(Default)]
+pub struct Backlinks {
+    resolver: Resolver,
+    /// The links of every page.
+    links: HashMap<PageRef, Vec<String>>,
+    /// The pages linking to every target, as it is written in the links.
+    sources: HashMap<String, Vec<PageRef>>,
+}
+
+impl Backlinks {
+    /// Reads every page of the vault and collects the links between them.
+    pub fn build(vault: &Vault) -> Backlinks {
+        let mut backlinks = Backlinks::default();
+        for page in all_pages(vault) {
+            backlinks.reload(vault, &page);
+        }
+        backlinks
+    }
+    /// Reads `page` again after it was saved, renamed or deleted, and
+    /// updates its links.
+    pub fn reload(&mut self, vault: &Vault, page: &PageRef) {
+        for target in self.links.remove(page).unwrap_or_default() {
+            if let Some(sources) = self.sources.get_mut(&target) {
+                sources.retain(|p| p != page);
+            }
+        }
+        let links = match page.folder {
+            Folder::Notes => match read_document(vault, Folder::Notes, &page.name) {
+                Ok(doc) => {
+                    self.resolver.insert(&page.name, &doc.header);
+                    links_in(&doc.body)
+                }
+                Err(_) => {
+                    self.resolver.remove(&page.name);
+                    return;
+                }
+            },
+            Folder::Journal => match load_journal_page(vault, &page.name) {
+                Ok(journal) => {
+                    let mut links = journal.pages;
+                    links.extend(links_in(&journal.description));
+                    links
+                }
+                Err(_) => return,
+            },
+            Folder::Trails => match load_trail(vault, &page.name) {
+                Ok(trail) => {
+                    let mut links: Vec<_> = trail.hops.into_iter().map(|h| h.0).collect();
+                    links.extend(links_in(&trail.description));
+                    links
+                }
+                Err(_) => return,
+            },
+        };
+        for link in &links {
+            let sources = self.sources.entry(link.clone()).or_default();
+            if !sources.contains(page) {
+                sources.push(page.clone());
+            }
+        }
+        self.links.insert(page.clone(), links);
+    }
+    /// The pages linking to the note stored in `file`, in the order of
+    /// [all_pages].
+    pub fn to(&self, file: &str) -> Vec<PageRef> {
+        let mut names = vec![file];
+        if let Some((title, id)) = self.resolver.files.get(file) {
+            names.extend([title.as_str(), id.as_str()]);
+        }
+        let mut pages: Vec<PageRef> = Vec::new();
+        for name in names {
+            if self.resolver.resolve(name) != Some(file) {
+                continue;
+            }
+            for page in self.sources.get(name).into_iter().flatten() {
+                if !pages.contains(page) {
+                    pages.push(page.clone());
+                }
+            }
+        }
+        pages.sort_by(page_order);
+        pages
+    }
+}
+
+// RENAMING
+
+/// ## RenameReport
+///
+///  The outcome of renaming a note: every page whose links were rewritten,
+///  along with the number of links replaced in it, and the pages that were
+///  left untouched because they can't be read (a header that can't be
+///  parsed, or text that isn't valid UTF-8), with the reason why.
+///
+///  `file` is the file of the note before the rename, and `destination` the
+///  one after it: notes stored in a file named after their title follow the
+///  new title, when it's a valid file name.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RenameReport {
+    pub old: String,
+    pub new: String,
+    pub file: String,
+    pub destination: String,
+    pub pages: Vec<(PageRef, usize)>,
+    pub skipped: Vec<(PageRef, FileError)>,
+}
+
+impl RenameReport {
+    /// Total number of links rewritten across the vault.
+    pub fn links(&self) -> usize {
+        self.pages.iter().map(|(_, n)| n).sum()
+    }
+    /// Number of files touched by the rename, including the renamed note.
+    pub fn files(&self) -> usize {
+        let renamed = PageRef::new(Folder::Notes, &self.file);
+        match self.pages.iter().any(|(p, _)| *p == renamed) {
+            true => self.pages.len(),
+            false => self.pages.len() + 1,
+        }
+    }
+}
+
+/// Renames the note stored in `file` to `new`, rewriting every link to its
+/// old title in the notes, journal pages and trails of the vault (journal
+/// page lists and trail hops use the same syntax, so they're rewritten as
+/// well). Links to the id of the note don't need to change.
+///
+/// With `dry_run`, nothing is written and the report only previews the
+/// changes.
+///
+/// The rename is atomic: the new version of every file is first written to
+/// the metadata folder, and only moved over the old one once all of them
+/// were written. If moving one of them fails, the files already moved are
+/// restored to their original content.
+pub fn rename_note(
+    vault: &Vault,
+    file: &str,
+    new: &str,
+    dry_run: bool,
+) -> Result<RenameReport, RenameError> {
+    let new = new.trim();
+    if new.is_empty() || new.contains(['[', ']', '\n']) {
+        return Err(RenameError::InvalidTitle);
+    }
+    let doc = match read_document(vault, Folder::Notes, file) {
+        Ok(d) => d,
+        Err(e) if e.is_not_found() => return Err(RenameError::NotFound),
+        Err(e) => return Err(RenameError::FileError(e)),
+    };
+    let old = note_title(&doc.header, file);
+    let resolver = Resolver::build(vault);
+    if resolver.titles.get(new).is_some_and(|f| f != file) {
+        return Err(RenameError::AlreadyExists);
+    }
+    // Follow the title with the file name when possible
+    let destination = match file == old && is_file_name(new) {
+        true if new == file || !vault.path(Folder::Notes, new).exists() => String::from(new),
+        true => return Err(RenameError::AlreadyExists),
+        false => String::from(file),
+    };
+    let new_path = vault.path(Folder::Notes, &destination);
+
+    let old_link = format!("[{}]", old);
+    let new_link = format!("[{}]", new);
+
+    // Plan every change: (renamed page, original path, destination path,
+    // original contents, new contents)
+    let mut report = RenameReport {
+        old: old.clone(),
+        new: String::from(new),
+        file: String::from(file),
+        destination: destination.clone(),
+        pages: Vec::new(),
+        skipped: Vec::new(),
+    };
+    let mut changes = Vec::new();
+
+    for folder in Folder::ALL {
+        let names = match list_files(vault, folder) {
+            Ok(n) => n,
+            Err(e) => return Err(RenameError::FileError(e)),
+        };
+        for name in names {
+            let path = vault.path(folder, &name);
+            // Pages that can't be parsed are left alone rather than
+            // rewritten blindly
+            let contents = match read_text(&path) {
+                Ok(c) if has_header(&c) => match parse_document(&c) {
+                    Ok(_) => c,
+                    Err(e) => {
+                        let error = FileError::FormatError(path, e);
+                        report.skipped.push((PageRef::new(folder, &name), error));
+                        continue;
+                    }
+                },
+                Ok(c) => c,
+                Err(e @ FileError::FormatError(..)) => {
+                    report.skipped.push((PageRef::new(folder, &name), e));
+                    continue;
+                }
+                Err(e) => return Err(RenameError::FileError(e)),
+            };
+            let count = contents.matches(&old_link).count();
+            let is_renamed_note = folder == Folder::Notes && name == file;
+            if count > 0 {
+                report.pages.push((PageRef::new(folder, &name), count));
+            }
+            if count > 0 || is_renamed_note {
+                let (page, destination) = match is_renamed_note {
+                    true => (PageRef::new(folder, &destination), new_path.clone()),
+                    false => (PageRef::new(folder, &name), path.clone()),
+                };
+                let mut rewritten = contents.replace(&old_link, &new_link);
+                if is_renamed_note {
+                    // The new title is kept in the header, which notes
+                    // written before headers existed are given now.
+                    let mut header = doc.header.clone();
+                    let body = doc.body.replace(&old_link, &new_link);
+                    if header.id.is_empty() {
+                        let now = chrono::Local::now().naive_local();
+                        assign_id(vault, &mut header);
+                        header.created = timestamp(now);
+                        header.modified = timestamp(now);
+                    }
+                    header.title = String::from(new);
+                    rewritten = header.to_str() + &body;
+                }
+                changes.push((page, path, destination, contents, rewritten));
+            }
+        }
+    }
+
+    if dry_run {
+        return Ok(report);
+    }
+
+    // Stage the new contents
+    let staged: Vec<_> = (0..changes.len())
+        .map(|i| vault.meta_path("rename", &i.to_string()))
+        .collect();
+    let write_staged = changes
+        .iter()
+        .zip(staged.iter())
+        .try_for_each(|((_, _, _, _, rewritten), tmp)| save_meta_path(tmp, rewritten));
+    if let Err(e) = write_staged {
+        for tmp in &staged {
+            let _ = fs::remove_file(tmp);
+        }
+        return Err(RenameError::FileError(e));
+    }
+
+    // Move them over the old files, rolling back on failure
+    for (i, ((_, path, destination, _, _), tmp)) in changes.iter().zip(staged.iter()).enumerate() {
+        if let Err(e) = fs::rename(tmp, destination) {
+            for (_, path, destination, contents, _) in &changes[..i] {
+                let _ = fs::write(path, contents);
+                if destination != path {
+                    let _ = fs::remove_file(destination);
+                }
+            }
+            for tmp in &staged[i..] {
+                let _ = fs::remove_file(tmp);
+            }
+            return Err(RenameError::FileError(FileError::write(destination, e)));
+        }
+        if destination != path {
+            let _ = fs::remove_file(path);
+        }
+    }
+
+    // Carry over the undo history of the note, if any
+    if destination != file {
+        let _ = fs::rename(
+            vault.meta_path("history", file),
+            vault.meta_path("history", &destination),
+        );
+    }
+
+    // Bring the search index up to date
+    let mut entries = index_log_entry(&PageRef::new(Folder::Notes, file), None);
+    for (page, _, _, _, rewritten) in &changes {
+        entries.push_str(&index_log_entry(page, Some(&body_of(rewritten))));
+    }
+    let _ = update_index(vault, &entries);
+
+    Ok(report)
+}
+
+/// Writes `contents` to a path of the metadata folder, creating its parent
+/// folder if needed.
+fn save_meta_path(path: &Path, contents: &str) -> Result<(), FileError> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| FileError::write(dir, e))?;
+    }
+    fs::write(path, contents).map_err(|e| FileError::write(path, e))
+}
+
+// SEARCH
+
+/// Splits `text` into lowercase words, dropping punctuation.
+fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+        .collect()
+}
+
+/// ## SearchHit
+///
+///  A page matching a search query, with its relevance score and a short
+///  excerpt of the text around the first match.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchHit {
+    pub page: PageRef,
+    pub score: usize,
+    pub snippet: String,
+}
+
+/// Weight of a query word found in the name of a page, compared to the same
+/// word found in its text.
+const NAME_WEIGHT: usize = 10;
+
+/// Number of characters of context shown on each side of a match in a
+/// snippet.
+const SNIPPET_CONTEXT: usize = 40;
+
+/// Searches every note, journal page and trail of the vault for `query`,
+/// through the search index of the vault (which is built first if it doesn't
+/// exist).
+///
+/// Words are stemmed, and match every word of a page starting with the same
+/// stem, in the page's name or text. `"Quoted phrases"` only match the same
+/// words, in the same order, in the text. A page must match every word and
+/// phrase of the query. Pages are ranked by the number of matches, with the
+/// matches in the name weighing more than the ones in the text.
+pub fn search(vault: &Vault, query: &str) -> Vec<SearchHit> {
+    let index = Index::open(vault);
+
+    let words: Vec<_> = parse_query(query)
+        .into_iter()
+        .flat_map(|part| match part {
+            QueryPart::Word(w) => vec![w],
+            QueryPart::Phrase(p) => p,
+        })
+        .collect();
+
+    index
+        .query(query)
+        .into_iter()
+        .map(|(page, score)| {
+            let text = fs::read_to_string(vault.path(page.folder, &page.name)).unwrap_or_default();
+            let text = body_of(&text);
+            SearchHit {
+                snippet: snippet(&text, &words),
+                page,
+                score,
+            }
+        })
+        .collect()
+}
+
+/// Extracts a single-line excerpt of `text` around the first occurrence of
+/// any of `terms` (which may be word stems).
+fn snippet(text: &str, terms: &[String]) -> String {
+    let chars: Vec<char> = text
+        .chars()
+        .map(|c| if c.is_whitespace() { ' ' } else { c })
+        .collect();
+    let lower: Vec<char> = chars
+        .iter()
+        .map(|c| c.to_lowercase().next().unwrap_or(*c))
+        .collect();
+
+    let first = terms
+        .iter()
+        .filter_map(|t| {
+            let t: Vec<char> = t.chars().collect();
+            lower.windows(t.len()).position(|w| w == t.as_slice())
+        })
+        .min()
+        .unwrap_or(0);
+
+    let start = first.saturating_sub(SNIPPET_CONTEXT);
+    let end = (first + SNIPPET_CONTEXT).min(chars.len());
+    let mut excerpt: String = chars[start..end].iter().collect();
+    excerpt = excerpt.trim().to_string();
+    if start > 0 {
+        excerpt.insert_str(0, "...");
+    }
+    if end < chars.len() {
+        excerpt.push_str("...");
+    }
+    excerpt
+}
+
+// INDEX
+
+/// Section of the metadata folder holding the search index.
+const INDEX_SECTION: &str = "index";
+
+const INDEX_FILE: &str = "postings";
+
+/// File of the index where the pages saved since the postings file was last
+/// written are appended.
+const INDEX_LOG: &str = "log";
+
+/// First line of the index file, to recognize the format version.
+const INDEX_HEADER: &str = "vannevar-index 1";
+
+/// Reduces an English word to its stem, e.g. "trails" and "trailing" to
+/// "trail".
+fn stem(word: &str) -> String {
+    Stemmer::create(Algorithm::English).stem(word).into_owned()
+}
+
+/// Splits `text` into stemmed, lowercase words.
+fn index_terms(text: &str) -> Vec<String> {
+    tokenize(text).iter().map(|w| stem(w)).collect()
+}
+
+/// A 64-bit FNV-1a hash, used to skip reindexing pages that didn't change.
+fn fingerprint(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum QueryPart {
+    Word(String),
+    Phrase(Vec<String>),
+}
+
+/// Splits a query into single words and `"quoted phrases"`, all stemmed.
+fn parse_query(query: &str) -> Vec<QueryPart> {
+    query
+        .split('"')
+        .enumerate()
+        .flat_map(|(i, part)| match i % 2 {
+            0 => index_terms(part).into_iter().map(QueryPart::Word).collect(),
+            _ => {
+                let words = index_terms(part);
+                match words.len() {
+                    0 => vec![],
+                    1 => words.into_iter().map(QueryPart::Word).collect(),
+                    _ => vec![QueryPart::Phrase(words)],
+                }
+            }
+        })
+        .collect()
+}
+
+struct IndexedPage {
+    page: PageRef,
+    fingerprint: u64,
+    name_terms: Vec<String>,
+    /// The words of the text of the page, to drop its postings on removal.
+    terms: Vec<String>,
+}
+
+/// ## Index
+///
+///  A persistent inverted index of the vault: for every stemmed word, the
+///  pages containing it and the positions of the word in each page, so that
+///  phrase queries can be answered without reading the pages.
+///
+///  The index is stored in `.vannevar/index/postings`, in the vault, and can
+///  be rebuilt from scratch with `vannevar reindex`. The file starts with
+///  the `vannevar-index 1` header, followed by a line for each page
+///  (`p <id> <folder> <fingerprint> <name>`) and a line for each word
+///  (`w <word> <id>:<position>,<position> <id>:<position>...`).
+///
+///  Saving a note, journal page or trail doesn't rewrite that file: the new
+///  version of the page is appended to `.vannevar/index/log`, as a
+///  `p <folder> <fingerprint> <name>` line followed by a
+///  `w <word> <position>,<position>...` line for each of its words, and a
+///  removed page as a `r <folder> <name>` line. The log is replayed over
+///  the postings when the index is loaded, and folded back into them once
+///  it outgrows them.
+#[derive(Default)]
+struct Index {
+    pages: Vec<Option<IndexedPage>>,
+    ids: HashMap<PageRef, usize>,
+    postings: BTreeMap<String, Vec<(usize, Vec<usize>)>>,
+}
+
+impl Index {
+    /// Indexes every page of the vault.
+    pub fn build(vault: &Vault) -> Index {
+        let mut index = Index::default();
+
+        for folder in Folder::ALL {
+            for name in list_files(vault, folder).unwrap_or_default() {
+                if let Ok(text) = fs::read_to_string(vault.path(folder, &name)) {
+                    index.update(&PageRef::new(folder, &name), &body_of(&text));
+                }
+            }
+        }
+
+        index
+    }
+    /// Loads the index of the vault, building it first if it doesn't exist
+    /// or can't be read.
+    pub fn open(vault: &Vault) -> Index {
+        match Index::load(vault) {
+            Ok(index) => index,
+            Err(_) => {
+                let index = Index::build(vault);
+                let _ = index.save(vault);
+                index
+            }
+        }
+    }
+    /// Loads the postings of the index, and replays the log over them.
+    pub fn load(vault: &Vault) -> Result<Index, FileError> {
+        let mut index = Index::load_postings(vault)?;
+        match load_meta(vault, INDEX_SECTION, INDEX_LOG) {
+            Ok(log) => index.replay(vault, &log)?,
+            Err(e) if e.is_not_found() => {}
+            Err(e) => return Err(e),
+        }
+        Ok(index)
+    }
+    fn load_postings(vault: &Vault) -> Result<Index, FileError> {
+        let text = load_meta(vault, INDEX_SECTION, INDEX_FILE)?;
+        let mut lines = text.lines().enumerate();
+        let mut index = Index::default();
+        let malformed = |number: usize| {
+            let error = ParseError::new(number + 1, 1, "malformed index entry");
+            FileError::FormatError(vault.meta_path(INDEX_SECTION, INDEX_FILE), error)
+        };
+
+        if lines.next().map(|(_, l)| l) != Some(INDEX_HEADER) {
+            return Err(malformed(0));
+        }
+
+        for (number, line) in lines {
+            match line.split_once(' ') {
+                Some(("p", rest)) => {
+                    let (id, rest) = rest.split_once(' ').ok_or_else(|| malformed(number))?;
+                    match (id.parse::<usize>(), parse_indexed_page(rest)) {
+                        (Ok(id), Some((page, fingerprint))) if id == index.pages.len() => {
+                            index.push(page, fingerprint);
+                        }
+                        _ => return Err(malformed(number)),
+                    }
+                }
+                Some(("w", rest)) => {
+                    let mut fields = rest.split(' ');
+                    let word = match fields.next() {
+                        Some(w) => String::from(w),
+                        None => return Err(malformed(number)),
+                    };
+                    let mut postings = Vec::new();
+                    for field in fields {
+                        let (id, positions) = match field.split_once(':') {
+                            Some(p) => p,
+                            None => return Err(malformed(number)),
+                        };
+                        let id = match id.parse::<usize>() {
+                            Ok(id) if id < index.pages.len() => id,
+                            _ => return Err(malformed(number)),
+                        };
+                        match parse_positions(positions) {
+                            Some(p) => postings.push((id, p)),
+                            None => return Err(malformed(number)),
+                        }
+                        if let Some(p) = index.pages[id].as_mut() {
+                            p.terms.push(word.clone());
+                        }
+                    }
+                    index.postings.insert(word, postings);
+                }
+                _ => return Err(malformed(number)),
+            }
+        }
+
+        Ok(index)
+    }
+    /// Applies the pages saved and removed since the postings were written.
+    /// A log cut short by a crash is rejected, so that the index is rebuilt.
+    fn replay(&mut self, vault: &Vault, log: &str) -> Result<(), FileError> {
+        let malformed = |number: usize| {
+            let error = ParseError::new(number + 1, 1, "malformed index log entry");
+            FileError::FormatError(vault.meta_path(INDEX_SECTION, INDEX_LOG), error)
+        };
+        if !log.is_empty() && !log.ends_with('\n') {
+            return Err(malformed(log.lines().count() - 1));
+        }
+
+        let mut current: Option<(PageRef, u64, BTreeMap<String, Vec<usize>>)> = None;
+        for (number, line) in log.lines().enumerate() {
+            let (kind, rest) = line.split_once(' ').ok_or_else(|| malformed(number))?;
+            if kind != "w" {
+                if let Some((page, fingerprint, positions)) = current.take() {
+                    self.insert(&page, fingerprint, positions);
+                }
+            }
+            match kind {
+                "p" => {
+                    let (page, fingerprint) =
+                        parse_indexed_page(rest).ok_or_else(|| malformed(number))?;
+                    current = Some((page, fingerprint, BTreeMap::new()));
+                }
+                "r" => {
+                    let (folder, name) = rest.split_once(' ').ok_or_else(|| malformed(number))?;
+                    let folder = parse_folder(folder).ok_or_else(|| malformed(number))?;
+                    self.remove(&PageRef::new(folder, name));
+                }
+                "w" => {
+                    let (word, positions) =
+                        rest.split_once(' ').ok_or_else(|| malformed(number))?;
+                    let positions = parse_positions(positions).ok_or_else(|| malformed(number))?;
+                    match current.as_mut() {
+                        Some((_, _, words)) => words.insert(String::from(word), positions),
+                        None => return Err(malformed(number)),
+                    };
+                }
+                _ => return Err(malformed(number)),
+            }
+        }
+        if let Some((page, fingerprint, positions)) = current {
+            self.insert(&page, fingerprint, positions);
+        }
+
+        Ok(())
+    }
+    /// Writes the index to disk, dropping the pages that were removed. The
+    /// file is replaced atomically, so that a crash can't leave a truncated
+    /// index behind.
+    pub fn save(&self, vault: &Vault) -> Result<(), FileError> {
+        // Renumber the pages still in the index
+        let mut ids = vec![None; self.pages.len()];
+        let mut buffer = format!("{}\n", INDEX_HEADER);
+        let mut id = 0;
+        for (old, p) in self.pages.iter().enumerate() {
+            if let Some(p) = p {
+                ids[old] = Some(id);
+                buffer.push_str(&format!(
+                    "p {} {} {} {}\n",
+                    id,
+                    p.page.folder.name(),
+                    p.fingerprint,
+                    p.page.name
+                ));
+                id += 1;
+            }
+        }
+        for (word, postings) in &self.postings {
+            buffer.push_str(&format!("w {}", word));
+            for (id, positions) in postings {
+                let positions: Vec<_> = positions.iter().map(|p| p.to_string()).collect();
+                if let Some(id) = ids[*id] {
+                    buffer.push_str(&format!(" {}:{}", id, positions.join(",")));
+                }
+            }
+            buffer.push('\n');
+        }
+
+        // The log is folded into the postings: once they're in place, it
+        // can go. Replaying it over them again would do no harm.
+        let tmp = vault.meta_path(INDEX_SECTION, &format!("{}.tmp", INDEX_FILE));
+        let path = vault.meta_path(INDEX_SECTION, INDEX_FILE);
+        let log = vault.meta_path(INDEX_SECTION, INDEX_LOG);
+        save_meta_path(&tmp, &buffer)?;
+        fs::rename(tmp, &path).map_err(|e| FileError::write(&path, e))?;
+        match fs::remove_file(&log) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(FileError::write(&log, e)),
+            _ => Ok(()),
+        }
+    }
+    /// Adds a page with no words to the index, and returns its id.
+    fn push(&mut self, page: PageRef, fingerprint: u64) -> usize {
+        let id = self.pages.len();
+        self.ids.insert(page.clone(), id);
+        self.pages.push(Some(IndexedPage {
+            name_terms: index_terms(&page.name),
+            page,
+            fingerprint,
+            terms: Vec::new(),
+        }));
+        id
+    }
+    /// Drops a page from the index.
+    pub fn remove(&mut self, page: &PageRef) {
+        let id = match self.ids.remove(page) {
+            Some(id) => id,
+            None => return,
+        };
+        for term in self.pages[id].take().map(|p| p.terms).unwrap_or_default() {
+            if let Some(postings) = self.postings.get_mut(&term) {
+                postings.retain(|(i, _)| *i != id);
+                if postings.is_empty() {
+                    self.postings.remove(&term);
+                }
+            }
+        }
+    }
+    /// Indexes the current `text` of a page, replacing its previous version.
+    /// Returns `false` if the page was already indexed with the same text.
+    pub fn update(&mut self, page: &PageRef, text: &str) -> bool {
+        let hash = fingerprint(text);
+        if let Some(&id) = self.ids.get(page) {
+            if self.pages[id].as_ref().map(|p| p.fingerprint) == Some(hash) {
+                return false;
+            }
+        }
+
+        self.insert(page, hash, term_positions(text));
+        true
+    }
+    /// Replaces a page with the given words, at the given positions.
+    fn insert(&mut self, page: &PageRef, fingerprint: u64, words: BTreeMap<String, Vec<usize>>) {
+        self.remove(page);
+        let id = self.push(page.clone(), fingerprint);
+        let terms = words.keys().cloned().collect();
+        for (term, p) in words {
+            self.postings.entry(term).or_default().push((id, p));
+        }
+        if let Some(p) = self.pages[id].as_mut() {
+            p.terms = terms;
+        }
+    }
+    /// Ranks the pages matching `query`, as described in [search].
+    pub fn query(&self, query: &str) -> Vec<(PageRef, usize)> {
+        let parts = parse_query(query);
+        if parts.is_empty() {
+            return Vec::new();
+        }
+
+        let mut scores: Option<HashMap<usize, usize>> = None;
+
+        for part in parts {
+            let mut counts: HashMap<usize, usize> = HashMap::new();
+            match part {
+                QueryPart::Word(w) => {
+                    let matching = self
+                        .postings
+                        .range(w.clone()..)
+                        .take_while(|(term, _)| term.starts_with(w.as_str()));
+                    for (_, postings) in matching {
+                        for (id, positions) in postings {
+                            *counts.entry(*id).or_default() += positions.len();
+                        }
+                    }
+                    for (id, p) in self.pages.iter().enumerate() {
+                        if let Some(p) = p {
+                            let in_name = p
+                                .name_terms
+                                .iter()
+                                .filter(|t| t.starts_with(w.as_str()))
+                                .count();
+                            if in_name > 0 {
+                                *counts.entry(id).or_default() += in_name * NAME_WEIGHT;
+                            }
+                        }
+                    }
+                }
+                QueryPart::Phrase(words) => {
+                    let lists: Option<Vec<HashMap<usize, &Vec<usize>>>> = words
+                        .iter()
+                        .map(|w| {
+                            self.postings
+                                .get(w)
+                                .map(|p| p.iter().map(|(id, pos)| (*id, pos)).collect())
+                        })
+                        .collect();
+                    if let Some(lists) = lists {
+                        for (id, first) in &lists[0] {
+                            let found = first
+                                .iter()
+                                .filter(|&&start| {
+                                    lists.iter().enumerate().skip(1).all(|(offset, list)| {
+                                        list.get(id)
+                                            .map(|pos| pos.binary_search(&(start + offset)).is_ok())
+                                            .unwrap_or(false)
+                                    })
+                                })
+                                .count();
+                            if found > 0 {
+                                counts.insert(*id, found);
+                            }
+                        }
+                    }
+                }
+            }
+
+            scores = Some(match scores {
+                None => counts,
+                Some(previous) => previous
+                    .into_iter()
+                    .filter_map(|(id, score)| counts.get(&id).map(|c| (id, score + c)))
+                    .collect(),
+            });
+        }
+
+        let mut ranked: Vec<_> = scores
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|(id, score)| self.pages[id].as_ref().map(|p| (p.page.clone(), score)))
+            .collect();
+        ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.name.cmp(&b.0.name)));
+        ranked
+    }
+}
+
+/// The positions of every word of `text`, as they are indexed.
+fn term_positions(text: &str) -> BTreeMap<String, Vec<usize>> {
+    let mut positions: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    for (i, term) in index_terms(text).into_iter().enumerate() {
+        positions.entry(term).or_default().push(i);
+    }
+    positions
+}
+
+fn parse_folder(name: &str) -> Option<Folder> {
+    Folder::ALL.iter().find(|f| f.name() == name).copied()
+}
+
+/// Reads the `<folder> <fingerprint> <name>` of a page of the index.
+fn parse_indexed_page(fields: &str) -> Option<(PageRef, u64)> {
+    let fields: Vec<_> = fields.splitn(3, ' ').collect();
+    match fields[..] {
+        [folder, fingerprint, name] => Some((
+            PageRef::new(parse_folder(folder)?, name),
+            fingerprint.parse().ok()?,
+        )),
+        _ => None,
+    }
+}
+
+fn parse_positions(positions: &str) -> Option<Vec<usize>> {
+    positions.split(',').map(|p| p.parse().ok()).collect()
+}
+
+/// The log entry of the new `text` of a page, or of its removal.
+fn index_log_entry(page: &PageRef, text: Option<&str>) -> String {
+    let text = match text {
+        Some(t) => t,
+        None => return format!("r {} {}\n", page.folder.name(), page.name),
+    };
+    let mut entry = format!(
+        "p {} {} {}\n",
+        page.folder.name(),
+        fingerprint(text),
+        page.name
+    );
+    for (term, positions) in term_positions(text) {
+        let positions: Vec<_> = positions.iter().map(|p| p.to_string()).collect();
+        entry.push_str(&format!("w {} {}\n", term, positions.join(",")));
+    }
+    entry
+}
+
+/// Records new versions of pages in the search index, given as the entries
+/// of [index_log_entry]. They're appended to the log, which is folded into
+/// the postings once it's bigger than them. The index is built first if it
+/// doesn't exist yet.
+fn update_index(vault: &Vault, entries: &str) -> Result<(), FileError> {
+    let path = vault.meta_path(INDEX_SECTION, INDEX_FILE);
+    let log = vault.meta_path(INDEX_SECTION, INDEX_LOG);
+    let size = |path: &Path| fs::metadata(path).map(|m| m.len()).ok();
+
+    match (size(&path), size(&log)) {
+        (None, _) => reindex(vault).map(|_| ()),
+        (Some(postings), Some(logged)) if logged + entries.len() as u64 > postings => {
+            match Index::load(vault) {
+                Ok(mut index) => {
+                    index.replay(vault, entries)?;
+                    index.save(vault)
+                }
+                Err(_) => reindex(vault).map(|_| ()),
+            }
+        }
+        _ => {
+            let mut file = fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&log)
+                .map_err(|e| FileError::write(&log, e))?;
+            file.write_all(entries.as_bytes())
+                .map_err(|e| FileError::write(&log, e))
+        }
+    }
+}
+
+/// Rebuilds the search index from scratch. Returns the number of pages
+/// indexed.
+pub fn reindex(vault: &Vault) -> Result<usize, FileError> {
+    let index = Index::build(vault);
+    index.save(vault)?;
+    Ok(index.pages.len())
+}
+
+// FUZZY MATCHING
+
+/// ## FuzzyMatch
+///
+///  A candidate matching a fuzzy pattern: its position in the list of
+///  candidates, its score, and the positions (in `char`s) of the matched
+///  characters, for highlighting.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FuzzyMatch {
+    pub index: usize,
+    pub score: i64,
+    pub positions: Vec<usize>,
+}
+
+/// Matches `pattern` against `candidate` as a case-insensitive subsequence.
+///
+/// Matches are scored so that consecutive characters, characters at the
+/// start of a word and matches close to the start of the candidate rank
+/// higher, while gaps between matched characters are penalized.
+pub fn fuzzy_match(pattern: &str, candidate: &str) -> Option<(i64, Vec<usize>)> {
+    let pattern: Vec<char> = pattern.chars().flat_map(char::to_lowercase).collect();
+    let chars: Vec<char> = candidate.chars().collect();
+    let mut positions: Vec<usize> = Vec::with_capacity(pattern.len());
+    let mut score: i64 = 0;
+    let mut next = 0;
+
+    for p in pattern {
+        let found = (next..chars.len()).find(|&i| chars[i].to_lowercase().any(|c| c == p))?;
+
+        score += 1;
+        if found == 0 || !chars[found - 1].is_alphanumeric() {
+            score += 8;
+        }
+        match positions.last() {
+            Some(&last) if last + 1 == found => score += 5,
+            Some(&last) => score -= (found - last - 1).min(5) as i64,
+            None => score -= found.min(10) as i64,
+        }
+
+        positions.push(found);
+        next = found + 1;
+    }
+
+    Some((score, positions))
+}
+
+/// Filters and ranks `candidates` against a fuzzy `pattern`. With an empty
+/// pattern, every candidate matches, in its original order.
+pub fn fuzzy_filter(pattern: &str, candidates: &[String]) -> Vec<FuzzyMatch> {
+    let mut matches: Vec<_> = candidates
+        .iter()
+        .enumerate()
+        .filter_map(|(index, c)| {
+            fuzzy_match(pattern, c).map(|(score, positions)| FuzzyMatch {
+                index,
+                score,
+                positions,
+            })
+        })
+        .collect();
+
+    // Stable sort: candidates with the same score keep their order
+    matches.sort_by_key(|m| std::cmp::Reverse(m.score));
+    matches
+}
+
+// EXPORT
+
+/// ## ExportFormat
+///
+///  The formats a trail can be compiled to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExportFormat {
+    Markdown,
+    Text,
+    Html,
+}
+
+impl ExportFormat {
+    /// The extension of the files written in this format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "md",
+            ExportFormat::Text => "txt",
+            ExportFormat::Html => "html",
+        }
+    }
+}
+
+/// ## CompileOptions
+///
+///  How a trail is compiled into a document:
+///
+///  - `headings`: start the section of every hop with the title of its note;
+///  - `strip_links`: turn `[links]` into plain words;
+///  - `sources`: end the document with the list of the notes it was made of;
+///  - `formats`: the files to write, one per format.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CompileOptions {
+    pub headings: bool,
+    pub strip_links: bool,
+    pub sources: bool,
+    pub formats: Vec<ExportFormat>,
+}
+
+impl Default for CompileOptions {
+    fn default() -> Self {
+        CompileOptions {
+            headings: true,
+            strip_links: false,
+            sources: true,
+            formats: vec![ExportFormat::Markdown],
+        }
+    }
+}
+
+/// The pieces a compiled trail is made of, before being rendered in one of
+/// the [ExportFormat]s.
+enum Block {
+    Title(String),
+    Heading(String),
+    Transition(String),
+    Text(String),
+    Rule,
+    List(Vec<String>),
+}
+
+impl Trail {
+    /// Compiles the trail into a single document: the text of the note of
+    /// every hop, in order, with the hop descriptions as transitions between
+    /// them. Hops whose note doesn't exist are marked as missing.
+    pub fn compile(&self, vault: &Vault, options: &CompileOptions, format: ExportFormat) -> String {
+        let blocks = self.blocks(vault, options);
+        match format {
+            ExportFormat::Markdown => render_markdown(&blocks),
+            ExportFormat::Text => render_text(&blocks),
+            ExportFormat::Html => render_html(&self.name, &blocks),
+        }
+    }
+    fn blocks(&self, vault: &Vault, options: &CompileOptions) -> Vec<Block> {
+        let clean = |text: &str| match options.strip_links {
+            true => String::from(regex!(r"\[(.+?)\]").replace_all(text, "$1")),
+            false => String::from(text),
+        };
+        let mut blocks = vec![Block::Title(self.name.clone())];
+        let mut sources: Vec<(String, String)> = Vec::new();
+
+        if !self.description.trim().is_empty() {
+            blocks.push(Block::Text(clean(self.description.trim())));
+        }
+        for (target, description) in &self.hops {
+            let note = load_note(vault, target).ok();
+            let source = match &note {
+                Some(n) => (n.title.clone(), n.file.clone()),
+                None => (target.clone(), target.clone()),
+            };
+            if options.headings {
+                blocks.push(Block::Heading(source.0.clone()));
+            }
+            if !description.trim().is_empty() {
+                blocks.push(Block::Transition(clean(description.trim())));
+            }
+            match note {
+                Some(n) if !n.text.trim().is_empty() => {
+                    blocks.push(Block::Text(clean(n.text.trim())))
+                }
+                Some(_) => {}
+                None => blocks.push(Block::Transition(format!("(missing note: {})", target))),
+            }
+            if !sources.contains(&source) {
+                sources.push(source);
+            }
+        }
+        if options.sources && !sources.is_empty() {
+            blocks.push(Block::Rule);
+            blocks.push(Block::Heading(String::from("Sources")));
+            blocks.push(Block::List(
+                sources
+                    .iter()
+                    .map(|(title, file)| format!("{} ({}/{})", title, Folder::Notes.name(), file))
+                    .collect(),
+            ));
+        }
+        blocks
+    }
+}
+
+fn render_markdown(blocks: &[Block]) -> String {
+    let mut buffer = String::new();
+    for block in blocks {
+        match block {
+            Block::Title(t) => buffer.push_str(&format!("# {}\n\n", t)),
+            Block::Heading(t) => buffer.push_str(&format!("## {}\n\n", t)),
+            Block::Transition(t) => {
+                for line in t.lines().filter(|l| !l.trim().is_empty()) {
+                    buffer.push_str(&format!("> *{}*\n", line.trim()));
+                }
+                buffer.push('\n');
+            }
+            Block::Text(t) => buffer.push_str(&format!("{}\n\n", t)),
+            Block::Rule => buffer.push_str("---\n\n"),
+            Block::List(items) => {
+                for (i, item) in items.iter().enumerate() {
+                    buffer.push_str(&format!("{}. {}\n", i + 1, item));
+                }
+                buffer.push('\n');
+            }
+        }
+    }
+    buffer.truncate(buffer.trim_end().len());
+    buffer.push('\n');
+    buffer
+}
+
+fn render_text(blocks: &[Block]) -> String {
+    let underline = |t: &str, c: &str| c.repeat(t.chars().count());
+    let mut buffer = String::new();
+    for block in blocks {
+        match block {
+            Block::Title(t) => buffer.push_str(&format!("{}\n{}\n\n", t, underline(t, "="))),
+            Block::Heading(t) => buffer.push_str(&format!("{}\n{}\n\n", t, underline(t, "-"))),
+            Block::Transition(t) | Block::Text(t) => buffer.push_str(&format!("{}\n\n", t)),
+            Block::Rule => buffer.push_str("* * *\n\n"),
+            Block::List(items) => {
+                for (i, item) in items.iter().enumerate() {
+                    buffer.push_str(&format!("{}. {}\n", i + 1, item));
+                }
+                buffer.push('\n');
+            }
+        }
+    }
+    buffer.truncate(buffer.trim_end().len());
+    buffer.push('\n');
+    buffer
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Renders a block of text as HTML paragraphs, split at blank lines.
+fn html_paragraphs(text: &str) -> String {
+    text.split("\n\n")
+        .map(|p| p.trim())
+        .filter(|p| !p.is_empty())
+        .map(|p| format!("<p>{}</p>\n", escape_html(p).replace('\n', "<br>\n")))
+        .collect()
+}
+
+fn render_html(title: &str, blocks: &[Block]) -> String {
+    let mut body = String::new();
+    for block in blocks {
+        match block {
+            Block::Title(t) => body.push_str(&format!("<h1>{}</h1>\n", escape_html(t))),
+            Block::Heading(t) => body.push_str(&format!("<h2>{}</h2>\n", escape_html(t))),
+            Block::Transition(t) => body.push_str(&format!(
+                "<blockquote><em>{}</em></blockquote>\n",
+                escape_html(t)
+            )),
+            Block::Text(t) => body.push_str(&html_paragraphs(t)),
+            Block::Rule => body.push_str("<hr>\n"),
+            Block::List(items) => {
+                body.push_str("<ol>\n");
+                for item in items {
+                    body.push_str(&format!("<li>{}</li>\n", escape_html(item)));
+                }
+                body.push_str("</ol>\n");
+            }
+        }
+    }
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape_html(title),
+        body
+    )
+}
+
+/// Compiles a trail with the given options and writes one file per format
+/// into the `exports` folder of the vault, named after the trail. Returns
+/// the paths of the written files.
+pub fn export_trail(
+    vault: &Vault,
+    trail: &Trail,
+    options: &CompileOptions,
+) -> Result<Vec<PathBuf>, FileError> {
+    let mut written = Vec::new();
+    for format in &options.formats {
+        let path = vault.export_path(&format!("{}.{}", trail.name, format.extension()));
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| FileError::write(parent, e))?;
+        }
+        fs::write(&path, trail.compile(vault, options, *format))
+            .map_err(|e| FileError::write(&path, e))?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+// MIGRATION
+
+/// ## MigrationReport
+///
+///  The outcome of [migrate]: the pages upgraded to the current document
+///  format, the pages that couldn't be read (left untouched) with the
+///  reason why, and the folder the original files were copied to.
+pub struct MigrationReport {
+    pub migrated: Vec<PageRef>,
+    pub failed: Vec<(PageRef, FileError)>,
+    pub backup: PathBuf,
+}
+
+/// Upgrades every version 0 page of the vault to the current document
+/// format, in place. Each file is copied to `.vannevar/backup/<time>/`
+/// before being rewritten; pages whose body is malformed are reported and
+/// left as they are.
+///
+/// New ids and dates are taken from the last modification time of each
+/// file.
+pub fn migrate(vault: &Vault) -> Result<MigrationReport, FileError> {
+    let now = chrono::Local::now().naive_local();
+    let mut report = MigrationReport {
+        migrated: Vec::new(),
+        failed: Vec::new(),
+        backup: vault.meta_path("backup", &now.format(ID_FORMAT).to_string()),
+    };
+    let mut taken = all_ids(vault);
+
+    for folder in Folder::ALL {
+        for name in list_files(vault, folder)? {
+            let page = PageRef::new(folder, &name);
+            let path = vault.path(folder, &name);
+            let text = match read_text(&path) {
+                Ok(t) => t,
+                Err(e) => {
+                    report.failed.push((page, e));
+                    continue;
+                }
+            };
+            if has_header(&text) {
+                continue;
+            }
+
+            let checked = match folder {
+                Folder::Journal => parse_journal_body(&text, 1).map(|_| ()),
+                Folder::Trails => parse_trail_body(&text, 1).map(|_| ()),
+                Folder::Notes => Ok(()),
+            };
+            if let Err(e) = checked {
+                report.failed.push((page, FileError::FormatError(path, e)));
+                continue;
+            }
+
+            let time = modified_time(&path, now);
+            let mut header = Header::new(folder);
+            header.id = new_id(&taken, time);
+            header.title = name.clone();
+            header.created = timestamp(time);
+            header.modified = timestamp(time);
+            taken.insert(header.id.clone());
+
+            back_up_page(vault, &report.backup, &page)?;
+            fs::write(&path, header.to_str() + &text).map_err(|e| FileError::write(&path, e))?;
+            report.migrated.push(page);
+        }
+    }
+
+    Ok(report)
+}
+
+/// The last modification time of a file, or `now` if it can't be read.
+fn modified_time(path: &Path, now: chrono::NaiveDateTime) -> chrono::NaiveDateTime {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .map(|t| chrono::DateTime::<chrono::Local>::from(t).naive_local())
+        .unwrap_or(now)
+}
+
+/// Copies the file of a page to `backup/<folder>/<name>`, before it's
+/// upgraded to the current document format.
+fn back_up_page(vault: &Vault, backup: &Path, page: &PageRef) -> Result<(), FileError> {
+    let folder = backup.join(page.folder.name());
+    let copy = folder.join(&page.name);
+    fs::create_dir_all(&folder).map_err(|e| FileError::write(&folder, e))?;
+    fs::copy(vault.path(page.folder, &page.name), &copy).map_err(|e| FileError::write(&copy, e))?;
+    Ok(())
+}
+
+// HEALTH CHECK
+
+/// ## Problem
+///
+///  Something wrong found by [check_vault]:
+///
+///  - `Unreadable`: a page that can't be read, with the reason why (and the
+///    position of the error, when it's a formatting error);
+///  - `BrokenLink`: a `[link]` in a note or in a description that doesn't
+///    point to any note;
+///  - `MissingHop`: a hop (numbered from 1) of a trail whose note doesn't
+///    exist;
+///  - `UnresolvedJournalPage`: a page listed in a journal page that doesn't
+///    exist;
+///  - `Orphan`: a note that neither links to nor is linked by any page.
+///
+///  Orphans are only warnings: a note that was just written is often an
+///  orphan for a while.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Problem {
+    Unreadable(PageRef, FileError),
+    BrokenLink(PageRef, String),
+    MissingHop(PageRef, usize, String),
+    UnresolvedJournalPage(PageRef, String),
+    Orphan(PageRef),
+}
+
+impl Problem {
+    /// The page the problem was found in.
+    pub fn page(&self) -> &PageRef {
+        match self {
+            Problem::Unreadable(p, _)
+            | Problem::BrokenLink(p, _)
+            | Problem::MissingHop(p, _, _)
+            | Problem::UnresolvedJournalPage(p, _)
+            | Problem::Orphan(p) => p,
+        }
+    }
+    /// Tells whether the problem is only a warning (see [Problem]).
+    pub fn is_warning(&self) -> bool {
+        matches!(self, Problem::Orphan(_))
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let page = self.page();
+        write!(f, "{}/{}", page.folder.name(), page.name)?;
+        match self {
+            Problem::Unreadable(_, FileError::FormatError(_, e)) => {
+                write!(f, ":{}:{}: {}", e.line, e.column, e.message)
+            }
+            Problem::Unreadable(_, e) => write!(f, ": {}", e),
+            Problem::BrokenLink(_, target) => write!(f, ": broken link [{}]", target),
+            Problem::MissingHop(_, hop, target) => {
+                write!(f, ": hop {} points to the missing note [{}]", hop, target)
+            }
+            Problem::UnresolvedJournalPage(_, target) => {
+                write!(f, ": the listed page [{}] doesn't exist", target)
+            }
+            Problem::Orphan(_) => write!(f, ": orphan note (no links to or from it)"),
+        }
+    }
+}
+
+/// ## HealthReport
+///
+///  The outcome of [check_vault]: the number of pages checked and the
+///  problems found, in the order the pages were visited.
+pub struct HealthReport {
+    pub pages: usize,
+    pub problems: Vec<Problem>,
+}
+
+impl HealthReport {
+    /// Number of problems that aren't warnings.
+    pub fn errors(&self) -> usize {
+        self.problems.iter().filter(|p| !p.is_warning()).count()
+    }
+    /// Number of warnings.
+    pub fn warnings(&self) -> usize {
+        self.problems.iter().filter(|p| p.is_warning()).count()
+    }
+}
+
+/// Reads a page and checks its format, locating the error if there's one.
+fn check_page(vault: &Vault, page: &PageRef) -> Result<Document, FileError> {
+    let doc = read_document(vault, page.folder, &page.name)?;
+    match page.folder {
+        Folder::Journal => parse_journal_body(&doc.body, doc.body_line).map(|_| ()),
+        Folder::Trails => parse_trail_body(&doc.body, doc.body_line).map(|_| ()),
+        Folder::Notes => Ok(()),
+    }
+    .map_err(|e| FileError::FormatError(vault.path(page.folder, &page.name), e))?;
+    Ok(doc)
+}
+
+/// Walks every page of the vault, looking for unreadable pages, links and
+/// hops that don't point to any note, and orphan notes.
+pub fn check_vault(vault: &Vault) -> HealthReport {
+    let resolver = Resolver::build(vault);
+    let backlinks = Backlinks::build(vault);
+    let pages = all_pages(vault);
+    let mut problems = Vec::new();
+
+    for page in &pages {
+        let doc = match check_page(vault, page) {
+            Ok(d) => d,
+            Err(e) => {
+                problems.push(Problem::Unreadable(page.clone(), e));
+                continue;
+            }
+        };
+        let broken = |links: Vec<String>| -> Vec<String> {
+            links
+                .into_iter()
+                .filter(|l| resolver.resolve(l).is_none())
+                .collect()
+        };
+
+        match page.folder {
+            Folder::Notes => {
+                let links = links_in(&doc.body);
+                for target in broken(links.clone()) {
+                    problems.push(Problem::BrokenLink(page.clone(), target));
+                }
+                let inbound = backlinks.to(&page.name).iter().any(|p| p != page);
+                let outbound = links
+                    .iter()
+                    .filter_map(|l| resolver.resolve(l))
+                    .any(|file| file != page.name);
+                if !inbound && !outbound {
+                    problems.push(Problem::Orphan(page.clone()));
+                }
+            }
+            Folder::Journal => {
+                if let Ok((description, listed)) = parse_journal_body(&doc.body, doc.body_line) {
+                    for target in broken(links_in(&description)) {
+                        problems.push(Problem::BrokenLink(page.clone(), target));
+                    }
+                    for target in broken(listed) {
+                        problems.push(Problem::UnresolvedJournalPage(page.clone(), target));
+                    }
+                }
+            }
+            Folder::Trails => {
+                if let Ok((description, hops)) = parse_trail_body(&doc.body, doc.body_line) {
+                    for target in broken(links_in(&description)) {
+                        problems.push(Problem::BrokenLink(page.clone(), target));
+                    }
+                    for (i, (target, _)) in hops.into_iter().enumerate() {
+                        if resolver.resolve(&target).is_none() {
+                            problems.push(Problem::MissingHop(page.clone(), i + 1, target));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    HealthReport {
+        pages: pages.len(),
+        problems,
+    }
+}
+
+// JOURNAL CALENDAR
+
+/// The date of a journal page, from its name (`YYYY-MM-DD`).
+pub fn journal_date(name: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(name, "%Y-%m-%d").ok()
+}
+
+/// The name of the journal page of `date`.
+pub fn journal_name(date: NaiveDate) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
+
+/// The closest days before and after `date` that have a journal page.
+pub fn journal_neighbours(
+    vault: &Vault,
+    date: NaiveDate,
+) -> (Option<NaiveDate>, Option<NaiveDate>) {
+    let dates: Vec<_> = list_files(vault, Folder::Journal)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|name| journal_date(name))
+        .collect();
+    let previous = dates.iter().filter(|d| **d < date).max().copied();
+    let next = dates.iter().filter(|d| **d > date).min().copied();
+
+    (previous, next)
+}
+
+/// Every day that has a journal page, with the number of notes listed on
+/// it. Pages that can't be read are counted as having no notes, and files
+/// that aren't named after a date are left out.
+pub fn journal_days(vault: &Vault) -> BTreeMap<NaiveDate, usize> {
+    list_files(vault, Folder::Journal)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|name| {
+            let date = journal_date(name)?;
+            let notes = load_journal_page(vault, name)
+                .map(|j| j.pages.len())
+                .unwrap_or(0);
+            Some((date, notes))
+        })
+        .collect()
+}
+
+// JOURNAL ROLLUPS
+
+/// ## Period
+///
+///  The length of the time span covered by a [Rollup]. Weeks start on
+///  Monday.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Period {
+    Week,
+    Month,
+}
+
+impl Period {
+    /// The first and the last day of the period that includes `date`.
+    pub fn range(&self, date: NaiveDate) -> (NaiveDate, NaiveDate) {
+        match self {
+            Period::Week => {
+                let start = date - chrono::Days::new(date.weekday().num_days_from_monday() as u64);
+                (start, start + chrono::Days::new(6))
+            }
+            Period::Month => {
+                let start = date.with_day(1).unwrap();
+                let end = start
+                    .checked_add_months(chrono::Months::new(1))
+                    .and_then(|d| d.pred_opt())
+                    .unwrap_or(NaiveDate::MAX);
+                (start, end)
+            }
+        }
+    }
+}
+
+/// ## Rollup
+///
+///  A review of the journal pages of a week or a month, e.g. for a weekly
+///  retrospective:
+///
+///  - `days`: the date and the description of every journal page of the
+///    period, oldest first, with the log lines in chronological order;
+///  - `notes`: the notes listed on those pages, by title and without
+///    duplicates;
+///  - `trails`: the trails created or modified during the period.
+///
+///  A rollup is built from the vault every time it's needed, and never
+///  saved as a page: it can only be exported (see [export_rollup]).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rollup {
+    pub period: Period,
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    pub days: Vec<(NaiveDate, String)>,
+    pub notes: Vec<String>,
+    pub trails: Vec<String>,
+}
+
+/// "1 note", "3 notes".
+fn count(n: usize, word: &str) -> String {
+    match n {
+        1 => format!("1 {}", word),
+        _ => format!("{} {}s", n, word),
+    }
+}
+
+impl Rollup {
+    /// Gathers the journal pages of the `period` that includes `date`. Pages
+    /// that can't be read are left out.
+    pub fn build(vault: &Vault, period: Period, date: NaiveDate) -> Rollup {
+        let (start, end) = period.range(date);
+        let in_range = |d: NaiveDate| start <= d && d <= end;
+        let resolver = Resolver::build(vault);
+
+        let mut dates: Vec<_> = list_files(vault, Folder::Journal)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|name| journal_date(name))
+            .filter(|d| in_range(*d))
+            .collect();
+        dates.sort();
+
+        let mut days = Vec::new();
+        let mut notes: Vec<String> = Vec::new();
+        for date in dates {
+            if let Ok(page) = load_journal_page(vault, &journal_name(date)) {
+                for target in &page.pages {
+                    let title = String::from(resolver.title(target));
+                    if !notes.contains(&title) {
+                        notes.push(title);
+                    }
+                }
+                days.push((date, page.sorted_description()));
+            }
+        }
+
+        let touched = |stamp: &str| stamp.get(..10).and_then(journal_date).is_some_and(in_range);
+        let mut trails: Vec<_> = list_files(vault, Folder::Trails)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|name| match read_document(vault, Folder::Trails, name) {
+                Ok(doc) => touched(&doc.header.created) || touched(&doc.header.modified),
+                Err(_) => false,
+            })
+            .collect();
+        trails.sort();
+
+        Rollup {
+            period,
+            start,
+            end,
+            days,
+            notes,
+            trails,
+        }
+    }
+    /// E.g. "Week 42 of 2026" or "October 2026".
+    pub fn title(&self) -> String {
+        match self.period {
+            Period::Week => format!(
+                "Week {} of {}",
+                self.start.iso_week().week(),
+                self.start.iso_week().year()
+            ),
+            Period::Month => self.start.format("%B %Y").to_string(),
+        }
+    }
+    /// The name of the exported file, without extension: e.g.
+    /// `journal-2026-W42` or `journal-2026-10`.
+    pub fn name(&self) -> String {
+        match self.period {
+            Period::Week => format!(
+                "journal-{}-W{:02}",
+                self.start.iso_week().year(),
+                self.start.iso_week().week()
+            ),
+            Period::Month => self.start.format("journal-%Y-%m").to_string(),
+        }
+    }
+    fn blocks(&self) -> Vec<Block> {
+        let mut blocks = vec![
+            Block::Title(self.title()),
+            Block::Text(format!(
+                "From {} to {}: {}, {}, {}.",
+                self.start.format("%A %-d %B %Y"),
+                self.end.format("%A %-d %B %Y"),
+                count(self.days.len(), "journal page"),
+                count(self.notes.len(), "note"),
+                count(self.trails.len(), "trail")
+            )),
+        ];
+        for (date, description) in &self.days {
+            blocks.push(Block::Heading(date.format("%A %-d %B").to_string()));
+            blocks.push(Block::Text(match description.trim() {
+                "" => String::from("(no description)"),
+                d => String::from(d),
+            }));
+        }
+        for (heading, list) in [("Notes", &self.notes), ("Trails", &self.trails)] {
+            if !list.is_empty() {
+                blocks.push(Block::Heading(String::from(heading)));
+                blocks.push(Block::List(list.clone()));
+            }
+        }
+        blocks
+    }
+    /// Renders the rollup as a document in the given format.
+    pub fn compile(&self, format: ExportFormat) -> String {
+        let blocks = self.blocks();
+        match format {
+            ExportFormat::Markdown => render_markdown(&blocks),
+            ExportFormat::Text => render_text(&blocks),
+            ExportFormat::Html => render_html(&self.title(), &blocks),
+        }
+    }
+}
+
+/// Writes a rollup as Markdown into the `exports` folder of the vault, and
+/// returns the path of the file.
+pub fn export_rollup(vault: &Vault, rollup: &Rollup) -> Result<PathBuf, FileError> {
+    let path = vault.export_path(&format!(
+        "{}.{}",
+        rollup.name(),
+        ExportFormat::Markdown.extension()
+    ));
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| FileError::write(parent, e))?;
+    }
+    fs::write(&path, rollup.compile(ExportFormat::Markdown))
+        .map_err(|e| FileError::write(&path, e))?;
+    Ok(path)
+}
+
+// TEMPLATES
+
+/// Fills in the placeholders of a page template: `{{date}}` (`YYYY-MM-DD`),
+/// `{{weekday}}` (e.g. `Monday`), `{{title}}` and `{{time}}` (`HH:MM`).
+/// Unknown placeholders are left as they are, and so is the trailing newline
+/// of the file.
+pub fn fill_template(template: &str, title: &str, date: NaiveDate, time: NaiveTime) -> String {
+    let filled =
+        regex!(r"\{\{\s*(\w+)\s*\}\}").replace_all(template, |c: &lazy_regex::Captures| {
+            match &c[1] {
+                "date" => journal_name(date),
+                "weekday" => date.format("%A").to_string(),
+                "title" => String::from(title),
+                "time" => time.format("%H:%M").to_string(),
+                _ => String::from(&c[0]),
+            }
+        });
+
+    String::from(filled.trim_end_matches('\n'))
+}
+
+/// The template of new pages of a kind, filled in for a page named `title`
+/// on `date`. Empty when the vault has no (readable) template for the kind.
+fn apply_template(vault: &Vault, folder: Folder, title: &str, date: NaiveDate) -> String {
+    match fs::read_to_string(vault.template_path(folder)) {
+        Ok(template) => fill_template(&template, title, date, chrono::Local::now().time()),
+        Err(_) => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process;
+
+    /// A vault in a fresh temporary directory, removed when dropped.
+    struct TempVault(Vault);
+
+    impl TempVault {
+        fn new(name: &str) -> TempVault {
+            let root = env::temp_dir().join(format!("vannevar-lib-{}-{}", name, process::id()));
+            let _ = fs::remove_dir_all(&root);
+            TempVault(Vault::open(root).unwrap())
+        }
+    }
+
+    impl Drop for TempVault {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0.root);
+        }
+    }
+
+    fn note(name: &str) -> PageRef {
+        PageRef::new(Folder::Notes, name)
+    }
+
+    /// The names of the pages matching `query`, best first.
+    fn names(index: &Index, query: &str) -> Vec<String> {
+        index
+            .query(query)
+            .into_iter()
+            .map(|(p, _)| p.name)
+            .collect()
+    }
+
+    fn sample_index() -> Index {
+        let mut index = Index::default();
+        index.update(&note("Memex"), "As we may think, the memex stores trails.");
+        index.update(
+            &note("Trails"),
+            "Trailing through notes: we think in trails.",
+        );
+        index.update(
+            &PageRef::new(Folder::Journal, "2024-03-11"),
+            "Read As We May Think again.",
+        );
+        index
+    }
+
+    #[test]
+    fn templates_fill_in_the_placeholders() {
+        let date = NaiveDate::from_ymd_opt(2026, 10, 12).unwrap();
+        let time = NaiveTime::from_hms_opt(9, 5, 0).unwrap();
+
+        let filled = fill_template(
+            "{{title}}: {{weekday}} {{date}} at {{time}} {{place}}\n\n",
+            "Standup",
+            date,
+            time,
+        );
+
+        assert_eq!(filled, "Standup: Monday 2026-10-12 at 09:05 {{place}}");
+    }
+
+    #[test]
+    fn log_lines_are_sorted_by_time() {
+        let mut page = Journal::for_date("2026-10-12");
+        page.description = String::from(
+            "Calibration day.\n14:30 Results in\n09:00 Samples out\n9:15 not a log line",
+        );
+        page.log(NaiveTime::from_hms_opt(11, 5, 0).unwrap(), "Lunch");
+
+        let entries = page.entries();
+
+        let times: Vec<_> = entries
+            .iter()
+            .map(|(t, _)| t.format("%H:%M").to_string())
+            .collect();
+        assert_eq!(times, ["09:00", "11:05", "14:30"]);
+        assert_eq!(page.free_text(), "Calibration day.\n9:15 not a log line");
+        assert_eq!(
+            page.sorted_description(),
+            "Calibration day.\n9:15 not a log line\n09:00 Samples out\n11:05 Lunch\n14:30 Results in"
+        );
+    }
+
+    #[test]
+    fn words_match_their_stem_in_names_and_text() {
+        let index = sample_index();
+
+        // "trailing" and "trails" share the stem "trail", and the name of a
+        // page weighs more than its text
+        assert_eq!(names(&index, "trailing"), ["Trails", "Memex"]);
+        assert_eq!(names(&index, "TRAIL"), ["Trails", "Memex"]);
+        // Every word of the query must match
+        assert_eq!(names(&index, "memex trails"), ["Memex"]);
+        assert!(names(&index, "hypertext").is_empty());
+        assert!(names(&index, "  ").is_empty());
+    }
+
+    #[test]
+    fn phrases_match_words_in_order() {
+        let index = sample_index();
+
+        assert_eq!(
+            names(&index, "\"as we may think\""),
+            ["2024-03-11", "Memex"]
+        );
+        assert!(names(&index, "\"may we think\"").is_empty());
+        assert_eq!(names(&index, "\"we think\" trails"), ["Trails"]);
+        // A quoted single word is just a word
+        assert_eq!(names(&index, "\"stores\""), ["Memex"]);
+    }
+
+    #[test]
+    fn updates_replace_the_previous_text_of_a_page() {
+        let mut index = sample_index();
+
+        assert!(!index.update(&note("Memex"), "As we may think, the memex stores trails."));
+        assert!(index.update(&note("Memex"), "A desk with microfilm."));
+
+        assert_eq!(names(&index, "microfilm"), ["Memex"]);
+        assert!(names(&index, "stores").is_empty());
+        index.remove(&note("Trails"));
+        assert_eq!(names(&index, "think"), ["2024-03-11"]);
+    }
+
+    #[test]
+    fn the_index_is_saved_and_loaded_back() {
+        let vault = TempVault::new("index");
+        let mut index = sample_index();
+        index.remove(&note("Memex"));
+
+        index.save(&vault.0).unwrap();
+        let loaded = Index::load(&vault.0).unwrap();
+
+        // Removed pages are dropped, and the others renumbered
+        assert_eq!(loaded.pages.len(), 2);
+        for query in ["trails", "\"as we may think\"", "memex", "notes think"] {
+            assert_eq!(loaded.query(query), index.query(query), "{}", query);
+        }
+        let text = load_meta(&vault.0, INDEX_SECTION, INDEX_FILE).unwrap();
+        assert!(text.starts_with("vannevar-index 1\np 0 notes "));
+    }
+
+    #[test]
+    fn malformed_indexes_are_rejected() {
+        let vault = TempVault::new("index-malformed");
+        let path = vault.0.meta_path(INDEX_SECTION, INDEX_FILE);
+        for (text, line) in [
+            ("vannevar-index 0\n", 1),
+            ("vannevar-index 1\np 1 notes 42 Memex\n", 2),
+            ("vannevar-index 1\np 0 notes 42 Memex\nw memex 1:0\n", 3),
+            ("vannevar-index 1\np 0 notes 42 Memex\nw memex 0:first\n", 3),
+        ] {
+            save_meta_path(&path, text).unwrap();
+            match Index::load(&vault.0) {
+                Err(FileError::FormatError(_, e)) => assert_eq!(e.line, line, "{:?}", text),
+                Err(e) => panic!("{:?}: unexpected error {:?}", text, e),
+                Ok(_) => panic!("{:?} was loaded", text),
+            }
+        }
+    }
+
+    #[test]
+    fn backlinks_follow_the_saved_pages() {
+        let vault = TempVault::new("backlinks");
+        let v = &vault.0;
+        let mut bush = Note::from_str("Bush", String::from("Imagined the [Memex]."));
+        bush.save(v).unwrap();
+        let mut journal = Journal::from_template(v, "2024-03-11");
+        journal.pages.push(String::from("Memex"));
+        journal.save(v).unwrap();
+        let mut backlinks = Backlinks::build(v);
+
+        // The links were written before the note existed
+        let mut memex = Note::from_str("Memex", String::from("A device."));
+        memex.save(v).unwrap();
+        assert!(backlinks.to(&memex.file).is_empty());
+        backlinks.reload(v, &note(&memex.file));
+        assert_eq!(
+            backlinks.to(&memex.file),
+            vec![
+                note(&bush.file),
+                PageRef::new(Folder::Journal, "2024-03-11")
+            ]
+        );
+
+        bush.text = String::from("Imagined a device.");
+        bush.save(v).unwrap();
+        backlinks.reload(v, &note(&bush.file));
+        assert_eq!(
+            backlinks.to(&memex.file),
+            vec![PageRef::new(Folder::Journal, "2024-03-11")]
+        );
+    }
+
+    #[test]
+    fn shared_titles_pass_to_the_next_note() {
+        let header = |id: &str, title: &str| Header {
+            id: String::from(id),
+            title: String::from(title),
+            ..Header::new(Folder::Notes)
+        };
+        let mut resolver = Resolver::default();
+        resolver.insert("b", &header("2", "Memex"));
+        resolver.insert("a", &header("1", "Memex"));
+        assert_eq!(resolver.resolve("Memex"), Some("a"));
+
+        resolver.remove("a");
+        assert_eq!(resolver.resolve("Memex"), Some("b"));
+        assert_eq!(resolver.resolve("1"), None);
+        resolver.insert("b", &header("2", "Memex, revised"));
+        assert_eq!(resolver.resolve("Memex"), None);
+        assert_eq!(resolver.title("2"), "Memex, revised");
+    }
+
+    #[test]
+    fn saved_pages_are_appended_to_the_index_log() {
+        let vault = TempVault::new("index-log");
+        let v = &vault.0;
+        sample_index().save(v).unwrap();
+        let postings = load_meta(v, INDEX_SECTION, INDEX_FILE).unwrap();
+
+        update_index(
+            v,
+            &index_log_entry(&note("Memex"), Some("A desk with microfilm.")),
+        )
+        .unwrap();
+        update_index(v, &index_log_entry(&note("Trails"), None)).unwrap();
+
+        assert_eq!(load_meta(v, INDEX_SECTION, INDEX_FILE).unwrap(), postings);
+        let index = Index::load(v).unwrap();
+        assert_eq!(names(&index, "microfilm"), vec!["Memex"]);
+        assert!(names(&index, "trails").is_empty());
+        assert_eq!(names(&index, "think"), vec!["2024-03-11"]);
+
+        // The log is folded into the postings once it outgrows them
+        let journal = PageRef::new(Folder::Journal, "2024-03-11");
+        for _ in 0..10 {
+            update_index(v, &index_log_entry(&journal, Some(&postings))).unwrap();
+        }
+        let folded = load_meta(v, INDEX_SECTION, INDEX_FILE).unwrap();
+        let log = load_meta(v, INDEX_SECTION, INDEX_LOG).unwrap_or_default();
+        assert_ne!(folded, postings);
+        assert!(log.len() <= folded.len());
+        let index = Index::load(v).unwrap();
+        assert_eq!(names(&index, "microfilm"), vec!["Memex"]);
+        assert_eq!(index.pages.iter().flatten().count(), 2);
+    }
+
+    #[test]
+    fn malformed_index_logs_are_rejected() {
+        let vault = TempVault::new("index-log-malformed");
+        sample_index().save(&vault.0).unwrap();
+        let path = vault.0.meta_path(INDEX_SECTION, INDEX_LOG);
+        for (text, line) in [
+            ("w memex 0\n", 1),
+            ("p notes 42 Memex\nw memex 0,first\n", 2),
+            ("r books Memex\n", 1),
+            // Cut short by a crash
+            ("p notes 42 Memex\nw memex 0,1", 2),
+        ] {
+            save_meta_path(&path, text).unwrap();
+            match Index::load(&vault.0) {
+                Err(FileError::FormatError(_, e)) => assert_eq!(e.line, line, "{:?}", text),
+                Err(e) => panic!("{:?}: unexpected error {:?}", text, e),
+                Ok(_) => panic!("{:?} was loaded", text),
+            }
+        }
+    }
 }
@@ -1,11 +1,37 @@
-use controller::Controller;
+use cli::{parse_args, Command, USAGE};
+use vannevar::controller::Controller;
+use vannevar::terminal::Terminal;
+use vannevar::Vault;
 
-mod controller;
-mod view;
+use std::env;
+use std::process;
+
+mod cli;
 
-mod lib;
 fn main() {
-    let mut c = Controller::new();
+    let args = match parse_args(env::args().skip(1)) {
+        Ok(a) => a,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+
+    let root = Vault::locate(args.vault);
+    let vault = match Vault::open(root.clone()) {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("Could not open the vault at {}: {}", root.display(), e);
+            process::exit(1);
+        }
+    };
+
+    match args.command {
+        Command::Interactive => {
+            let mut c = Controller::new(vault, Terminal);
 
-    c.execute();
+            c.execute();
+        }
+        command => process::exit(cli::run(&command, &vault)),
+    }
 }
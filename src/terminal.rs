@@ -0,0 +1,2730 @@
+//! # `terminal` module
+//!
+//!  This module contains the [Terminal] backend of the [View] trait, and is
+//!  mostly composed of calls to the functions of the `termion` crate. It's
+//!  only built with the `tui` feature, which is enabled by default.
+//!
+//!  Like all UI code, it's messy.
+//!
+//!  *Since the program can't do much in case of a crash of the terminal emulator
+//!  on which it is running, all the `termion` functions returning a `Result` are
+//!  simply handled with `.unwrap()`. It's ugly but there's little to no utility
+//!  in doing otherwise.*
+extern crate termion;
+
+use termion::event::Key;
+use termion::input::TermRead;
+use termion::raw::IntoRawMode;
+use termion::{clear, color, cursor, style, terminal_size};
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io::{stdin, stdout, Write};
+use std::ops::Range;
+use std::path::PathBuf;
+use std::process::{self, Command};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use chrono::{Datelike, Days, Local, Months, NaiveDate};
+
+use crate::editor::{Buffer, Edit, History};
+use crate::view::*;
+use crate::{
+    fuzzy_filter, journal_name, CompileOptions, EditorKind, ExportFormat, FileError, HealthReport,
+    Journal, Note, PageRef, RenameReport, Rollup, SearchHit, Trail,
+};
+
+/// ## Terminal
+///
+///  The [View] backend drawing every screen on the terminal, in raw mode.
+pub struct Terminal;
+
+/// ## Start page
+///  
+///  This view component is merely an introductory screen and has no other
+///  purpose.
+fn start_page() {
+    let mut stdout = stdout().into_raw_mode().unwrap();
+    let stdin = stdin();
+
+    write!(
+        stdout,
+        "{clear}{cursor}{goto}{red}{bold}VANNEVAR{reset_color}{reset_style}",
+        clear = clear::All,
+        cursor = cursor::Hide,
+        goto = cursor::Goto(
+            terminal_size().unwrap().0 / 2 - 3,
+            terminal_size().unwrap().1 / 2 - 2
+        ),
+        red = color::Fg(color::Red),
+        bold = style::Bold,
+        reset_color = color::Fg(color::Reset),
+        reset_style = style::Reset
+    )
+    .unwrap();
+
+    write!(
+        stdout,
+        "{goto}{white}Press any key to continue.{reset}",
+        // Goto the cell.
+        goto = cursor::Goto(
+            terminal_size().unwrap().0 / 2 - 13,
+            terminal_size().unwrap().1 / 2 + 2
+        ),
+        white = color::Fg(color::White),
+        reset = color::Fg(color::Reset)
+    )
+    .unwrap();
+
+    stdout.flush().unwrap();
+
+    stdin.keys().next();
+}
+
+/// ## Display journal
+///
+///  This component represents the main interface of the journal mode. It presents
+///  the current journal page (the one for the current day, by default).
+///
+///  It shows the following elements of the journal page:
+///
+///  - the date;
+///  - the description;
+///  - the pages created during the day, in a list that scrolls with the
+///    arrow keys, `Home`, `End`, `PgUp` and `PgDn`.
+///
+///  It allows access to other pages:
+///  - by pressing `Enter`, it opens the selected page of the list;
+///  - by pressing `d` or `D`, it opens the description editing menu;
+///  - by pressing `e' or `E`, it opens a page to create new links;
+///  - by pressing `a` or `A`, it asks for a line to log, stamped with the
+///    current time;
+///  - by pressing `m' or `M`, it goes back to the main menu;
+///  - by pressing `l' or `L`, it opens the link jump menu;
+///  - by pressing `f` or `F`, it opens the fuzzy finder;
+///  - by pressing `p`, `P` or the left arrow, it goes to the previous journal
+///    page, skipping the days without one;
+///  - by pressing `n`, `N` or the right arrow, it goes to the next journal page;
+///  - by pressing `t` or `T`, it jumps to the journal page of the current day;
+///  - by pressing `r` or `R`, it shows the rollup of the week of the page;
+///  - by pressing `q` or `Q`, it quits the application.
+///
+///  The Display journal view communicates with the controller through
+///  [JournalMessage] messages.
+fn display_journal(page: &Journal) -> JournalMessage {
+    let mut stdout = stdout().into_raw_mode().unwrap();
+    let stdin = stdin();
+    let mut keys = stdin.keys();
+    let mut scroll_list = ScrollList::new();
+    let links: Vec<_> = page.pages.iter().map(|l| format!("[{}]", l)).collect();
+
+    loop {
+        // Write the date of the journal page
+        write!(
+            stdout,
+            "{clear}{cursor}{goto}{red}{bold}{date}{reset_color}{reset_style}",
+            clear = clear::All,
+            cursor = cursor::Hide,
+            goto = cursor::Goto(1, 1),
+            red = color::Fg(color::Red),
+            bold = style::Bold,
+            reset_color = color::Fg(color::Reset),
+            reset_style = style::Reset,
+            date = page.date
+        )
+        .unwrap();
+
+        write!(
+            stdout,
+            "{goto}{bold}Description: {reset_style}",
+            goto = cursor::Goto(1, 3),
+            bold = style::Bold,
+            reset_style = style::Reset,
+        )
+        .unwrap();
+
+        // Write the free text of the description, followed by the log of the
+        // day in chronological order
+        let mut lines = Vec::new();
+        let free_text = page.free_text();
+        if !free_text.is_empty() {
+            lines.push(free_text);
+        }
+        for (time, text) in page.entries() {
+            lines.push(format!("{}  {}", time.format("%H:%M"), text));
+        }
+        let text_rows = write_text(&mut stdout, &lines.join("\n"), 4);
+
+        // Write the page list for the day
+        write!(
+            stdout,
+            "{goto}{bold}Pages created or edited today:{reset_style}",
+            goto = cursor::Goto(1, 5 + text_rows),
+            bold = style::Bold,
+            reset_style = style::Reset,
+        )
+        .unwrap();
+
+        let page_len = write_scroll_list(
+            &mut stdout,
+            &mut scroll_list,
+            &links,
+            6 + text_rows,
+            "(Enter to open the selected page)",
+        );
+        stdout.flush().unwrap();
+
+        // Key event handler
+        match keys.next() {
+            Some(k) => match k.unwrap() {
+                Key::Char('\n') if !links.is_empty() => {
+                    return JournalMessage::OpenLink(scroll_list.selected)
+                }
+                Key::Char(c) => match c {
+                    'd' | 'D' => return JournalMessage::EditDescription,
+                    'e' | 'E' => return JournalMessage::EditLinks,
+                    'm' | 'M' => return JournalMessage::Menu,
+                    'l' | 'L' => return JournalMessage::SelectLinks,
+                    'f' | 'F' => return JournalMessage::Find,
+                    'p' | 'P' => return JournalMessage::Previous,
+                    'n' | 'N' => return JournalMessage::Next,
+                    't' | 'T' => return JournalMessage::Today,
+                    'r' | 'R' => return JournalMessage::Review,
+                    'a' | 'A' => return JournalMessage::Log,
+                    'q' | 'Q' => return JournalMessage::Exit,
+                    _ => {}
+                },
+                Key::Left => return JournalMessage::Previous,
+                Key::Right => return JournalMessage::Next,
+                key => {
+                    scroll_key(&mut scroll_list, &key, links.len(), page_len);
+                }
+            },
+            None => return JournalMessage::Exit,
+        }
+    }
+}
+
+/// ## Select create journal
+///
+///  This component is displayed when the user tries to open the journal page
+///  of a day (usually the current one), and it doesn't exist. The user is
+///  faced with the choice of creating one, or going back.
+fn select_create_journal(date: &str) -> bool {
+    let mut stdout = stdout().into_raw_mode().unwrap();
+    let stdin = stdin();
+    let day = match journal_name(Local::now().date_naive()) == date {
+        true => String::from("today"),
+        false => String::from(date),
+    };
+    let message = format!("The journal page for {} does not exist.", day);
+
+    write!(
+        stdout,
+        "{clear}{goto}{message}",
+        clear = clear::All,
+        goto = cursor::Goto(
+            (terminal_size().unwrap().0 / 2)
+                .saturating_sub(message.len() as u16 / 2)
+                .max(1),
+            terminal_size().unwrap().1 / 2
+        ),
+        message = message
+    )
+    .unwrap();
+
+    write!(
+        stdout,
+        "{goto}Create it? (y/n)",
+        goto = cursor::Goto(
+            terminal_size().unwrap().0 / 2 - 8,
+            terminal_size().unwrap().1 / 2 + 1
+        )
+    )
+    .unwrap();
+
+    stdout.flush().unwrap();
+
+    let choice: bool = false;
+
+    // Key event handler
+    for c in stdin.keys() {
+        if let Key::Char(c) = c.unwrap() {
+            match c {
+                'y' | 'Y' => return true,
+                'n' | 'N' => return false,
+                _ => {}
+            }
+        }
+    }
+
+    choice
+}
+
+/// Width of a day in the [calendar]: the day of the month, the number of
+/// notes in brackets and a space.
+const CALENDAR_CELL: u16 = 7;
+
+/// ## Calendar
+///
+///  Shows the month of the `selected` day as a grid, one week per row,
+///  starting on Monday. The days with a journal page are highlighted and
+///  followed by the number of notes listed on them, and today is underlined.
+///
+///  - the left and right arrows move by a day, up and down by a week;
+///  - `PgUp` and `PgDn` move by a month;
+///  - `t` jumps to today;
+///  - `Enter` opens the selected day, even if it has no page yet;
+///  - `r` shows the rollup of the week of the selected day;
+///  - `Esc` goes back;
+///  - `q` or `Q` quits the application.
+fn calendar(selected: NaiveDate, days: &BTreeMap<NaiveDate, usize>) -> CalendarMessage {
+    let mut stdout = stdout().into_raw_mode().unwrap();
+    let stdin = stdin();
+    let mut keys = stdin.keys();
+    let today = Local::now().date_naive();
+    let mut selected = selected;
+
+    loop {
+        let (width, height) = terminal_size().unwrap();
+        let left = (width / 2).saturating_sub(CALENDAR_CELL * 7 / 2).max(1);
+        let first = selected.with_day(1).unwrap();
+        let month = first.format("%B %Y").to_string();
+
+        write!(
+            stdout,
+            "{clear}{cursor}{goto}{red}{bold}JOURNAL{reset_color}{reset_style}{goto_month}{bold}{month}{reset_style}",
+            clear = clear::All,
+            cursor = cursor::Hide,
+            goto = cursor::Goto((width / 2).saturating_sub(3).max(1), 1),
+            red = color::Fg(color::Red),
+            bold = style::Bold,
+            reset_color = color::Fg(color::Reset),
+            reset_style = style::Reset,
+            goto_month = cursor::Goto((width / 2).saturating_sub(month.len() as u16 / 2).max(1), 3),
+            month = month
+        )
+        .unwrap();
+
+        for (i, name) in ["Mo", "Tu", "We", "Th", "Fr", "Sa", "Su"]
+            .iter()
+            .enumerate()
+        {
+            write!(
+                stdout,
+                "{}{}",
+                cursor::Goto(left + i as u16 * CALENDAR_CELL, 5),
+                name
+            )
+            .unwrap();
+        }
+
+        let offset = first.weekday().num_days_from_monday();
+        for day in first.iter_days().take_while(|d| d.month() == first.month()) {
+            let cell = offset + day.day0();
+            let label = match days.get(&day) {
+                Some(notes) => format!("{:>2}({})", day.day(), notes),
+                None => format!("{:>2}", day.day()),
+            };
+            let mut styles = String::new();
+            if days.contains_key(&day) {
+                styles.push_str(&format!("{}{}", color::Fg(color::Green), style::Bold));
+            }
+            if day == today {
+                styles.push_str(style::Underline.as_ref());
+            }
+            if day == selected {
+                styles.push_str(style::Invert.as_ref());
+            }
+            write!(
+                stdout,
+                "{goto}{styles}{label}{reset_color}{reset_style}",
+                goto = cursor::Goto(
+                    left + (cell % 7) as u16 * CALENDAR_CELL,
+                    6 + (cell / 7) as u16
+                ),
+                styles = styles,
+                label = label,
+                reset_color = color::Fg(color::Reset),
+                reset_style = style::Reset
+            )
+            .unwrap();
+        }
+
+        let status = match days.get(&selected) {
+            Some(1) => String::from("1 note"),
+            Some(notes) => format!("{} notes", notes),
+            None => String::from("no journal page, press Enter to create it"),
+        };
+        write!(
+            stdout,
+            "{goto}{bold}{date}{reset_style}: {status}",
+            goto = cursor::Goto(left, 13),
+            bold = style::Bold,
+            reset_style = style::Reset,
+            date = selected.format("%A %-d %B %Y"),
+            status = status
+        )
+        .unwrap();
+
+        write!(
+            stdout,
+            "{goto}Arrows to move, PgUp/PgDn to change month, (t) today, (r) review, Enter to open, Esc to go back",
+            goto = cursor::Goto(1, height)
+        )
+        .unwrap();
+        stdout.flush().unwrap();
+
+        let moved = match keys.next() {
+            Some(k) => match k.unwrap() {
+                Key::Left => selected.pred_opt(),
+                Key::Right => selected.succ_opt(),
+                Key::Up => selected.checked_sub_days(Days::new(7)),
+                Key::Down => selected.checked_add_days(Days::new(7)),
+                Key::PageUp => selected.checked_sub_months(Months::new(1)),
+                Key::PageDown => selected.checked_add_months(Months::new(1)),
+                Key::Char('t') | Key::Char('T') => Some(today),
+                Key::Char('\n') => return CalendarMessage::Open(selected),
+                Key::Char('r') | Key::Char('R') => return CalendarMessage::Review(selected),
+                Key::Char('q') | Key::Char('Q') => return CalendarMessage::Exit,
+                Key::Esc => return CalendarMessage::Back,
+                _ => None,
+            },
+            None => return CalendarMessage::Exit,
+        };
+        if let Some(day) = moved {
+            selected = day;
+        }
+    }
+}
+
+/// ## Display rollup
+///
+///  Shows a [Rollup] of the journal pages of a week or a month, read-only:
+///  the descriptions of the pages under their dates, then the notes and the
+///  trails touched during the period.
+///
+///  - the up and down arrows, `PgUp` and `PgDn` scroll the text;
+///  - `p` (or the left arrow) and `n` (or the right arrow) go to the
+///    previous and the next period;
+///  - `w` and `m` switch to the week or the month;
+///  - `x` exports the rollup as Markdown;
+///  - `Esc` goes back;
+///  - `q` or `Q` quits the application.
+fn display_rollup(rollup: &Rollup) -> RollupMessage {
+    let mut stdout = stdout().into_raw_mode().unwrap();
+    let stdin = stdin();
+    let mut keys = stdin.keys();
+    let buffer = Buffer::new(&rollup.compile(ExportFormat::Text));
+    let mut top = 0;
+
+    loop {
+        let (width, height) = terminal_size().unwrap();
+        let rows = buffer.layout((width as usize).max(1));
+        let page = (height as usize).saturating_sub(2).max(1);
+        top = top.min(rows.len().saturating_sub(page));
+
+        write!(stdout, "{}{}", clear::All, cursor::Hide).unwrap();
+        for (i, row) in rows.iter().skip(top).take(page).enumerate() {
+            write!(
+                stdout,
+                "{}{}",
+                cursor::Goto(1, 1 + i as u16),
+                buffer.row_text(row)
+            )
+            .unwrap();
+        }
+        write!(
+            stdout,
+            "{goto}{bold}(p/n) previous/next, (w) week, (m) month, (x) export, Esc to go back{reset_style}",
+            goto = cursor::Goto(1, height),
+            bold = style::Bold,
+            reset_style = style::Reset
+        )
+        .unwrap();
+        stdout.flush().unwrap();
+
+        match keys.next() {
+            Some(k) => match k.unwrap() {
+                Key::Char('p') | Key::Char('P') | Key::Left => return RollupMessage::Previous,
+                Key::Char('n') | Key::Char('N') | Key::Right => return RollupMessage::Next,
+                Key::Char('w') | Key::Char('W') => return RollupMessage::Week,
+                Key::Char('m') | Key::Char('M') => return RollupMessage::Month,
+                Key::Char('x') | Key::Char('X') => return RollupMessage::Export,
+                Key::Char('q') | Key::Char('Q') => return RollupMessage::Exit,
+                Key::Esc => return RollupMessage::Back,
+                Key::Up => top = top.saturating_sub(1),
+                Key::Down => top += 1,
+                Key::PageUp => top = top.saturating_sub(page),
+                Key::PageDown => top += page,
+                _ => {}
+            },
+            None => return RollupMessage::Exit,
+        }
+    }
+}
+
+/// Moves the selection of a [ScrollList] with the arrow keys, `Home`,
+/// `End`, `PgUp` and `PgDn`, in a list of `len` items of which `page` fit on
+/// screen. Returns `false` if the key doesn't move the selection.
+fn scroll_key(list: &mut ScrollList, key: &Key, len: usize, page: usize) -> bool {
+    let scroll = match key {
+        Key::Up => Scroll::Up,
+        Key::Down => Scroll::Down,
+        Key::PageUp => Scroll::PageUp,
+        Key::PageDown => Scroll::PageDown,
+        Key::Home => Scroll::First,
+        Key::End => Scroll::Last,
+        _ => return false,
+    };
+    list.scroll(scroll, len, page);
+    true
+}
+
+/// Writes the position of the viewport of a list (e.g. `11-20 of 57`) and a
+/// reminder of the keys on row `row`.
+fn write_list_footer<W: Write>(
+    stdout: &mut W,
+    row: u16,
+    visible: &Range<usize>,
+    len: usize,
+    keys: &str,
+) {
+    let position = match len {
+        0 => String::from("0 of 0"),
+        _ => format!("{}-{} of {}", visible.start + 1, visible.end, len),
+    };
+    write!(
+        stdout,
+        "{goto}{clear}{bold}{position}{reset_style}  {keys}",
+        goto = cursor::Goto(1, row),
+        clear = clear::CurrentLine,
+        bold = style::Bold,
+        reset_style = style::Reset,
+        position = position,
+        keys = keys
+    )
+    .unwrap();
+}
+
+/// Writes an item of a list on row `row`, highlighted if it is selected.
+fn write_list_item<W: Write>(stdout: &mut W, row: u16, text: &str, selected: bool) {
+    let width = terminal_size().unwrap().0 as usize;
+    let text: String = text.chars().take(width.saturating_sub(2)).collect();
+    match selected {
+        true => write!(
+            stdout,
+            "{goto}{bold}> {invert}{text}{reset_style}",
+            goto = cursor::Goto(1, row),
+            bold = style::Bold,
+            invert = style::Invert,
+            reset_style = style::Reset,
+            text = text
+        ),
+        false => write!(
+            stdout,
+            "{goto}  {text}",
+            goto = cursor::Goto(1, row),
+            text = text
+        ),
+    }
+    .unwrap();
+}
+
+/// Draws the items of a list from row `top` down to the row above the
+/// footer, scrolled so that the selected one is visible, and the footer with
+/// the position of the viewport. Returns the number of items that fit.
+fn write_scroll_list<W: Write>(
+    stdout: &mut W,
+    list: &mut ScrollList,
+    items: &[String],
+    top: u16,
+    keys: &str,
+) -> usize {
+    let height = terminal_size().unwrap().1;
+    let page = height.saturating_sub(top) as usize;
+    let visible = list.viewport(items.len(), page);
+
+    if items.is_empty() && page > 0 {
+        write!(stdout, "{}(nothing yet)", cursor::Goto(1, top)).unwrap();
+    }
+    for i in visible.clone().take(page) {
+        write_list_item(
+            stdout,
+            top + (i - visible.start) as u16,
+            &items[i],
+            i == list.selected,
+        );
+    }
+    write_list_footer(stdout, height, &visible, items.len(), keys);
+
+    page
+}
+
+/// ## Link menu
+///
+///  Shows a list of pages under `heading`, scrolling it when it doesn't fit
+///  on screen.
+///
+///  - the arrow keys, `Home`, `End`, `PgUp` and `PgDn` move the selection;
+///  - `Enter` opens the selected page;
+///  - `Esc` goes back;
+///  - `q` or `Q` quits the application.
+///
+///  The index of the selected item is sent back in [LinkMessage::GotoLink].
+fn link_menu(heading: &str, list: &[String]) -> LinkMessage {
+    let mut stdout = stdout().into_raw_mode().unwrap();
+    let stdin = stdin();
+    let mut keys = stdin.keys();
+    let mut scroll_list = ScrollList::new();
+
+    loop {
+        let (width, height) = terminal_size().unwrap();
+        let page = (height as usize).saturating_sub(4);
+        let visible = scroll_list.viewport(list.len(), page);
+
+        write!(
+            stdout,
+            "{clear}{cursor}{goto}{red}{bold}{heading}{reset_color}{reset_style}",
+            clear = clear::All,
+            cursor = cursor::Hide,
+            goto = cursor::Goto(
+                (width / 2).saturating_sub(heading.len() as u16 / 2).max(1),
+                1
+            ),
+            red = color::Fg(color::Red),
+            bold = style::Bold,
+            reset_color = color::Fg(color::Reset),
+            reset_style = style::Reset,
+            heading = heading
+        )
+        .unwrap();
+
+        if list.is_empty() {
+            write!(stdout, "{}(nothing yet)", cursor::Goto(1, 3)).unwrap();
+        }
+        for i in visible.clone() {
+            write_list_item(
+                &mut stdout,
+                3 + (i - visible.start) as u16,
+                &list[i],
+                i == scroll_list.selected,
+            );
+        }
+
+        write_list_footer(
+            &mut stdout,
+            height,
+            &visible,
+            list.len(),
+            "(Enter to open, Esc to go back)",
+        );
+        stdout.flush().unwrap();
+
+        match keys.next() {
+            Some(k) => match k.unwrap() {
+                Key::Char('\n') => {
+                    if !list.is_empty() {
+                        return LinkMessage::GotoLink(scroll_list.selected);
+                    }
+                }
+                Key::Char('q') | Key::Char('Q') => return LinkMessage::Exit,
+                Key::Esc => return LinkMessage::Back,
+                key => {
+                    scroll_key(&mut scroll_list, &key, list.len(), page);
+                }
+            },
+            None => return LinkMessage::Exit,
+        }
+    }
+}
+
+/// ## Health check
+///
+///  Lists the problems found by a check of the vault, errors and warnings
+///  alike, below a summary line. `Enter` opens the page the selected
+///  problem was found in.
+fn health_check(report: &HealthReport) -> LinkMessage {
+    let mut stdout = stdout().into_raw_mode().unwrap();
+    let stdin = stdin();
+    let mut keys = stdin.keys();
+    let mut scroll_list = ScrollList::new();
+    let problems = &report.problems;
+
+    loop {
+        let (width, height) = terminal_size().unwrap();
+        let page = (height as usize).saturating_sub(6);
+        let visible = scroll_list.viewport(problems.len(), page);
+
+        write!(
+            stdout,
+            "{clear}{cursor}{goto}{red}{bold}HEALTH CHECK{reset_color}{reset_style}",
+            clear = clear::All,
+            cursor = cursor::Hide,
+            goto = cursor::Goto((width / 2).saturating_sub(6).max(1), 1),
+            red = color::Fg(color::Red),
+            bold = style::Bold,
+            reset_color = color::Fg(color::Reset),
+            reset_style = style::Reset
+        )
+        .unwrap();
+
+        let summary = match problems.len() {
+            0 => format!("No problems found in {} pages.", report.pages),
+            _ => format!(
+                "Checked {} pages: {} errors, {} warnings.",
+                report.pages,
+                report.errors(),
+                report.warnings()
+            ),
+        };
+        write!(stdout, "{}{}", cursor::Goto(1, 3), summary).unwrap();
+
+        for i in visible.clone() {
+            let problem = &problems[i];
+            let text = match problem.is_warning() {
+                true => format!("warning: {}", problem),
+                false => format!("error: {}", problem),
+            };
+            write_list_item(
+                &mut stdout,
+                5 + (i - visible.start) as u16,
+                &text,
+                i == scroll_list.selected,
+            );
+        }
+
+        write_list_footer(
+            &mut stdout,
+            height,
+            &visible,
+            problems.len(),
+            "(Enter to open the page, Esc to go back)",
+        );
+        stdout.flush().unwrap();
+
+        match keys.next() {
+            Some(k) => match k.unwrap() {
+                Key::Char('\n') => {
+                    if !problems.is_empty() {
+                        return LinkMessage::GotoLink(scroll_list.selected);
+                    }
+                }
+                Key::Char('q') | Key::Char('Q') => return LinkMessage::Exit,
+                Key::Esc => return LinkMessage::Back,
+                key => {
+                    scroll_key(&mut scroll_list, &key, problems.len(), page);
+                }
+            },
+            None => return LinkMessage::Exit,
+        }
+    }
+}
+
+/// Writes `text` starting from row `top`, wrapping it to the width of the
+/// terminal and honoring its newlines. Returns the number of rows used.
+fn write_text<W: Write>(stdout: &mut W, text: &str, top: u16) -> u16 {
+    let width = (terminal_size().unwrap().0 as usize).max(1);
+    let buffer = Buffer::new(text);
+    let rows = buffer.layout(width);
+
+    for (i, row) in rows.iter().enumerate() {
+        write!(
+            stdout,
+            "{goto}{text}",
+            goto = cursor::Goto(1, top + i as u16),
+            text = buffer.row_text(row)
+        )
+        .unwrap();
+    }
+
+    rows.len() as u16
+}
+
+/// ## Display note
+///
+///  This component shows a note: its title, its text and, below them, the
+///  list of the pages that link to it (the *backlinks*), which scrolls with
+///  the arrow keys, `Home`, `End`, `PgUp` and `PgDn`.
+///
+///  - by pressing `Enter`, it saves the note and opens the selected backlink;
+///  - by pressing `e` or `E`, it opens the editor;
+///  - by pressing `r` or `R`, it renames the note;
+///  - by pressing `l` or `L`, it opens the menu of the outgoing links;
+///  - by pressing `b` or `B`, it opens the menu of the backlinks;
+///  - by pressing `f` or `F`, it opens the fuzzy finder;
+///  - by pressing `m` or `M`, it goes back to the main menu;
+///  - by pressing `Esc`, it goes back to the journal;
+///  - by pressing `q` or `Q`, it quits the application.
+fn display_note(page: &Note, backlinks: &[PageRef]) -> NoteMessage {
+    let mut stdout = stdout().into_raw_mode().unwrap();
+    let stdin = stdin();
+    let mut keys = stdin.keys();
+    let mut scroll_list = ScrollList::new();
+    let labels: Vec<_> = backlinks.iter().map(|p| p.label()).collect();
+
+    loop {
+        write!(
+            stdout,
+            "{clear}{cursor}{goto}{red}{bold}{title}{reset_color}{reset_style}",
+            clear = clear::All,
+            cursor = cursor::Hide,
+            goto = cursor::Goto(1, 1),
+            red = color::Fg(color::Red),
+            bold = style::Bold,
+            reset_color = color::Fg(color::Reset),
+            reset_style = style::Reset,
+            title = page.title
+        )
+        .unwrap();
+
+        let text_rows = write_text(&mut stdout, &page.text, 3);
+
+        // Write the list of the pages linking to this note
+        let offset = 4 + text_rows;
+
+        write!(
+            stdout,
+            "{goto}{bold}Referenced by:{reset_style}",
+            goto = cursor::Goto(1, offset),
+            bold = style::Bold,
+            reset_style = style::Reset,
+        )
+        .unwrap();
+
+        let page_len = write_scroll_list(
+            &mut stdout,
+            &mut scroll_list,
+            &labels,
+            offset + 1,
+            "(Enter to open the selected page)",
+        );
+        stdout.flush().unwrap();
+
+        match keys.next() {
+            Some(k) => match k.unwrap() {
+                Key::Char('\n') if !labels.is_empty() => {
+                    return NoteMessage::OpenBacklink(scroll_list.selected)
+                }
+                Key::Char(c) => match c {
+                    'e' | 'E' => return NoteMessage::Edit,
+                    'r' | 'R' => return NoteMessage::Rename,
+                    'l' | 'L' => return NoteMessage::SelectLinks,
+                    'b' | 'B' => return NoteMessage::SelectBacklinks,
+                    'f' | 'F' => return NoteMessage::Find,
+                    'm' | 'M' => return NoteMessage::Menu,
+                    'q' | 'Q' => return NoteMessage::Exit,
+                    _ => {}
+                },
+                Key::Esc => return NoteMessage::Back,
+                key => {
+                    scroll_key(&mut scroll_list, &key, labels.len(), page_len);
+                }
+            },
+            None => return NoteMessage::Back,
+        }
+    }
+}
+
+/// ## Display trail
+///
+///  This component shows a trail: its name, its description and its hops,
+///  numbered from 1 in a list that scrolls with the up and down arrows,
+///  `Home`, `End`, `PgUp` and `PgDn`.
+///
+///  - by pressing `Enter`, it opens the note of the selected hop;
+///  - by pressing `d` or `D`, it edits the description;
+///  - by pressing `e` or `E`, it adds a hop;
+///  - by pressing `r` or `R`, it opens the hop editor;
+///  - by pressing `w` or `W`, it walks the trail;
+///  - by pressing `x` or `X`, it exports the trail;
+///  - by pressing `l` or `L`, it opens the link jump menu;
+///  - by pressing `f` or `F`, it opens the fuzzy finder;
+///  - by pressing `m` or `M`, it goes back to the main menu;
+///  - by pressing `q` or `Q`, it quits the application.
+fn display_trail(page: &Trail) -> TrailMessage {
+    let mut stdout = stdout().into_raw_mode().unwrap();
+    let stdin = stdin();
+    let mut keys = stdin.keys();
+    let mut scroll_list = ScrollList::new();
+    let len = page.hops.len();
+
+    loop {
+        write!(
+            stdout,
+            "{clear}{cursor}{goto}{red}{bold}{name}{reset_color}{reset_style}",
+            clear = clear::All,
+            cursor = cursor::Hide,
+            goto = cursor::Goto(1, 1),
+            red = color::Fg(color::Red),
+            bold = style::Bold,
+            reset_color = color::Fg(color::Reset),
+            reset_style = style::Reset,
+            name = page.name
+        )
+        .unwrap();
+
+        write!(
+            stdout,
+            "{goto}{bold}Description: {reset_style}",
+            goto = cursor::Goto(1, 3),
+            bold = style::Bold,
+            reset_style = style::Reset,
+        )
+        .unwrap();
+
+        let top = 5 + write_text(&mut stdout, &page.description, 4);
+        let height = terminal_size().unwrap().1;
+        // Every hop takes two rows: its name and its description.
+        let page_len = height.saturating_sub(top) as usize / 2;
+        let visible = scroll_list.viewport(len, page_len);
+
+        if len == 0 {
+            write!(
+                stdout,
+                "{}(no hops yet: press e to add one)",
+                cursor::Goto(1, top)
+            )
+            .unwrap();
+        }
+        for i in visible.clone().take(page_len) {
+            let row = top + 2 * (i - visible.start) as u16;
+            let (name, description) = &page.hops[i];
+            write_list_item(
+                &mut stdout,
+                row,
+                &format!("Hop {}: {}", i + 1, name),
+                i == scroll_list.selected,
+            );
+            write!(
+                stdout,
+                "{goto}    {italic}{description}{reset_style}",
+                goto = cursor::Goto(1, row + 1),
+                italic = style::Italic,
+                reset_style = style::Reset,
+                description = description
+            )
+            .unwrap();
+        }
+
+        write_list_footer(
+            &mut stdout,
+            height,
+            &visible,
+            len,
+            "(Enter to open the selected hop)",
+        );
+        stdout.flush().unwrap();
+
+        match keys.next() {
+            Some(k) => match k.unwrap() {
+                Key::Char('\n') if len > 0 => return TrailMessage::OpenHop(scroll_list.selected),
+                Key::Char(c) => match c {
+                    'l' | 'L' => return TrailMessage::SelectLink,
+                    'm' | 'M' => return TrailMessage::MainMenu,
+                    'q' | 'Q' => return TrailMessage::Quit,
+                    'd' | 'D' => return TrailMessage::EditDescription,
+                    'e' | 'E' => return TrailMessage::AddLink,
+                    'r' | 'R' => return TrailMessage::EditHops,
+                    'w' | 'W' => return TrailMessage::Walk,
+                    'x' | 'X' => return TrailMessage::Export,
+                    'f' | 'F' => return TrailMessage::Find,
+                    _ => {}
+                },
+                key => {
+                    scroll_key(&mut scroll_list, &key, len, page_len);
+                }
+            },
+            None => return TrailMessage::Quit,
+        }
+    }
+}
+
+/// ## Walk trail
+///
+///  Shows the note of hop number `hop` of a trail full-screen, under the
+///  description of the hop, so that the trail can be read from start to end
+///  like a single document. A progress bar shows how far along the trail the
+///  reader is.
+///
+///  - by pressing `n`, `Space` or the right arrow, it goes to the next hop;
+///  - by pressing `p` or the left arrow, it goes to the previous hop;
+///  - by pressing `t`, `T` or `Esc`, it goes back to the trail overview;
+///  - by pressing `f` or `F`, it opens the fuzzy finder;
+///  - by pressing `m` or `M`, it goes back to the main menu;
+///  - by pressing `q` or `Q`, it quits the application.
+///
+///  `note` is `None` when the note of the hop doesn't exist yet.
+fn walk_trail(trail: &Trail, hop: usize, note: Option<&Note>) -> WalkMessage {
+    let mut stdout = stdout().into_raw_mode().unwrap();
+    let stdin = stdin();
+
+    let total = trail.hops.len();
+    let (name, description) = &trail.hops[hop];
+    let bar_width = 20;
+    let done = (hop + 1) * bar_width / total.max(1);
+
+    write!(
+        stdout,
+        "{clear}{cursor}{goto}{red}{bold}{trail}{reset_color}{reset_style}  hop {number} of {total}  [{done}{todo}]",
+        clear = clear::All,
+        cursor = cursor::Hide,
+        goto = cursor::Goto(1, 1),
+        red = color::Fg(color::Red),
+        bold = style::Bold,
+        reset_color = color::Fg(color::Reset),
+        reset_style = style::Reset,
+        trail = trail.name,
+        number = hop + 1,
+        total = total,
+        done = "#".repeat(done),
+        todo = "-".repeat(bar_width - done)
+    )
+    .unwrap();
+
+    write!(
+        stdout,
+        "{goto}{italic}{description}{reset_style}",
+        goto = cursor::Goto(1, 3),
+        italic = style::Italic,
+        reset_style = style::Reset,
+        description = description
+    )
+    .unwrap();
+
+    write!(
+        stdout,
+        "{goto}{bold}{name}{reset_style}",
+        goto = cursor::Goto(1, 5),
+        bold = style::Bold,
+        reset_style = style::Reset,
+        name = name
+    )
+    .unwrap();
+
+    match note {
+        Some(n) => {
+            write_text(&mut stdout, &n.text, 7);
+        }
+        None => write!(
+            stdout,
+            "{}(this note doesn't exist yet)",
+            cursor::Goto(1, 7)
+        )
+        .unwrap(),
+    }
+
+    let hint = match hop + 1 == total {
+        true => "End of the trail. (p) previous, (t) back to the trail",
+        false => "(n) next, (p) previous, (t) back to the trail",
+    };
+    write!(
+        stdout,
+        "{goto}{bold}{hint}{reset_style}",
+        goto = cursor::Goto(1, terminal_size().unwrap().1),
+        bold = style::Bold,
+        reset_style = style::Reset,
+        hint = hint
+    )
+    .unwrap();
+
+    stdout.flush().unwrap();
+
+    for k in stdin.keys() {
+        match k.unwrap() {
+            Key::Char(c) => match c {
+                'n' | 'N' | ' ' => return WalkMessage::Next,
+                'p' | 'P' => return WalkMessage::Previous,
+                't' | 'T' => return WalkMessage::Overview,
+                'f' | 'F' => return WalkMessage::Find,
+                'm' | 'M' => return WalkMessage::Menu,
+                'q' | 'Q' => return WalkMessage::Exit,
+                _ => {}
+            },
+            Key::Right => return WalkMessage::Next,
+            Key::Left => return WalkMessage::Previous,
+            Key::Esc => return WalkMessage::Overview,
+            _ => {}
+        }
+    }
+
+    WalkMessage::Exit
+}
+
+/// ## Export options
+///
+///  Lets the user choose how to compile the trail `name` into a document,
+///  toggling each option with its key. Returns `false` if the user gave up.
+///
+///  - `h`, `s` and `l` toggle the headings, the source list and the
+///    stripping of link brackets;
+///  - `1`, `2` and `3` toggle the Markdown, plain text and HTML files;
+///  - `Enter` compiles the trail;
+///  - `Esc` goes back to the trail.
+fn export_options(name: &str, options: &mut CompileOptions) -> bool {
+    let mut stdout = stdout().into_raw_mode().unwrap();
+    let stdin = stdin();
+    let mut keys = stdin.keys();
+
+    loop {
+        write!(
+            stdout,
+            "{clear}{cursor}{goto}{red}{bold}EXPORT {name}{reset_color}{reset_style}",
+            clear = clear::All,
+            cursor = cursor::Hide,
+            goto = cursor::Goto(1, 1),
+            red = color::Fg(color::Red),
+            bold = style::Bold,
+            reset_color = color::Fg(color::Reset),
+            reset_style = style::Reset,
+            name = name
+        )
+        .unwrap();
+
+        let check = |b: bool| match b {
+            true => "[x]",
+            false => "[ ]",
+        };
+        let lines = [
+            format!("(h) {} A heading for every hop", check(options.headings)),
+            format!("(s) {} List of sources at the end", check(options.sources)),
+            format!(
+                "(l) {} Strip the brackets of [links]",
+                check(options.strip_links)
+            ),
+            String::new(),
+            format!(
+                "(1) {} Markdown",
+                check(options.formats.contains(&ExportFormat::Markdown))
+            ),
+            format!(
+                "(2) {} Plain text",
+                check(options.formats.contains(&ExportFormat::Text))
+            ),
+            format!(
+                "(3) {} HTML",
+                check(options.formats.contains(&ExportFormat::Html))
+            ),
+        ];
+        for (i, line) in lines.iter().enumerate() {
+            write!(stdout, "{}{}", cursor::Goto(1, 3 + i as u16), line).unwrap();
+        }
+
+        write!(
+            stdout,
+            "{goto}{bold}Enter to export, Esc to go back{reset_style}",
+            goto = cursor::Goto(1, terminal_size().unwrap().1),
+            bold = style::Bold,
+            reset_style = style::Reset,
+        )
+        .unwrap();
+
+        stdout.flush().unwrap();
+
+        let format = match keys.next() {
+            Some(k) => match k.unwrap() {
+                Key::Char('\n') => {
+                    if !options.formats.is_empty() {
+                        return true;
+                    }
+                    None
+                }
+                Key::Char('h') | Key::Char('H') => {
+                    options.headings = !options.headings;
+                    None
+                }
+                Key::Char('s') | Key::Char('S') => {
+                    options.sources = !options.sources;
+                    None
+                }
+                Key::Char('l') | Key::Char('L') => {
+                    options.strip_links = !options.strip_links;
+                    None
+                }
+                Key::Char('1') => Some(ExportFormat::Markdown),
+                Key::Char('2') => Some(ExportFormat::Text),
+                Key::Char('3') => Some(ExportFormat::Html),
+                Key::Esc => return false,
+                _ => None,
+            },
+            None => return false,
+        };
+
+        if let Some(f) = format {
+            match options.formats.iter().position(|x| *x == f) {
+                Some(i) => {
+                    options.formats.remove(i);
+                }
+                None => options.formats.push(f),
+            }
+        }
+    }
+}
+
+/// ## Export done
+///
+///  Lists the files written by an export.
+fn export_done(paths: &[PathBuf]) {
+    let mut stdout = stdout().into_raw_mode().unwrap();
+    let stdin = stdin();
+
+    write!(
+        stdout,
+        "{clear}{cursor}{goto}The trail was compiled to:",
+        clear = clear::All,
+        cursor = cursor::Hide,
+        goto = cursor::Goto(1, 1)
+    )
+    .unwrap();
+
+    for (i, path) in paths.iter().enumerate() {
+        write!(
+            stdout,
+            "{}{}",
+            cursor::Goto(1, 3 + i as u16),
+            path.display()
+        )
+        .unwrap();
+    }
+
+    write!(
+        stdout,
+        "{goto}Press any key to continue.",
+        goto = cursor::Goto(1, 4 + paths.len() as u16)
+    )
+    .unwrap();
+
+    stdout.flush().unwrap();
+
+    stdin.keys().next();
+}
+
+/// ## Edit hops
+///
+///  Lists the hops of a trail, starting with hop `selected` highlighted, and
+///  lets the user rearrange them:
+///
+///  - the arrow keys, `Home`, `End`, `PgUp` and `PgDn` move the selection;
+///  - `u` and `d` move the selected hop up and down the trail;
+///  - `i` inserts a new hop before the selected one, `a` after it;
+///  - `e` edits the description of the selected hop;
+///  - `x` or `Delete` removes the selected hop, after asking for
+///    confirmation;
+///  - `Esc` goes back to the trail.
+fn edit_hops(trail: &Trail, selected: usize) -> HopMessage {
+    let mut stdout = stdout().into_raw_mode().unwrap();
+    let stdin = stdin();
+    let mut keys = stdin.keys();
+    let mut scroll_list = ScrollList::at(selected);
+    let len = trail.hops.len();
+
+    loop {
+        let height = terminal_size().unwrap().1;
+        // Every hop takes two rows: its name and its description.
+        let page = (height as usize).saturating_sub(4) / 2;
+        let visible = scroll_list.viewport(len, page);
+
+        write!(
+            stdout,
+            "{clear}{cursor}{goto}{red}{bold}HOPS OF {name}{reset_color}{reset_style}",
+            clear = clear::All,
+            cursor = cursor::Hide,
+            goto = cursor::Goto(1, 1),
+            red = color::Fg(color::Red),
+            bold = style::Bold,
+            reset_color = color::Fg(color::Reset),
+            reset_style = style::Reset,
+            name = trail.name
+        )
+        .unwrap();
+
+        if len == 0 {
+            write!(
+                stdout,
+                "{}(no hops yet: press i to add one)",
+                cursor::Goto(1, 3)
+            )
+            .unwrap();
+        }
+        for i in visible.clone() {
+            let row = 3 + 2 * (i - visible.start) as u16;
+            let (name, description) = &trail.hops[i];
+            write_list_item(
+                &mut stdout,
+                row,
+                &format!("{}. {}", i + 1, name),
+                i == scroll_list.selected,
+            );
+            write!(
+                stdout,
+                "{goto}    {italic}{description}{reset_style}",
+                goto = cursor::Goto(1, row + 1),
+                italic = style::Italic,
+                reset_style = style::Reset,
+                description = description
+            )
+            .unwrap();
+        }
+
+        write_list_footer(
+            &mut stdout,
+            height,
+            &visible,
+            len,
+            "(u/d) move, (i/a) insert, (e) edit, (x) remove, Esc to go back",
+        );
+        stdout.flush().unwrap();
+
+        let selected = scroll_list.selected;
+        match keys.next() {
+            Some(k) => match k.unwrap() {
+                Key::Char('u') | Key::Char('U') if len > 0 => return HopMessage::MoveUp(selected),
+                Key::Char('d') | Key::Char('D') if len > 0 => {
+                    return HopMessage::MoveDown(selected)
+                }
+                Key::Char('i') | Key::Char('I') => return HopMessage::Insert(selected),
+                Key::Char('a') | Key::Char('A') => {
+                    return HopMessage::Insert((selected + 1).min(len))
+                }
+                Key::Char('e') | Key::Char('E') if len > 0 => {
+                    return HopMessage::EditDescription(selected)
+                }
+                Key::Char('x') | Key::Char('X') | Key::Delete if len > 0 => {
+                    write!(
+                        stdout,
+                        "{goto}{clear}{bold}Remove hop {number} ({name})? (y/n){reset_style}",
+                        goto = cursor::Goto(1, height),
+                        clear = clear::CurrentLine,
+                        bold = style::Bold,
+                        reset_style = style::Reset,
+                        number = selected + 1,
+                        name = trail.hops[selected].0
+                    )
+                    .unwrap();
+                    stdout.flush().unwrap();
+                    if let Some(Ok(Key::Char('y'))) | Some(Ok(Key::Char('Y'))) = keys.next() {
+                        return HopMessage::Remove(selected);
+                    }
+                }
+                Key::Esc => return HopMessage::Back,
+                key => {
+                    scroll_key(&mut scroll_list, &key, len, page);
+                }
+            },
+            None => return HopMessage::Back,
+        }
+    }
+}
+
+/// ## Edit hop description
+///
+///  Asks for the new description of a hop. Returns `None` if the user gave
+///  up.
+fn edit_hop_description(description: &str) -> Option<String> {
+    text_prompt("EDIT HOP", "Hop description", description)
+}
+
+fn select_create_trail() -> CreateTrailMessage {
+    let mut stdout = stdout().into_raw_mode().unwrap();
+    let stdin = stdin();
+
+    write!(
+        stdout,
+        "{clear}{goto}There is no trail currently loaded.",
+        clear = clear::All,
+        goto = cursor::Goto(
+            terminal_size().unwrap().0 / 2 - 18,
+            terminal_size().unwrap().1 / 2
+        )
+    )
+    .unwrap();
+
+    write!(
+        stdout,
+        "{goto}(c) Create one.",
+        goto = cursor::Goto(
+            terminal_size().unwrap().0 / 2 - 7,
+            terminal_size().unwrap().1 / 2 + 1
+        )
+    )
+    .unwrap();
+
+    write!(
+        stdout,
+        "{goto}(l) Load one.",
+        goto = cursor::Goto(
+            terminal_size().unwrap().0 / 2 - 6,
+            terminal_size().unwrap().1 / 2 + 2
+        )
+    )
+    .unwrap();
+
+    stdout.flush().unwrap();
+
+    for c in stdin.keys() {
+        if let Key::Char(c) = c.unwrap() {
+            match c {
+                'c' | 'C' => return CreateTrailMessage::CreateTrail,
+                'l' | 'L' => return CreateTrailMessage::LoadTrail,
+                'j' | 'J' => return CreateTrailMessage::ReturnToJournal,
+                _ => {}
+            }
+        }
+    }
+
+    CreateTrailMessage::ReturnToJournal
+}
+
+fn display_menu() -> MenuOption {
+    let mut stdout = stdout().into_raw_mode().unwrap();
+    let stdin = stdin();
+
+    write!(
+        stdout,
+        "{clear}{cursor}{goto}{red}{bold}MAIN MENU{reset_color}{reset_style}",
+        clear = clear::All,
+        cursor = cursor::Hide,
+        goto = cursor::Goto(
+            terminal_size().unwrap().0 / 2 - 5,
+            terminal_size().unwrap().1 / 2 - 8
+        ),
+        red = color::Fg(color::Red),
+        bold = style::Bold,
+        reset_color = color::Fg(color::Reset),
+        reset_style = style::Reset
+    )
+    .unwrap();
+
+    write!(
+        stdout,
+        "{goto}{white}Select an option and press enter.{reset}",
+        // Goto the cell.
+        goto = cursor::Goto(
+            terminal_size().unwrap().0 / 2 - 16,
+            terminal_size().unwrap().1 / 2 - 6
+        ),
+        white = color::Fg(color::White),
+        reset = color::Fg(color::Reset)
+    )
+    .unwrap();
+
+    write!(
+        stdout,
+        "{goto}{white}(j) Open today's journal.{reset}",
+        // Goto the cell.
+        goto = cursor::Goto(
+            terminal_size().unwrap().0 / 2 - 12,
+            terminal_size().unwrap().1 / 2 - 4
+        ),
+        white = color::Fg(color::White),
+        reset = color::Fg(color::Reset)
+    )
+    .unwrap();
+
+    write!(
+        stdout,
+        "{goto}{white}(o) Open the journal calendar.{reset}",
+        // Goto the cell.
+        goto = cursor::Goto(
+            terminal_size().unwrap().0 / 2 - 12,
+            terminal_size().unwrap().1 / 2 - 2
+        ),
+        white = color::Fg(color::White),
+        reset = color::Fg(color::Reset)
+    )
+    .unwrap();
+
+    write!(
+        stdout,
+        "{goto}{white}(n) Open the current note.{reset}",
+        // Goto the cell.
+        goto = cursor::Goto(
+            terminal_size().unwrap().0 / 2 - 13,
+            terminal_size().unwrap().1 / 2
+        ),
+        white = color::Fg(color::White),
+        reset = color::Fg(color::Reset)
+    )
+    .unwrap();
+
+    write!(
+        stdout,
+        "{goto}{white}(N) Create or load a note.{reset}",
+        // Goto the cell.
+        goto = cursor::Goto(
+            terminal_size().unwrap().0 / 2 - 13,
+            terminal_size().unwrap().1 / 2 + 2
+        ),
+        white = color::Fg(color::White),
+        reset = color::Fg(color::Reset)
+    )
+    .unwrap();
+
+    write!(
+        stdout,
+        "{goto}{white}(t) Open the current trail.{reset}",
+        // Goto the cell.
+        goto = cursor::Goto(
+            terminal_size().unwrap().0 / 2 - 13,
+            terminal_size().unwrap().1 / 2 + 4
+        ),
+        white = color::Fg(color::White),
+        reset = color::Fg(color::Reset)
+    )
+    .unwrap();
+
+    write!(
+        stdout,
+        "{goto}{white}(T) Create or load a trail.{reset}",
+        // Goto the cell.
+        goto = cursor::Goto(
+            terminal_size().unwrap().0 / 2 - 13,
+            terminal_size().unwrap().1 / 2 + 6
+        ),
+        white = color::Fg(color::White),
+        reset = color::Fg(color::Reset)
+    )
+    .unwrap();
+
+    write!(
+        stdout,
+        "{goto}{white}(s) Search the vault.{reset}",
+        // Goto the cell.
+        goto = cursor::Goto(
+            terminal_size().unwrap().0 / 2 - 10,
+            terminal_size().unwrap().1 / 2 + 8
+        ),
+        white = color::Fg(color::White),
+        reset = color::Fg(color::Reset)
+    )
+    .unwrap();
+
+    write!(
+        stdout,
+        "{goto}{white}(c) Check the vault.{reset}",
+        // Goto the cell.
+        goto = cursor::Goto(
+            terminal_size().unwrap().0 / 2 - 10,
+            terminal_size().unwrap().1 / 2 + 10
+        ),
+        white = color::Fg(color::White),
+        reset = color::Fg(color::Reset)
+    )
+    .unwrap();
+
+    write!(
+        stdout,
+        "{goto}{white}(f) Find a page by name.{reset}",
+        // Goto the cell.
+        goto = cursor::Goto(
+            terminal_size().unwrap().0 / 2 - 11,
+            terminal_size().unwrap().1 / 2 + 12
+        ),
+        white = color::Fg(color::White),
+        reset = color::Fg(color::Reset)
+    )
+    .unwrap();
+
+    write!(
+        stdout,
+        "{goto}{white}(q) Quit.{reset}",
+        // Goto the cell.
+        goto = cursor::Goto(
+            terminal_size().unwrap().0 / 2 - 4,
+            terminal_size().unwrap().1 / 2 + 14
+        ),
+        white = color::Fg(color::White),
+        reset = color::Fg(color::Reset)
+    )
+    .unwrap();
+
+    stdout.flush().unwrap();
+
+    for k in stdin.keys() {
+        match k.unwrap() {
+            Key::Char(c) => match c {
+                'j' => return MenuOption::Journal,
+                'o' => return MenuOption::LoadJournal,
+                'n' => return MenuOption::Notes,
+                'N' => return MenuOption::LoadCreateNote,
+                't' => return MenuOption::Trails,
+                'T' => return MenuOption::LoadCreateTrail,
+                's' | 'S' => return MenuOption::Search,
+                'c' | 'C' => return MenuOption::Check,
+                'f' | 'F' => return MenuOption::Find,
+                'q' | 'Q' => return MenuOption::Quit,
+                _ => {}
+            },
+            Key::Esc => return MenuOption::Quit,
+            _ => {}
+        }
+    }
+
+    MenuOption::Quit
+}
+
+/// ## Text editor
+///
+///  This is the multi-line editor shared by notes, journal descriptions and
+///  trail descriptions. The text is held in an [editor::Buffer], which takes
+///  care of soft-wrapping the lines to the width of the terminal; this
+///  component only draws the visible rows and forwards the keys.
+///
+///  - `Enter` inserts a newline;
+///  - the arrow keys, `Home`, `End`, `PgUp` and `PgDn` move the cursor;
+///  - `Backspace` and `Delete` remove the character before or under the
+///    cursor;
+///  - `Ctrl-Z` and `Ctrl-Y` undo and redo the last edit;
+///  - `Esc` closes the editor and returns the edited text.
+///
+///  Every edit is recorded in `history`, which the caller may keep around
+///  after the editor is closed.
+///
+///  The last row of the terminal is used as a status bar.
+fn text_editor(text: &str, history: &mut History) -> String {
+    let mut stdout = stdout().into_raw_mode().unwrap();
+    let stdin = stdin();
+
+    let mut buffer = Buffer::new(text);
+
+    draw_editor(&mut stdout, &mut buffer);
+
+    for k in stdin.keys() {
+        let (width, height) = editor_size();
+        let before = buffer.snapshot();
+
+        match k.unwrap() {
+            Key::Esc => break,
+            Key::Char(c) => {
+                buffer.insert(c);
+                let edit = match c {
+                    '\n' => Edit::Newline,
+                    _ => Edit::Typing,
+                };
+                history.commit(before, edit, &buffer);
+            }
+            Key::Backspace => {
+                buffer.backspace();
+                history.commit(before, Edit::Deletion, &buffer);
+            }
+            Key::Delete => {
+                buffer.delete();
+                history.commit(before, Edit::Deletion, &buffer);
+            }
+            Key::Ctrl('z') => {
+                history.undo(&mut buffer);
+            }
+            Key::Ctrl('y') => {
+                history.redo(&mut buffer);
+            }
+            k => {
+                match k {
+                    Key::Left => buffer.left(),
+                    Key::Right => buffer.right(),
+                    Key::Up => buffer.move_rows(-1, width),
+                    Key::Down => buffer.move_rows(1, width),
+                    Key::PageUp => buffer.move_rows(-(height as isize), width),
+                    Key::PageDown => buffer.move_rows(height as isize, width),
+                    Key::Home => buffer.home(),
+                    Key::End => buffer.end(),
+                    _ => {}
+                }
+                history.break_group();
+            }
+        }
+
+        draw_editor(&mut stdout, &mut buffer);
+    }
+
+    write!(stdout, "{}", cursor::Hide).unwrap();
+    stdout.flush().unwrap();
+
+    buffer.text()
+}
+
+/// Width and height of the text area of the editor. One column is kept free
+/// for the cursor at the end of a full row, and one row for the status bar.
+fn editor_size() -> (usize, usize) {
+    let (width, height) = terminal_size().unwrap();
+    (
+        (width as usize).saturating_sub(1).max(1),
+        (height as usize).saturating_sub(1).max(1),
+    )
+}
+
+fn draw_editor<W: Write>(stdout: &mut W, buffer: &mut Buffer) {
+    let (width, height) = editor_size();
+    let rows = buffer.layout(width);
+    let top = buffer.scroll_to_cursor(&rows, height);
+
+    write!(stdout, "{}{}", cursor::Hide, clear::All).unwrap();
+
+    for (i, row) in rows.iter().skip(top).take(height).enumerate() {
+        write!(
+            stdout,
+            "{goto}{text}",
+            goto = cursor::Goto(1, i as u16 + 1),
+            text = buffer.row_text(row)
+        )
+        .unwrap();
+    }
+
+    let (line, col) = buffer.cursor();
+    write!(
+        stdout,
+        "{goto}{bold}EDIT{reset_style}  line {line}/{lines}, column {col}  (Esc to finish, Ctrl-Z/Ctrl-Y to undo/redo)",
+        goto = cursor::Goto(1, height as u16 + 1),
+        bold = style::Bold,
+        reset_style = style::Reset,
+        line = line + 1,
+        lines = buffer.line_count(),
+        col = col + 1
+    )
+    .unwrap();
+
+    let (current, x) = buffer.cursor_on_screen(&rows);
+    write!(
+        stdout,
+        "{goto}{show_cursor}",
+        goto = cursor::Goto(x as u16 + 1, (current - top) as u16 + 1),
+        show_cursor = cursor::Show
+    )
+    .unwrap();
+
+    stdout.flush().unwrap();
+}
+
+/// ## External editor
+///
+///  Hands `text` to the editor set in `$VISUAL` or `$EDITOR` through a
+///  temporary file, and reads it back once the editor exits. The screen is
+///  cleared and the cursor shown before starting the editor, since the
+///  terminal is handed over to it.
+///
+///  Returns `None` if neither variable is set. If the editor exits with an
+///  error (e.g. `:cq` in vim), the text is returned unchanged.
+fn external_editor(text: &str) -> Option<String> {
+    let editor = env::var("VISUAL")
+        .ok()
+        .filter(|e| !e.is_empty())
+        .or_else(|| env::var("EDITOR").ok().filter(|e| !e.is_empty()))?;
+
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    let path = env::temp_dir().join(format!("vannevar-{}-{}.md", process::id(), stamp));
+    if fs::write(&path, text).is_err() {
+        return None;
+    }
+
+    let mut stdout = stdout();
+    write!(
+        stdout,
+        "{clear}{goto}{show_cursor}",
+        clear = clear::All,
+        goto = cursor::Goto(1, 1),
+        show_cursor = cursor::Show
+    )
+    .unwrap();
+    stdout.flush().unwrap();
+
+    // Run through the shell, so that `$EDITOR` may carry arguments
+    // (e.g. `code --wait`).
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$1\"", editor))
+        .arg("sh")
+        .arg(&path)
+        .status();
+
+    let edited = match status {
+        Ok(s) if s.success() => fs::read_to_string(&path).ok(),
+        _ => None,
+    };
+    let _ = fs::remove_file(&path);
+
+    write!(stdout, "{}{}", clear::All, cursor::Hide).unwrap();
+    stdout.flush().unwrap();
+
+    match edited {
+        // Most editors add a newline at the end of the file.
+        Some(new_text) => match new_text.strip_suffix('\n') {
+            Some(stripped) if !text.ends_with('\n') => Some(String::from(stripped)),
+            _ => Some(new_text),
+        },
+        None => Some(String::from(text)),
+    }
+}
+
+/// Opens `text` in the editor chosen in the configuration, falling back to
+/// the built-in one if no external editor is set.
+fn edit_with(text: &str, history: &mut History, editor: EditorKind) -> String {
+    match editor {
+        EditorKind::External => match external_editor(text) {
+            Some(t) => t,
+            None => text_editor(text, history),
+        },
+        EditorKind::Builtin => text_editor(text, history),
+    }
+}
+
+fn edit_journal_description(desc: &str, editor: EditorKind) -> String {
+    edit_with(desc, &mut History::new(), editor)
+}
+
+fn edit_note(text: &str, history: &mut History, editor: EditorKind) -> String {
+    edit_with(text, history, editor)
+}
+
+/// ## Save error
+///
+///  Tells the user that the page of the given kind (`note`, `trail`, ...)
+///  couldn't be saved, along with the reason why.
+fn save_error(text: &str, error: &FileError) {
+    let mut stdout = stdout().into_raw_mode().unwrap();
+    let stdin = stdin();
+
+    write!(
+        stdout,
+        "{clear}{cursor}{goto}{red}{bold}ERROR{reset_color}{reset_style}",
+        clear = clear::All,
+        cursor = cursor::Hide,
+        goto = cursor::Goto(
+            terminal_size().unwrap().0 / 2 - 2,
+            terminal_size().unwrap().1 / 2 - 2
+        ),
+        red = color::Fg(color::Red),
+        bold = style::Bold,
+        reset_color = color::Fg(color::Reset),
+        reset_style = style::Reset
+    )
+    .unwrap();
+
+    write!(
+        stdout,
+        "{goto}{white}Could not save {name}.{reset}",
+        // Goto the cell.
+        goto = cursor::Goto(
+            terminal_size().unwrap().0 / 2 - 13,
+            terminal_size().unwrap().1 / 2 + 2
+        ),
+        white = color::Fg(color::White),
+        name = text,
+        reset = color::Fg(color::Reset)
+    )
+    .unwrap();
+
+    let rows = write_text(
+        &mut stdout,
+        &error.to_string(),
+        terminal_size().unwrap().1 / 2 + 4,
+    );
+
+    write!(
+        stdout,
+        "{goto}{white}Press any key to continue.{reset}",
+        // Goto the cell.
+        goto = cursor::Goto(
+            terminal_size().unwrap().0 / 2 - 13,
+            terminal_size().unwrap().1 / 2 + 5 + rows
+        ),
+        white = color::Fg(color::White),
+        reset = color::Fg(color::Reset)
+    )
+    .unwrap();
+
+    stdout.flush().unwrap();
+
+    stdin.keys().next();
+}
+
+fn add_journal_link() -> String {
+    let mut stdout = stdout().into_raw_mode().unwrap();
+    let stdin = stdin();
+
+    write!(
+        stdout,
+        "{clear}{cursor}{goto}{red}{bold}CREATE NEW NOTE{reset_color}{reset_style}",
+        clear = clear::All,
+        cursor = cursor::Hide,
+        goto = cursor::Goto(
+            terminal_size().unwrap().0 / 2 - 8,
+            terminal_size().unwrap().1 / 2 - 2
+        ),
+        red = color::Fg(color::Red),
+        bold = style::Bold,
+        reset_color = color::Fg(color::Reset),
+        reset_style = style::Reset
+    )
+    .unwrap();
+
+    write!(
+        stdout,
+        "{goto}{bold}Page name: {reset}",
+        // Goto the cell.
+        goto = cursor::Goto(
+            terminal_size().unwrap().0 / 2 - 13,
+            terminal_size().unwrap().1 / 2 + 2
+        ),
+        bold = style::Bold,
+        reset = style::Reset
+    )
+    .unwrap();
+
+    stdout.flush().unwrap();
+
+    let mut buf = String::new();
+
+    for k in stdin.keys() {
+        match k.unwrap() {
+            Key::Char(c) => match c {
+                '\n' => return buf,
+                _ => buf.push(c),
+            },
+            Key::Backspace => {
+                buf.pop();
+            }
+            Key::Esc => return String::new(),
+            _ => {}
+        }
+
+        write!(
+            stdout,
+            "{goto}{bold}Page name: {reset}{name}",
+            // Goto the cell.
+            goto = cursor::Goto(
+                terminal_size().unwrap().0 / 2 - 13,
+                terminal_size().unwrap().1 / 2 + 2
+            ),
+            bold = style::Bold,
+            reset = style::Reset,
+            name = buf
+        )
+        .unwrap();
+
+        write!(stdout, "{}", clear::AfterCursor).unwrap();
+
+        stdout.flush().unwrap();
+    }
+
+    String::new()
+}
+
+/// ## Log journal line
+///
+///  Asks for a single line to add to the log of the day, labelled with the
+///  time it will be stamped with.
+fn log_journal_line() -> String {
+    let time = Local::now().format("%H:%M").to_string();
+    text_prompt("LOG A LINE", &time, "").unwrap_or_default()
+}
+
+fn select_create_note(title: &str) -> bool {
+    let mut stdout = stdout().into_raw_mode().unwrap();
+    let stdin = stdin();
+
+    write!(
+        stdout,
+        "{clear}{goto}The note for {name} does not exist.",
+        clear = clear::All,
+        goto = cursor::Goto(
+            terminal_size().unwrap().0 / 2 - 21,
+            terminal_size().unwrap().1 / 2
+        ),
+        name = title
+    )
+    .unwrap();
+
+    write!(
+        stdout,
+        "{goto}Create it? (y/n)",
+        goto = cursor::Goto(
+            terminal_size().unwrap().0 / 2 - 8,
+            terminal_size().unwrap().1 / 2 + 1
+        )
+    )
+    .unwrap();
+
+    stdout.flush().unwrap();
+
+    let choice: bool = false;
+
+    for c in stdin.keys() {
+        if let Key::Char(c) = c.unwrap() {
+            match c {
+                'y' | 'Y' => return true,
+                'n' | 'N' => return false,
+                _ => {}
+            }
+        }
+    }
+
+    choice
+}
+
+fn reset_cursor() {
+    let mut stdout = stdout().into_raw_mode().unwrap();
+    write!(stdout, "{}", cursor::Show).unwrap();
+    stdout.flush().unwrap();
+}
+
+fn create_new_trail() -> String {
+    let mut stdout = stdout().into_raw_mode().unwrap();
+    let stdin = stdin();
+
+    write!(
+        stdout,
+        "{clear}{cursor}{goto}{red}{bold}CREATE NEW TRAIL{reset_color}{reset_style}",
+        clear = clear::All,
+        cursor = cursor::Hide,
+        goto = cursor::Goto(
+            terminal_size().unwrap().0 / 2 - 8,
+            terminal_size().unwrap().1 / 2 - 2
+        ),
+        red = color::Fg(color::Red),
+        bold = style::Bold,
+        reset_color = color::Fg(color::Reset),
+        reset_style = style::Reset
+    )
+    .unwrap();
+
+    write!(
+        stdout,
+        "{goto}{bold}Trail name: {reset}",
+        // Goto the cell.
+        goto = cursor::Goto(
+            terminal_size().unwrap().0 / 2 - 13,
+            terminal_size().unwrap().1 / 2 + 2
+        ),
+        bold = style::Bold,
+        reset = style::Reset
+    )
+    .unwrap();
+
+    stdout.flush().unwrap();
+
+    let mut buf = String::new();
+
+    for k in stdin.keys() {
+        match k.unwrap() {
+            Key::Char(c) => match c {
+                '\n' => return buf,
+                _ => buf.push(c),
+            },
+            Key::Backspace => {
+                buf.pop();
+            }
+            Key::Esc => return String::new(),
+            _ => {}
+        }
+
+        write!(
+            stdout,
+            "{goto}{bold}Trail name: {reset}{name}",
+            // Goto the cell.
+            goto = cursor::Goto(
+                terminal_size().unwrap().0 / 2 - 13,
+                terminal_size().unwrap().1 / 2 + 2
+            ),
+            bold = style::Bold,
+            reset = style::Reset,
+            name = buf
+        )
+        .unwrap();
+
+        write!(stdout, "{}", clear::AfterCursor).unwrap();
+
+        stdout.flush().unwrap();
+    }
+
+    String::new()
+}
+
+fn edit_trail_description(desc: &str, editor: EditorKind) -> String {
+    edit_with(desc, &mut History::new(), editor)
+}
+
+enum TrailHopState {
+    Name,
+    Description,
+    End,
+}
+
+fn add_trail_hop() -> (String, String) {
+    let mut stdout = stdout().into_raw_mode().unwrap();
+    let stdin = stdin();
+
+    write!(
+        stdout,
+        "{clear}{cursor}{goto}{red}{bold}CREATE NEW HOP{reset_color}{reset_style}",
+        clear = clear::All,
+        cursor = cursor::Hide,
+        goto = cursor::Goto(
+            terminal_size().unwrap().0 / 2 - 7,
+            terminal_size().unwrap().1 / 2 - 2
+        ),
+        red = color::Fg(color::Red),
+        bold = style::Bold,
+        reset_color = color::Fg(color::Reset),
+        reset_style = style::Reset
+    )
+    .unwrap();
+
+    write!(
+        stdout,
+        "{goto}{bold}Hop name: {reset}",
+        // Goto the cell.
+        goto = cursor::Goto(
+            terminal_size().unwrap().0 / 2 - 13,
+            terminal_size().unwrap().1 / 2 + 2
+        ),
+        bold = style::Bold,
+        reset = style::Reset
+    )
+    .unwrap();
+
+    stdout.flush().unwrap();
+
+    let mut name_buf = String::new();
+    let mut desc_buf = String::new();
+
+    let keys = stdin.keys();
+    let mut current_state = TrailHopState::Name;
+
+    for k in keys.into_iter() {
+        match k.unwrap() {
+            Key::Char(c) => match c {
+                '\n' => match current_state {
+                    TrailHopState::Name => current_state = TrailHopState::Description,
+                    TrailHopState::Description => current_state = TrailHopState::End,
+                    TrailHopState::End => break,
+                },
+                _ => match current_state {
+                    TrailHopState::Name => name_buf.push(c),
+                    TrailHopState::Description => desc_buf.push(c),
+                    TrailHopState::End => {}
+                },
+            },
+            Key::Backspace => {
+                match current_state {
+                    TrailHopState::Name => name_buf.pop(),
+                    _ => desc_buf.pop(),
+                };
+            }
+            Key::Esc => return (String::new(), String::new()),
+            _ => {}
+        }
+
+        match current_state {
+            TrailHopState::Name => {
+                write!(
+                    stdout,
+                    "{goto}{bold}Hop name: {reset}{name}",
+                    // Goto the cell.
+                    goto = cursor::Goto(
+                        terminal_size().unwrap().0 / 2 - 13,
+                        terminal_size().unwrap().1 / 2 + 2
+                    ),
+                    bold = style::Bold,
+                    reset = style::Reset,
+                    name = name_buf
+                )
+                .unwrap();
+
+                write!(stdout, "{}", clear::AfterCursor).unwrap();
+            }
+            TrailHopState::Description => {
+                write!(
+                    stdout,
+                    "{goto}{bold}Hop description: {reset}{description}",
+                    // Goto the cell.
+                    goto = cursor::Goto(
+                        terminal_size().unwrap().0 / 2 - 13,
+                        terminal_size().unwrap().1 / 2 + 2
+                    ),
+                    bold = style::Bold,
+                    reset = style::Reset,
+                    description = desc_buf
+                )
+                .unwrap();
+            }
+            _ => {}
+        }
+
+        stdout.flush().unwrap();
+    }
+
+    (name_buf, desc_buf)
+}
+
+/// ## Error
+///
+///  Shows an error `message`, followed by the `details` of the error that
+///  caused it (e.g. the file that couldn't be read and why), if any.
+fn display_error(message: &str, details: Option<&str>) -> DisplayErrorMessage {
+    let mut stdout = stdout().into_raw_mode().unwrap();
+    let stdin = stdin();
+
+    write!(
+        stdout,
+        "{clear}{red}{goto}{bold}ERROR!{reset_color}{reset_font}",
+        clear = clear::All,
+        goto = cursor::Goto(terminal_size().unwrap().0 / 2 - 3, 1),
+        red = color::Fg(color::Red),
+        bold = style::Bold,
+        reset_color = color::Fg(color::Reset),
+        reset_font = style::Reset,
+    )
+    .unwrap();
+
+    let mut line_number = write_text(&mut stdout, message, 3);
+    if let Some(details) = details {
+        write!(stdout, "{}", color::Fg(color::LightBlack)).unwrap();
+        line_number += 1 + write_text(&mut stdout, details, 4 + line_number);
+        write!(stdout, "{}", color::Fg(color::Reset)).unwrap();
+    }
+
+    write!(
+        stdout,
+        "{goto1}(q) Exit{goto2}(m) Menu",
+        goto1 = cursor::Goto(terminal_size().unwrap().0 / 2 - 4, 4 + line_number),
+        goto2 = cursor::Goto(terminal_size().unwrap().0 / 2 - 4, 6 + line_number)
+    )
+    .unwrap();
+
+    stdout.flush().unwrap();
+
+    for k in stdin.keys() {
+        if let Key::Char(c) = k.unwrap() {
+            match c {
+                'm' | 'M' => return DisplayErrorMessage::Menu,
+                'q' | 'Q' => return DisplayErrorMessage::Exit,
+                _ => {}
+            }
+        }
+    }
+
+    DisplayErrorMessage::Menu
+}
+
+/// ## Text prompt
+///
+///  Asks the user for a single line of text, under a red `heading`. The
+///  line starts out as `initial`. Returns the typed text when `Enter` is
+///  pressed, or `None` if the user presses `Esc`.
+fn text_prompt(heading: &str, label: &str, initial: &str) -> Option<String> {
+    let mut stdout = stdout().into_raw_mode().unwrap();
+    let stdin = stdin();
+
+    write!(
+        stdout,
+        "{clear}{cursor}{goto}{red}{bold}{heading}{reset_color}{reset_style}",
+        clear = clear::All,
+        cursor = cursor::Hide,
+        goto = cursor::Goto(
+            (terminal_size().unwrap().0 / 2).saturating_sub(heading.chars().count() as u16 / 2),
+            terminal_size().unwrap().1 / 2 - 2
+        ),
+        red = color::Fg(color::Red),
+        bold = style::Bold,
+        reset_color = color::Fg(color::Reset),
+        reset_style = style::Reset,
+        heading = heading
+    )
+    .unwrap();
+
+    let mut buf = String::from(initial);
+    let mut keys = stdin.keys();
+
+    loop {
+        write!(
+            stdout,
+            "{goto}{bold}{label}: {reset}{text}{clear}",
+            goto = cursor::Goto(1, terminal_size().unwrap().1 / 2 + 2),
+            bold = style::Bold,
+            reset = style::Reset,
+            label = label,
+            text = buf,
+            clear = clear::AfterCursor
+        )
+        .unwrap();
+
+        stdout.flush().unwrap();
+
+        match keys.next() {
+            Some(k) => match k.unwrap() {
+                Key::Char('\n') => return Some(buf),
+                Key::Char(c) => buf.push(c),
+                Key::Backspace => {
+                    buf.pop();
+                }
+                Key::Esc => return None,
+                _ => {}
+            },
+            None => return None,
+        }
+    }
+}
+
+/// ## Load or create note
+///
+///  Asks for the title of a note to open. The controller offers to create
+///  the note when there's none with that title.
+fn load_create_note() -> String {
+    text_prompt("OPEN NOTE", "Title", "").unwrap_or_default()
+}
+
+/// ## Rename note prompt
+///
+///  Asks for the new title of the note `title`.
+fn rename_note_prompt(title: &str) -> String {
+    text_prompt("RENAME NOTE", "New title", title).unwrap_or_default()
+}
+
+/// ## Confirm rename
+///
+///  Previews a rename, listing every page whose links will be rewritten and
+///  the pages it leaves alone because they can't be read, and asks the user
+///  whether to apply it.
+fn confirm_rename(report: &RenameReport) -> bool {
+    let mut stdout = stdout().into_raw_mode().unwrap();
+    let stdin = stdin();
+
+    write!(
+        stdout,
+        "{clear}{cursor}{goto}{red}{bold}RENAME {old} TO {new}{reset_color}{reset_style}",
+        clear = clear::All,
+        cursor = cursor::Hide,
+        goto = cursor::Goto(1, 1),
+        red = color::Fg(color::Red),
+        bold = style::Bold,
+        reset_color = color::Fg(color::Reset),
+        reset_style = style::Reset,
+        old = report.old,
+        new = report.new
+    )
+    .unwrap();
+
+    write!(
+        stdout,
+        "{goto}{links} links will be rewritten, {files} files will be touched:",
+        goto = cursor::Goto(1, 3),
+        links = report.links(),
+        files = report.files()
+    )
+    .unwrap();
+
+    for (i, (page, count)) in report.pages.iter().enumerate() {
+        write!(
+            stdout,
+            "{goto}{label} ({count})",
+            goto = cursor::Goto(1, 5 + i as u16),
+            label = page.label(),
+            count = count
+        )
+        .unwrap();
+    }
+
+    // Pages that can't be read are left as they are
+    let offset = 6 + report.pages.len() as u16;
+    if !report.skipped.is_empty() {
+        write!(
+            stdout,
+            "{goto}{bold}These pages can't be read and won't be changed:{reset_style}",
+            goto = cursor::Goto(1, offset),
+            bold = style::Bold,
+            reset_style = style::Reset,
+        )
+        .unwrap();
+    }
+    for (i, (_, error)) in report.skipped.iter().enumerate() {
+        write!(
+            stdout,
+            "{goto}{error}",
+            goto = cursor::Goto(1, offset + 1 + i as u16),
+            error = error
+        )
+        .unwrap();
+    }
+
+    write!(
+        stdout,
+        "{goto}{bold}Apply? (y/n){reset_style}",
+        goto = cursor::Goto(1, terminal_size().unwrap().1),
+        bold = style::Bold,
+        reset_style = style::Reset,
+    )
+    .unwrap();
+
+    stdout.flush().unwrap();
+
+    for c in stdin.keys() {
+        match c.unwrap() {
+            Key::Char('y') | Key::Char('Y') => return true,
+            Key::Char('n') | Key::Char('N') | Key::Esc => return false,
+            _ => {}
+        }
+    }
+
+    false
+}
+
+/// ## Rename done
+///
+///  Tells the user how many files were touched by a rename.
+fn rename_done(report: &RenameReport) {
+    let mut stdout = stdout().into_raw_mode().unwrap();
+    let stdin = stdin();
+
+    write!(
+        stdout,
+        "{clear}{cursor}{goto}Renamed {old} to {new}: {links} links rewritten in {files} files.",
+        clear = clear::All,
+        cursor = cursor::Hide,
+        goto = cursor::Goto(1, terminal_size().unwrap().1 / 2),
+        old = report.old,
+        new = report.new,
+        links = report.links(),
+        files = report.files()
+    )
+    .unwrap();
+
+    if !report.skipped.is_empty() {
+        write!(
+            stdout,
+            "{goto}{skipped} pages couldn't be read and were left unchanged.",
+            goto = cursor::Goto(1, terminal_size().unwrap().1 / 2 + 1),
+            skipped = report.skipped.len()
+        )
+        .unwrap();
+    }
+
+    write!(
+        stdout,
+        "{goto}Press any key to continue.",
+        goto = cursor::Goto(1, terminal_size().unwrap().1 / 2 + 2)
+    )
+    .unwrap();
+
+    stdout.flush().unwrap();
+
+    stdin.keys().next();
+}
+
+/// ## Search prompt
+///
+///  Asks for the words to search in the vault.
+fn search_prompt() -> String {
+    text_prompt("SEARCH", "Query", "").unwrap_or_default()
+}
+
+/// ## Search results
+///
+///  Lists the pages matching a search, with an excerpt of each of them. Like
+///  in the [link_menu], the user picks a result with the arrow keys and opens
+///  it with `Enter`; `Esc` goes back to the search prompt.
+fn search_results(query: &str, hits: &[SearchHit]) -> LinkMessage {
+    let mut stdout = stdout().into_raw_mode().unwrap();
+    let stdin = stdin();
+    let mut keys = stdin.keys();
+    let mut scroll_list = ScrollList::new();
+
+    loop {
+        let height = terminal_size().unwrap().1;
+        // Every result takes two rows: the name of the page and the excerpt.
+        let page = (height as usize).saturating_sub(4) / 2;
+        let visible = scroll_list.viewport(hits.len(), page);
+
+        write!(
+            stdout,
+            "{clear}{cursor}{goto}{red}{bold}Results for \"{query}\"{reset_color}{reset_style}",
+            clear = clear::All,
+            cursor = cursor::Hide,
+            goto = cursor::Goto(1, 1),
+            red = color::Fg(color::Red),
+            bold = style::Bold,
+            reset_color = color::Fg(color::Reset),
+            reset_style = style::Reset,
+            query = query
+        )
+        .unwrap();
+
+        for i in visible.clone() {
+            let row = 3 + 2 * (i - visible.start) as u16;
+            write_list_item(
+                &mut stdout,
+                row,
+                &hits[i].page.label(),
+                i == scroll_list.selected,
+            );
+            write!(
+                stdout,
+                "{goto}    {italic}{snippet}{reset_style}",
+                goto = cursor::Goto(1, row + 1),
+                italic = style::Italic,
+                reset_style = style::Reset,
+                snippet = hits[i].snippet
+            )
+            .unwrap();
+        }
+
+        write_list_footer(
+            &mut stdout,
+            height,
+            &visible,
+            hits.len(),
+            "(Enter to open, Esc to go back)",
+        );
+        stdout.flush().unwrap();
+
+        match keys.next() {
+            Some(k) => match k.unwrap() {
+                Key::Char('\n') => {
+                    if !hits.is_empty() {
+                        return LinkMessage::GotoLink(scroll_list.selected);
+                    }
+                }
+                Key::Esc => return LinkMessage::Back,
+                key => {
+                    scroll_key(&mut scroll_list, &key, hits.len(), page);
+                }
+            },
+            None => return LinkMessage::Exit,
+        }
+    }
+}
+
+/// ## Fuzzy finder
+///
+///  Lets the user pick one of `pages` by typing a few characters of its name:
+///  the list is filtered and ranked as the user types, with the matched
+///  characters highlighted.
+///
+///  - the arrow keys, `PgUp` and `PgDn` move the selection;
+///  - `Enter` selects the highlighted page;
+///  - `Esc` goes back.
+///
+///  Returns the index of the selected page in `pages`.
+fn fuzzy_finder(heading: &str, pages: &[PageRef]) -> FinderMessage {
+    let mut stdout = stdout().into_raw_mode().unwrap();
+    let stdin = stdin();
+
+    let names: Vec<String> = pages.iter().map(|p| p.name.clone()).collect();
+    let mut pattern = String::new();
+    let mut scroll_list = ScrollList::new();
+    let mut keys = stdin.keys();
+
+    loop {
+        let matches = fuzzy_filter(&pattern, &names);
+        let height = terminal_size().unwrap().1;
+        let page = (height as usize).saturating_sub(4);
+        let visible = scroll_list.viewport(matches.len(), page);
+
+        write!(
+            stdout,
+            "{clear}{cursor}{goto}{red}{bold}{heading}{reset_color}{reset_style}",
+            clear = clear::All,
+            cursor = cursor::Hide,
+            goto = cursor::Goto(1, 1),
+            red = color::Fg(color::Red),
+            bold = style::Bold,
+            reset_color = color::Fg(color::Reset),
+            reset_style = style::Reset,
+            heading = heading
+        )
+        .unwrap();
+
+        for i in visible.clone() {
+            let m = &matches[i];
+            let found = &pages[m.index];
+            let marker = match i == scroll_list.selected {
+                true => format!("{}> ", style::Bold),
+                false => String::from("  "),
+            };
+            write!(
+                stdout,
+                "{goto}{marker}{kind:<8}{reset_style}",
+                goto = cursor::Goto(1, 3 + (i - visible.start) as u16),
+                marker = marker,
+                kind = found.kind(),
+                reset_style = style::Reset
+            )
+            .unwrap();
+
+            for (i, c) in found.name.chars().enumerate() {
+                match m.positions.contains(&i) {
+                    true => write!(
+                        stdout,
+                        "{red}{bold}{c}{reset_color}{reset_style}",
+                        red = color::Fg(color::Red),
+                        bold = style::Bold,
+                        reset_color = color::Fg(color::Reset),
+                        reset_style = style::Reset,
+                        c = c
+                    )
+                    .unwrap(),
+                    false => write!(stdout, "{}", c).unwrap(),
+                }
+            }
+        }
+
+        write_list_footer(
+            &mut stdout,
+            height,
+            &visible,
+            matches.len(),
+            "(Enter to open, Esc to go back)",
+        );
+
+        write!(
+            stdout,
+            "{goto}{bold}> {reset_style}{pattern}{show_cursor}",
+            goto = cursor::Goto(1, 2),
+            bold = style::Bold,
+            reset_style = style::Reset,
+            pattern = pattern,
+            show_cursor = cursor::Show
+        )
+        .unwrap();
+
+        stdout.flush().unwrap();
+
+        match keys.next() {
+            Some(k) => match k.unwrap() {
+                Key::Char('\n') => {
+                    if let Some(m) = matches.get(scroll_list.selected) {
+                        write!(stdout, "{}", cursor::Hide).unwrap();
+                        return FinderMessage::Select(m.index);
+                    }
+                }
+                Key::Char(c) => {
+                    pattern.push(c);
+                    scroll_list = ScrollList::new();
+                }
+                Key::Backspace => {
+                    pattern.pop();
+                    scroll_list = ScrollList::new();
+                }
+                Key::Esc => break,
+                key => {
+                    scroll_key(&mut scroll_list, &key, matches.len(), page);
+                }
+            },
+            None => break,
+        }
+    }
+
+    write!(stdout, "{}", cursor::Hide).unwrap();
+    FinderMessage::Back
+}
+
+impl View for Terminal {
+    fn start_page(&mut self) {
+        start_page()
+    }
+    fn display_menu(&mut self) -> MenuOption {
+        display_menu()
+    }
+    fn reset_cursor(&mut self) {
+        reset_cursor()
+    }
+    fn select_create_journal(&mut self, date: &str) -> bool {
+        select_create_journal(date)
+    }
+    fn display_journal(&mut self, page: &Journal) -> JournalMessage {
+        display_journal(page)
+    }
+    fn calendar(
+        &mut self,
+        selected: NaiveDate,
+        days: &BTreeMap<NaiveDate, usize>,
+    ) -> CalendarMessage {
+        calendar(selected, days)
+    }
+    fn display_rollup(&mut self, rollup: &Rollup) -> RollupMessage {
+        display_rollup(rollup)
+    }
+    fn edit_journal_description(&mut self, desc: &str, editor: EditorKind) -> String {
+        edit_journal_description(desc, editor)
+    }
+    fn add_journal_link(&mut self) -> String {
+        add_journal_link()
+    }
+    fn log_journal_line(&mut self) -> String {
+        log_journal_line()
+    }
+    fn select_create_note(&mut self, title: &str) -> bool {
+        select_create_note(title)
+    }
+    fn display_note(&mut self, page: &Note, backlinks: &[PageRef]) -> NoteMessage {
+        display_note(page, backlinks)
+    }
+    fn edit_note(&mut self, text: &str, history: &mut History, editor: EditorKind) -> String {
+        edit_note(text, history, editor)
+    }
+    fn load_create_note(&mut self) -> String {
+        load_create_note()
+    }
+    fn rename_note_prompt(&mut self, title: &str) -> String {
+        rename_note_prompt(title)
+    }
+    fn confirm_rename(&mut self, report: &RenameReport) -> bool {
+        confirm_rename(report)
+    }
+    fn rename_done(&mut self, report: &RenameReport) {
+        rename_done(report)
+    }
+    fn select_create_trail(&mut self) -> CreateTrailMessage {
+        select_create_trail()
+    }
+    fn create_new_trail(&mut self) -> String {
+        create_new_trail()
+    }
+    fn display_trail(&mut self, page: &Trail) -> TrailMessage {
+        display_trail(page)
+    }
+    fn edit_trail_description(&mut self, desc: &str, editor: EditorKind) -> String {
+        edit_trail_description(desc, editor)
+    }
+    fn add_trail_hop(&mut self) -> (String, String) {
+        add_trail_hop()
+    }
+    fn edit_hops(&mut self, trail: &Trail, selected: usize) -> HopMessage {
+        edit_hops(trail, selected)
+    }
+    fn edit_hop_description(&mut self, description: &str) -> Option<String> {
+        edit_hop_description(description)
+    }
+    fn walk_trail(&mut self, trail: &Trail, hop: usize, note: Option<&Note>) -> WalkMessage {
+        walk_trail(trail, hop, note)
+    }
+    fn export_options(&mut self, name: &str, options: &mut CompileOptions) -> bool {
+        export_options(name, options)
+    }
+    fn export_done(&mut self, paths: &[PathBuf]) {
+        export_done(paths)
+    }
+    fn link_menu(&mut self, heading: &str, list: &[String]) -> LinkMessage {
+        link_menu(heading, list)
+    }
+    fn fuzzy_finder(&mut self, heading: &str, pages: &[PageRef]) -> FinderMessage {
+        fuzzy_finder(heading, pages)
+    }
+    fn search_prompt(&mut self) -> String {
+        search_prompt()
+    }
+    fn search_results(&mut self, query: &str, hits: &[SearchHit]) -> LinkMessage {
+        search_results(query, hits)
+    }
+    fn health_check(&mut self, report: &HealthReport) -> LinkMessage {
+        health_check(report)
+    }
+    fn save_error(&mut self, text: &str, error: &FileError) {
+        save_error(text, error)
+    }
+    fn display_error(&mut self, message: &str, details: Option<&str>) -> DisplayErrorMessage {
+        display_error(message, details)
+    }
+}
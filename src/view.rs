@@ -1,1207 +1,386 @@
-/// # `view` module
+//! # `view` module
+//!
+//!  This module describes the view of the application: the [View] trait lists
+//!  every screen the controller can show, and the `enum`s below are the
+//!  messages those screens send back once the user has made a choice.
+//!
+//!  Each screen blocks until the user answers it. The controller doesn't
+//!  know how the screens are drawn, so it can be driven by any backend: the
+//!  [Terminal] backend draws them with `termion`, while tests can answer them
+//!  from a script.
+//!
+//!  [Terminal]: crate::terminal::Terminal
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::path::PathBuf;
+
+use chrono::NaiveDate;
+
+use crate::editor::History;
+use crate::{
+    CompileOptions, EditorKind, FileError, HealthReport, Journal, Note, PageRef, RenameReport,
+    Rollup, SearchHit, Trail,
+};
+
+/// ## MenuOption
 ///
-///  This module contains the UI code and is mostly composed of calls to the
-///  functions of the `termion` crate.
-///
-///  Like all UI code, it's messy.
-///
-///  *Since the program can't do much in case of a crash of the terminal emulator
-///  on which it is running, all the `termion` functions returning a `Result` are
-///  simply handled with `.unwrap()`. It's ugly but there's little to no utility
-///  in doing otherwise.*
-
-
-extern crate termion;
-
-use termion::cursor::DetectCursorPos;
-use termion::event::Key;
-use termion::input::TermRead;
-use termion::raw::IntoRawMode;
-use termion::{clear, color, cursor, scroll, style, terminal_size};
-
-use std::io::{stdin, stdout, Write};
-
-use crate::lib::{Journal, Note, Trail};
-
-/// ## Start page
-///  
-///  This view component is merely an introductory screen and has no other
-///  purpose.
-
-pub fn start_page() {
-    let mut stdout = stdout().into_raw_mode().unwrap();
-    let stdin = stdin();
-
-    write!(
-        stdout,
-        "{clear}{cursor}{goto}{red}{bold}VANNEVAR{reset_color}{reset_style}",
-        clear = clear::All,
-        cursor = cursor::Hide,
-        goto = cursor::Goto(
-            terminal_size().unwrap().0 / 2 - 3,
-            terminal_size().unwrap().1 / 2 - 2
-        ),
-        red = color::Fg(color::Red),
-        bold = style::Bold,
-        reset_color = color::Fg(color::Reset),
-        reset_style = style::Reset
-    )
-    .unwrap();
-
-    write!(
-        stdout,
-        "{goto}{white}Press any key to continue.{reset}",
-        // Goto the cell.
-        goto = cursor::Goto(
-            terminal_size().unwrap().0 / 2 - 13,
-            terminal_size().unwrap().1 / 2 + 2
-        ),
-        white = color::Fg(color::White),
-        reset = color::Fg(color::Reset)
-    )
-    .unwrap();
-
-    stdout.flush().unwrap();
-
-    for c in stdin.keys() {
-        match c.unwrap() {
-            _ => break,
-        }
-    }
+///  This `enum` represents the entries of the main menu.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MenuOption {
+    Journal,
+    LoadJournal,
+    Notes,
+    LoadCreateNote,
+    Trails,
+    LoadCreateTrail,
+    Search,
+    Check,
+    Find,
+    Quit,
 }
 
 /// ## JournalMessage
 ///
-///  This `enum` represents the messages that the `display-journal` view component
-///  might send to the controller.
+///  This `enum` represents the messages that the [View::display_journal] view
+///  component might send to the controller. `Previous` and `Next` ask for the
+///  closest day with a journal page, `Today` for the page of the current
+///  day, `Review` for the rollup of the week of the page, and `Log` for a
+///  new line in the log of the day. `OpenLink` carries the position of the
+///  page selected in the list of the day.
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum JournalMessage {
     EditDescription,
     EditLinks,
     Menu,
     SelectLinks,
+    OpenLink(usize),
+    Find,
+    Previous,
+    Next,
+    Today,
+    Review,
+    Log,
     Exit,
 }
 
-/// ## Display journal
-///
-///  This component represents the main interface of the journal mode. It presents
-///  the current journal page (the one for the current day, by default).
-///
-///  It shows the following elements of the journal page:
+/// ## CalendarMessage
 ///
-///  - the date;
-///  - the description;
-///  - the pages created during the day.
-///
-///  It allows access to other pages:
-///  - by pressing `d` or `D`, it opens the description editing menu;
-///  - by pressing `e' or `E`, it opens a page to create new links;
-///  - by pressing `m' or `M`, it goes back to the main menu;
-///  - by pressing `l' or `L`, it opens the link jump menu;
-///  - by pressing `q` or `Q`, it quits the application.
-///
-///  The Display journal view communicates with the controller through
-///  [JournalMessage] messages.
-
-pub fn display_journal(page: &Journal) -> JournalMessage {
-    let mut stdout = stdout().into_raw_mode().unwrap();
-    let stdin = stdin();
-
-    // Write the date of the journal page
-    write!(
-        stdout,
-        "{clear}{cursor}{goto}{red}{bold}{date}{reset_color}{reset_style}",
-        clear = clear::All,
-        cursor = cursor::Hide,
-        goto = cursor::Goto(1, 1),
-        red = color::Fg(color::Red),
-        bold = style::Bold,
-        reset_color = color::Fg(color::Reset),
-        reset_style = style::Reset,
-        date = page.date
-    )
-    .unwrap();
-
-    write!(
-        stdout,
-        "{goto}{bold}Description: {reset_style}",
-        goto = cursor::Goto(1, 3),
-        bold = style::Bold,
-        reset_style = style::Reset,
-    )
-    .unwrap();
-
-    write!(
-        stdout,
-        " {goto}{text}",
-        goto = cursor::Goto(1, 4),
-        text = page.description
-    )
-    .unwrap();
-
-    // Calculate the cursor offset after writing the description
-    let pos: u16 = page.description.len() as u16 / terminal_size().unwrap().0;
-
-    // Write the page list for the day
-    write!(
-        stdout,
-        "{goto}{bold}Pages created or edited today:{reset_style}{goto}",
-        goto = cursor::Goto(1, 6 + pos),
-        bold = style::Bold,
-        reset_style = style::Reset,
-    )
-    .unwrap();
-
-    for (i, s) in page.pages.iter().enumerate() {
-        write!(
-            stdout,
-            "{goto}[{link}]",
-            goto = cursor::Goto(1, 7 + pos + i as u16),
-            link = s
-        )
-        .unwrap();
-    }
-
-    stdout.flush().unwrap();
-
-    // Key event handler
-    for k in stdin.keys() {
-        match k.unwrap() {
-            Key::Char(c) => match c {
-                'd' | 'D' => return JournalMessage::EditDescription,
-                'e' | 'E' => return JournalMessage::EditLinks,
-                'm' | 'M' => return JournalMessage::Menu,
-                'l' | 'L' => return JournalMessage::SelectLinks,
-                'q' | 'Q' => return JournalMessage::Exit,
-                _ => {}
-            },
-            _ => {}
-        }
-    }
-
-    JournalMessage::Exit
+///  This `enum` represents the messages that the [View::calendar] view
+///  component might send to the controller. `Open` carries the selected day,
+///  whether it has a journal page or not, and `Review` asks for the rollup of
+///  its week.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CalendarMessage {
+    Open(NaiveDate),
+    Review(NaiveDate),
+    Back,
+    Exit,
 }
 
-
-/// ## Select create journal
+/// ## RollupMessage
 ///
-///  This component is displayed when the user tries to open the journal page for
-///  the current day, and it doesn't exist. The user is faced with the choice of
-///  creating one, or going back.
-
-pub fn select_create_journal() -> bool {
-    let mut stdout = stdout().into_raw_mode().unwrap();
-    let stdin = stdin();
-
-    write!(
-        stdout,
-        "{clear}{goto}The journal page for today does not exist.",
-        clear = clear::All,
-        goto = cursor::Goto(
-            terminal_size().unwrap().0 / 2 - 21,
-            terminal_size().unwrap().1 / 2
-        )
-    )
-    .unwrap();
-
-    write!(
-        stdout,
-        "{goto}Create it? (y/n)",
-        goto = cursor::Goto(
-            terminal_size().unwrap().0 / 2 - 8,
-            terminal_size().unwrap().1 / 2 + 1
-        )
-    )
-    .unwrap();
-
-    stdout.flush().unwrap();
-
-    let choice: bool = false;
-
-    // Key event handler
-    for c in stdin.keys() {
-        match c.unwrap() {
-            Key::Char(c) => match c {
-                'y' | 'Y' => return true,
-                'n' | 'N' => return false,
-                _ => {}
-            },
-            _ => {}
-        }
-    }
-
-    choice
+///  This `enum` represents the messages that the [View::display_rollup] view
+///  component might send to the controller. `Week` and `Month` switch to the
+///  week or the month of the first day shown.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RollupMessage {
+    Previous,
+    Next,
+    Week,
+    Month,
+    Export,
+    Back,
+    Exit,
 }
 
+/// ## LinkMessage
+///
+///  This `enum` represents the messages sent by the view components that
+///  show a list of pages, such as [View::link_menu]. `GotoLink` carries the
+///  position of the selected entry.
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum LinkMessage {
     Exit,
     Back,
-    GotoLink(String),
-}
-
-pub fn link_menu(list: &Vec<String>) -> LinkMessage {
-    let mut stdout = stdout().into_raw_mode().unwrap();
-    let stdin = stdin();
-
-    write!(
-        stdout,
-        "{clear}{cursor}{goto}{red}{bold} Select link: {reset_color}{reset_style}",
-        clear = clear::All,
-        cursor = cursor::Hide,
-        goto = cursor::Goto(terminal_size().unwrap().0 / 2 - 6, 1),
-        red = color::Fg(color::Red),
-        bold = style::Bold,
-        reset_color = color::Fg(color::Reset),
-        reset_style = style::Reset,
-    )
-    .unwrap();
-
-    for (i, x) in list.iter().enumerate() {
-        write!(
-            stdout,
-            "{goto}{bold}{number}. {reset_style}{text}",
-            goto = cursor::Goto(1, 2 + i as u16),
-            number = i,
-            bold = style::Bold,
-            reset_style = style::Reset,
-            text = x
-        )
-        .unwrap();
-    }
-
-    write!(
-        stdout,
-        "{goto}---",
-        goto = cursor::Goto(1, terminal_size().unwrap().1 - 2)
-    )
-    .unwrap();
-
-    write!(
-        stdout,
-        "{goto}{bold}Selection: {reset_style}",
-        goto = cursor::Goto(1, terminal_size().unwrap().1 - 1),
-        bold = style::Bold,
-        reset_style = style::Reset,
-    )
-    .unwrap();
-
-    stdout.flush().unwrap();
-
-    let mut str_buf = String::new();
-
-    for c in stdin.keys() {
-        match c.unwrap() {
-            Key::Char(c) => match c {
-                '\n' => break,
-                'q' | 'Q' => return LinkMessage::Exit,
-                _ => {
-                    str_buf.push(c);
-                    write!(stdout, "{}", c).unwrap();
-                    stdout.flush().unwrap();
-                }
-            },
-            Key::Esc => return LinkMessage::Back,
-            _ => {}
-        }
-    }
-
-    LinkMessage::GotoLink(str_buf)
+    GotoLink(usize),
 }
 
+/// ## NoteMessage
+///
+///  This `enum` represents the messages that the [View::display_note] view
+///  component might send to the controller. `OpenBacklink` carries the
+///  position of the selected backlink.
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum NoteMessage {
     Edit,
+    Rename,
     SelectLinks,
+    SelectBacklinks,
+    OpenBacklink(usize),
+    Find,
     Menu,
     Exit,
     Back,
 }
 
-pub fn display_note(page: &Note) -> NoteMessage {
-    let mut stdout = stdout().into_raw_mode().unwrap();
-    let stdin = stdin();
-
-    write!(
-        stdout,
-        "{clear}{cursor}{goto}{red}{bold}{title}{reset_color}{reset_style}",
-        clear = clear::All,
-        cursor = cursor::Hide,
-        goto = cursor::Goto(1, 1),
-        red = color::Fg(color::Red),
-        bold = style::Bold,
-        reset_color = color::Fg(color::Reset),
-        reset_style = style::Reset,
-        title = page.title
-    )
-    .unwrap();
-
-    write!(
-        stdout,
-        " {goto}{text}",
-        goto = cursor::Goto(1, 3),
-        text = page.text
-    )
-    .unwrap();
-
-    stdout.flush().unwrap();
-
-    for k in stdin.keys() {
-        match k.unwrap() {
-            Key::Char(c) => match c {
-                'e' | 'E' => return NoteMessage::Edit,
-                'l' | 'L' => return NoteMessage::SelectLinks,
-                'm' | 'M' => return NoteMessage::Menu,
-                'q' | 'Q' => return NoteMessage::Exit,
-                _ => {}
-            },
-            Key::Esc => return NoteMessage::Back,
-            _ => {}
-        }
-    }
-
-    NoteMessage::Back
-}
-
+/// ## TrailMessage
+///
+///  This `enum` represents the messages that the [View::display_trail] view
+///  component might send to the controller. `OpenHop` carries the position
+///  of the selected hop.
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum TrailMessage {
     SelectLink,
+    OpenHop(usize),
     MainMenu,
     Quit,
     EditDescription,
     AddLink,
-    RemoveLink,
+    EditHops,
+    Walk,
+    Export,
+    Find,
 }
 
-pub fn display_trail(page: &Trail) -> TrailMessage {
-    let mut stdout = stdout().into_raw_mode().unwrap();
-    let stdin = stdin();
-
-    write!(
-        stdout,
-        "{clear}{cursor}{goto}{red}{bold}{name}{reset_color}{reset_style}",
-        clear = clear::All,
-        cursor = cursor::Hide,
-        goto = cursor::Goto(1, 1),
-        red = color::Fg(color::Red),
-        bold = style::Bold,
-        reset_color = color::Fg(color::Reset),
-        reset_style = style::Reset,
-        name = page.name
-    )
-    .unwrap();
-
-    write!(
-        stdout,
-        "{goto}{bold}Description: {reset_style}",
-        goto = cursor::Goto(1, 3),
-        bold = style::Bold,
-        reset_style = style::Reset,
-    )
-    .unwrap();
-
-    write!(
-        stdout,
-        " {goto}{text}",
-        goto = cursor::Goto(1, 4),
-        text = page.description
-    )
-    .unwrap();
-
-    stdout.flush().unwrap();
-
-    let mut base_offset = 7 + page.description.chars().count() as u16 / terminal_size().unwrap().0;
-
-    for (i, x) in page.hops.iter().enumerate() {
-        let line_number = 3 + (10 + 5 + x.1.len() + x.0.len()) as u16 / terminal_size().unwrap().0;
-
-        write!(
-            stdout,
-            "{goto}{bold}Hop {number}:{reset_style} {name}",
-            goto = cursor::Goto(1, base_offset),
-            bold = style::Bold,
-            reset_style = style::Reset,
-            number = i,
-            name = x.0,
-        )
-        .unwrap();
-
-        write!(
-            stdout,
-            "{goto}{bold}Description:{reset_style} {desc}",
-            goto = cursor::Goto(
-                1,
-                base_offset + 1 + (x.0.len() as u16) / terminal_size().unwrap().0
-            ),
-            bold = style::Bold,
-            reset_style = style::Reset,
-            desc = x.1
-        )
-        .unwrap();
-
-        base_offset += line_number;
-    }
-
-    stdout.flush().unwrap();
-
-    for k in stdin.keys() {
-        match k.unwrap() {
-            Key::Char(c) => match c {
-                'l' | 'L' => return TrailMessage::SelectLink,
-                'm' | 'M' => return TrailMessage::MainMenu,
-                'q' | 'Q' => return TrailMessage::Quit,
-                'd' | 'D' => return TrailMessage::EditDescription,
-                'e' | 'E' => return TrailMessage::AddLink,
-                'r' | 'R' => return TrailMessage::RemoveLink,
-                _ => {}
-            },
-            Key::Down => {
-                write!(stdout, "{}{}", scroll::Down(1), cursor::Up(1)).unwrap();
-                stdout.flush().unwrap();
-            }
-            Key::Up => {
-                write!(stdout, "{}{}", scroll::Up(1), cursor::Down(1)).unwrap();
-                stdout.flush().unwrap();
-            }
-            _ => {}
-        }
-    }
+/// ## WalkMessage
+///
+///  This `enum` represents the messages that the [View::walk_trail] view
+///  component might send to the controller.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WalkMessage {
+    Next,
+    Previous,
+    Overview,
+    Find,
+    Menu,
+    Exit,
+}
 
-    TrailMessage::Quit
+/// ## HopMessage
+///
+///  This `enum` represents the messages that the [View::edit_hops] view
+///  component might send to the controller. Every message acting on a hop
+///  carries its position in the trail.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HopMessage {
+    Remove(usize),
+    MoveUp(usize),
+    MoveDown(usize),
+    Insert(usize),
+    EditDescription(usize),
+    Back,
 }
 
+/// ## CreateTrailMessage
+///
+///  This `enum` represents the messages that the [View::select_create_trail]
+///  view component might send to the controller.
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum CreateTrailMessage {
     CreateTrail,
     LoadTrail,
     ReturnToJournal,
 }
 
-pub fn select_create_trail() -> CreateTrailMessage {
-    let mut stdout = stdout().into_raw_mode().unwrap();
-    let stdin = stdin();
-
-    write!(
-        stdout,
-        "{clear}{goto}There is no trail currently loaded.",
-        clear = clear::All,
-        goto = cursor::Goto(
-            terminal_size().unwrap().0 / 2 - 18,
-            terminal_size().unwrap().1 / 2
-        )
-    )
-    .unwrap();
-
-    write!(
-        stdout,
-        "{goto}(c) Create one.",
-        goto = cursor::Goto(
-            terminal_size().unwrap().0 / 2 - 7,
-            terminal_size().unwrap().1 / 2 + 1
-        )
-    )
-    .unwrap();
-
-    write!(
-        stdout,
-        "{goto}(l) Load one.",
-        goto = cursor::Goto(
-            terminal_size().unwrap().0 / 2 - 6,
-            terminal_size().unwrap().1 / 2 + 2
-        )
-    )
-    .unwrap();
-
-    stdout.flush().unwrap();
-
-    for c in stdin.keys() {
-        match c.unwrap() {
-            Key::Char(c) => match c {
-                'c' | 'C' => return CreateTrailMessage::CreateTrail,
-                'l' | 'L' => return CreateTrailMessage::LoadTrail,
-                'j' | 'J' => return CreateTrailMessage::ReturnToJournal,
-                _ => {}
-            },
-            _ => {}
-        }
-    }
-
-    CreateTrailMessage::ReturnToJournal
-}
-
-pub enum MenuOption {
-    Journal,
-    LoadJournal,
-    Notes,
-    LoadCreateNote,
-    Trails,
-    LoadCreateTrail,
-    Quit,
-}
-
-pub fn display_menu() -> MenuOption {
-    let mut stdout = stdout().into_raw_mode().unwrap();
-    let stdin = stdin();
-
-    write!(
-        stdout,
-        "{clear}{cursor}{goto}{red}{bold}MAIN MENU{reset_color}{reset_style}",
-        clear = clear::All,
-        cursor = cursor::Hide,
-        goto = cursor::Goto(
-            terminal_size().unwrap().0 / 2 - 5,
-            terminal_size().unwrap().1 / 2 - 8
-        ),
-        red = color::Fg(color::Red),
-        bold = style::Bold,
-        reset_color = color::Fg(color::Reset),
-        reset_style = style::Reset
-    )
-    .unwrap();
-
-    write!(
-        stdout,
-        "{goto}{white}Select an option and press enter.{reset}",
-        // Goto the cell.
-        goto = cursor::Goto(
-            terminal_size().unwrap().0 / 2 - 16,
-            terminal_size().unwrap().1 / 2 - 6
-        ),
-        white = color::Fg(color::White),
-        reset = color::Fg(color::Reset)
-    )
-    .unwrap();
-
-    write!(
-        stdout,
-        "{goto}{white}(j) Open today's journal.{reset}",
-        // Goto the cell.
-        goto = cursor::Goto(
-            terminal_size().unwrap().0 / 2 - 12,
-            terminal_size().unwrap().1 / 2 - 4
-        ),
-        white = color::Fg(color::White),
-        reset = color::Fg(color::Reset)
-    )
-    .unwrap();
-
-    write!(
-        stdout,
-        "{goto}{white}(o) Open old journal pages.{reset}",
-        // Goto the cell.
-        goto = cursor::Goto(
-            terminal_size().unwrap().0 / 2 - 12,
-            terminal_size().unwrap().1 / 2 - 2
-        ),
-        white = color::Fg(color::White),
-        reset = color::Fg(color::Reset)
-    )
-    .unwrap();
-
-    write!(
-        stdout,
-        "{goto}{white}(n) Open the current note.{reset}",
-        // Goto the cell.
-        goto = cursor::Goto(
-            terminal_size().unwrap().0 / 2 - 13,
-            terminal_size().unwrap().1 / 2
-        ),
-        white = color::Fg(color::White),
-        reset = color::Fg(color::Reset)
-    )
-    .unwrap();
-
-    write!(
-        stdout,
-        "{goto}{white}(N) Create or load a note.{reset}",
-        // Goto the cell.
-        goto = cursor::Goto(
-            terminal_size().unwrap().0 / 2 - 13,
-            terminal_size().unwrap().1 / 2 + 2
-        ),
-        white = color::Fg(color::White),
-        reset = color::Fg(color::Reset)
-    )
-    .unwrap();
-
-    write!(
-        stdout,
-        "{goto}{white}(t) Open the current trail.{reset}",
-        // Goto the cell.
-        goto = cursor::Goto(
-            terminal_size().unwrap().0 / 2 - 13,
-            terminal_size().unwrap().1 / 2 + 4
-        ),
-        white = color::Fg(color::White),
-        reset = color::Fg(color::Reset)
-    )
-    .unwrap();
-
-    write!(
-        stdout,
-        "{goto}{white}(T) Create or load a trail.{reset}",
-        // Goto the cell.
-        goto = cursor::Goto(
-            terminal_size().unwrap().0 / 2 - 13,
-            terminal_size().unwrap().1 / 2 + 4
-        ),
-        white = color::Fg(color::White),
-        reset = color::Fg(color::Reset)
-    )
-    .unwrap();
-
-    write!(
-        stdout,
-        "{goto}{white}(q) Quit.{reset}",
-        // Goto the cell.
-        goto = cursor::Goto(
-            terminal_size().unwrap().0 / 2 - 4,
-            terminal_size().unwrap().1 / 2 + 6
-        ),
-        white = color::Fg(color::White),
-        reset = color::Fg(color::Reset)
-    )
-    .unwrap();
-
-    stdout.flush().unwrap();
-
-    for k in stdin.keys() {
-        match k.unwrap() {
-            Key::Char(c) => match c {
-                'j' => return MenuOption::Journal,
-                'o' => return MenuOption::LoadJournal,
-                'n' => return MenuOption::Notes,
-                'N' => return MenuOption::LoadCreateNote,
-                't' => return MenuOption::Trails,
-                'T' => return MenuOption::LoadCreateTrail,
-                'q' | 'Q' => return MenuOption::Quit,
-                _ => {}
-            },
-            Key::Esc => return MenuOption::Quit,
-            _ => {}
-        }
-    }
-
-    MenuOption::Quit
+/// ## DisplayErrorMessage
+///
+///  This `enum` represents the messages that the [View::display_error] view
+///  component might send to the controller.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DisplayErrorMessage {
+    Menu,
+    Exit,
 }
 
-pub fn text_editor(text: &String) -> String {
-    let mut stdout = stdout().into_raw_mode().unwrap();
-    let stdin = stdin();
-
-    write!(
-        stdout,
-        "{goto}{clear}{show_cursor}{text}",
-        goto = cursor::Goto(1, 1),
-        clear = clear::All,
-        text = text,
-        show_cursor = cursor::Show
-    )
-    .unwrap();
-
-    stdout.flush().unwrap();
-
-    let mut new_text = text.clone();
-    let mut pointer = new_text.len();
-
-    let (mut _cur_x, cur_y) = stdout.cursor_pos().unwrap();
-
-    for k in stdin.keys() {
-        match k.unwrap() {
-            Key::Char(c) => {
-                if new_text.chars().count() == pointer {
-                    new_text.push(c);
-                } else {
-                    let mut part1: String = new_text
-                        .chars()
-                        .enumerate()
-                        .filter(|(i, _s)| *i < pointer)
-                        .map(|(_i, s)| s)
-                        .collect();
-                    let part2: String = new_text
-                        .chars()
-                        .enumerate()
-                        .filter(|(i, _s)| *i >= pointer)
-                        .map(|(_i, s)| s)
-                        .collect();
-                    part1.push(c);
-                    part1.push_str(&part2);
-                    new_text = part1;
-                }
-                pointer += 1;
-            }
-            Key::Esc => break,
-            Key::Backspace => {
-                if pointer == new_text.chars().count() && new_text.chars().count() != 0 {
-                    new_text.pop();
-                    pointer -= 1;
-                } else if new_text.chars().count() == 0 {
-                    new_text.pop();
-                } else {
-                    new_text = new_text
-                        .chars()
-                        .enumerate()
-                        .map(|(i, s)| if i != pointer { Some(s) } else { None })
-                        .flatten()
-                        .collect();
-                    pointer -= 1;
-                }
-            }
-            Key::Left => {
-                if pointer > 0 {
-                    pointer -= 1;
-                }
-            }
-            Key::Right => {
-                if pointer < new_text.chars().count() {
-                    pointer += 1;
-                }
-            }
-            _ => {}
-        }
-        write!(
-            stdout,
-            "{goto}{clear}{text}",
-            goto = cursor::Goto(1, 1),
-            clear = clear::All,
-            text = new_text
-        )
-        .unwrap();
-
-        write!(
-            stdout,
-            "{}",
-            cursor::Goto(
-                (pointer as u16 + 1) % terminal_size().unwrap().0,
-                cur_y + pointer as u16 / terminal_size().unwrap().0
-            )
-        )
-        .unwrap();
-        stdout.flush().unwrap();
-    }
-
-    new_text
+/// ## FinderMessage
+///
+///  This `enum` represents the messages that the [View::fuzzy_finder] view
+///  component might send to the controller.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FinderMessage {
+    Select(usize),
+    Back,
 }
 
-pub fn edit_journal_description(desc: &String) -> String {
-    text_editor(desc)
+/// ## Scroll
+///
+///  The moves of the selection of a [ScrollList]: one item, one page, or to
+///  either end of the list.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Scroll {
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    First,
+    Last,
 }
 
-pub fn edit_note(text: &String) -> String {
-    text_editor(text)
+/// ## ScrollList
+///
+///  The selection and the viewport of a list that may not fit on screen. It
+///  doesn't draw anything by itself: a backend asks it which items are
+///  visible, draws them with the selected one highlighted, and moves the
+///  selection as the user asks.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ScrollList {
+    pub selected: usize,
+    top: usize,
 }
 
-pub fn save_error(text: &str) {
-    let mut stdout = stdout().into_raw_mode().unwrap();
-    let stdin = stdin();
-
-    write!(
-        stdout,
-        "{clear}{cursor}{goto}{red}{bold}ERROR{reset_color}{reset_style}",
-        clear = clear::All,
-        cursor = cursor::Hide,
-        goto = cursor::Goto(
-            terminal_size().unwrap().0 / 2 - 2,
-            terminal_size().unwrap().1 / 2 - 2
-        ),
-        red = color::Fg(color::Red),
-        bold = style::Bold,
-        reset_color = color::Fg(color::Reset),
-        reset_style = style::Reset
-    )
-    .unwrap();
-
-    write!(
-        stdout,
-        "{goto}{white}Could not save {name}.{reset}",
-        // Goto the cell.
-        goto = cursor::Goto(
-            terminal_size().unwrap().0 / 2 - 13,
-            terminal_size().unwrap().1 / 2 + 2
-        ),
-        white = color::Fg(color::White),
-        name = text,
-        reset = color::Fg(color::Reset)
-    )
-    .unwrap();
-
-    write!(
-        stdout,
-        "{goto}{white}Press any key to continue.{reset}",
-        // Goto the cell.
-        goto = cursor::Goto(
-            terminal_size().unwrap().0 / 2 - 13,
-            terminal_size().unwrap().1 / 2 + 4
-        ),
-        white = color::Fg(color::White),
-        reset = color::Fg(color::Reset)
-    )
-    .unwrap();
-
-    stdout.flush().unwrap();
-
-    for c in stdin.keys() {
-        match c.unwrap() {
-            _ => break,
-        }
+impl ScrollList {
+    pub fn new() -> ScrollList {
+        ScrollList::default()
     }
-}
-
-pub fn add_journal_link() -> String {
-    let mut stdout = stdout().into_raw_mode().unwrap();
-    let stdin = stdin();
-
-    write!(
-        stdout,
-        "{clear}{cursor}{goto}{red}{bold}CREATE NEW NOTE{reset_color}{reset_style}",
-        clear = clear::All,
-        cursor = cursor::Hide,
-        goto = cursor::Goto(
-            terminal_size().unwrap().0 / 2 - 8,
-            terminal_size().unwrap().1 / 2 - 2
-        ),
-        red = color::Fg(color::Red),
-        bold = style::Bold,
-        reset_color = color::Fg(color::Reset),
-        reset_style = style::Reset
-    )
-    .unwrap();
-
-    write!(
-        stdout,
-        "{goto}{bold}Page name: {reset}",
-        // Goto the cell.
-        goto = cursor::Goto(
-            terminal_size().unwrap().0 / 2 - 13,
-            terminal_size().unwrap().1 / 2 + 2
-        ),
-        bold = style::Bold,
-        reset = style::Reset
-    )
-    .unwrap();
-
-    stdout.flush().unwrap();
-
-    let mut buf = String::new();
-
-    for k in stdin.keys() {
-        match k.unwrap() {
-            Key::Char(c) => match c {
-                '\n' => return buf,
-                _ => buf.push(c),
-            },
-            Key::Backspace => {
-                buf.pop();
-            }
-            Key::Esc => return String::new(),
-            _ => {}
-        }
-
-        write!(
-            stdout,
-            "{goto}{bold}Page name: {reset}{name}",
-            // Goto the cell.
-            goto = cursor::Goto(
-                terminal_size().unwrap().0 / 2 - 13,
-                terminal_size().unwrap().1 / 2 + 2
-            ),
-            bold = style::Bold,
-            reset = style::Reset,
-            name = buf
-        )
-        .unwrap();
-
-        write!(stdout, "{}", clear::AfterCursor).unwrap();
-
-        stdout.flush().unwrap();
+    /// A list with the item at `selected` selected.
+    pub fn at(selected: usize) -> ScrollList {
+        ScrollList { selected, top: 0 }
     }
-
-    String::new()
-}
-
-pub fn select_create_note(title: &str) -> bool {
-    let mut stdout = stdout().into_raw_mode().unwrap();
-    let stdin = stdin();
-
-    write!(
-        stdout,
-        "{clear}{goto}The note for {name} does not exist.",
-        clear = clear::All,
-        goto = cursor::Goto(
-            terminal_size().unwrap().0 / 2 - 21,
-            terminal_size().unwrap().1 / 2
-        ),
-        name = title
-    )
-    .unwrap();
-
-    write!(
-        stdout,
-        "{goto}Create it? (y/n)",
-        goto = cursor::Goto(
-            terminal_size().unwrap().0 / 2 - 8,
-            terminal_size().unwrap().1 / 2 + 1
-        )
-    )
-    .unwrap();
-
-    stdout.flush().unwrap();
-
-    let choice: bool = false;
-
-    for c in stdin.keys() {
-        match c.unwrap() {
-            Key::Char(c) => match c {
-                'y' | 'Y' => return true,
-                'n' | 'N' => return false,
-                _ => {}
-            },
-            _ => {}
-        }
+    /// Moves the selection in a list of `len` items, of which `page` fit on
+    /// screen.
+    pub fn scroll(&mut self, scroll: Scroll, len: usize, page: usize) {
+        let last = len.saturating_sub(1);
+        let page = page.max(1);
+        self.selected = match scroll {
+            Scroll::Up => self.selected.saturating_sub(1),
+            Scroll::Down => (self.selected + 1).min(last),
+            Scroll::PageUp => self.selected.saturating_sub(page),
+            Scroll::PageDown => (self.selected + page).min(last),
+            Scroll::First => 0,
+            Scroll::Last => last,
+        };
     }
-
-    choice
-}
-
-pub fn reset_cursor() {
-    let mut stdout = stdout().into_raw_mode().unwrap();
-    write!(stdout, "{}", cursor::Show).unwrap();
-    stdout.flush().unwrap();
-}
-
-pub fn create_new_trail() -> String {
-    let mut stdout = stdout().into_raw_mode().unwrap();
-    let stdin = stdin();
-
-    write!(
-        stdout,
-        "{clear}{cursor}{goto}{red}{bold}CREATE NEW TRAIL{reset_color}{reset_style}",
-        clear = clear::All,
-        cursor = cursor::Hide,
-        goto = cursor::Goto(
-            terminal_size().unwrap().0 / 2 - 8,
-            terminal_size().unwrap().1 / 2 - 2
-        ),
-        red = color::Fg(color::Red),
-        bold = style::Bold,
-        reset_color = color::Fg(color::Reset),
-        reset_style = style::Reset
-    )
-    .unwrap();
-
-    write!(
-        stdout,
-        "{goto}{bold}Trail name: {reset}",
-        // Goto the cell.
-        goto = cursor::Goto(
-            terminal_size().unwrap().0 / 2 - 13,
-            terminal_size().unwrap().1 / 2 + 2
-        ),
-        bold = style::Bold,
-        reset = style::Reset
-    )
-    .unwrap();
-
-    stdout.flush().unwrap();
-
-    let mut buf = String::new();
-
-    for k in stdin.keys() {
-        match k.unwrap() {
-            Key::Char(c) => match c {
-                '\n' => return buf,
-                _ => buf.push(c),
-            },
-            Key::Backspace => {
-                buf.pop();
-            }
-            Key::Esc => return String::new(),
-            _ => {}
+    /// Scrolls the viewport so that the selected item is visible, and
+    /// returns the range of the items to draw.
+    pub fn viewport(&mut self, len: usize, page: usize) -> Range<usize> {
+        let page = page.max(1);
+        self.selected = self.selected.min(len.saturating_sub(1));
+        if self.selected < self.top {
+            self.top = self.selected;
+        } else if self.selected >= self.top + page {
+            self.top = self.selected + 1 - page;
         }
-
-        write!(
-            stdout,
-            "{goto}{bold}Trail name: {reset}{name}",
-            // Goto the cell.
-            goto = cursor::Goto(
-                terminal_size().unwrap().0 / 2 - 13,
-                terminal_size().unwrap().1 / 2 + 2
-            ),
-            bold = style::Bold,
-            reset = style::Reset,
-            name = buf
-        )
-        .unwrap();
-
-        write!(stdout, "{}", clear::AfterCursor).unwrap();
-
-        stdout.flush().unwrap();
+        self.top = self.top.min(len.saturating_sub(page));
+        self.top..(self.top + page).min(len)
     }
-
-    String::new()
-}
-
-pub fn edit_trail_description(desc: &String) -> String {
-    text_editor(desc)
 }
 
-enum TrailHopState {
-    Name,
-    Description,
-    End,
+/// ## View
+///
+///  The screens of the application. Every method shows a screen, waits for
+///  the user and returns their answer to the controller. Text prompts return
+///  an empty string when they're cancelled.
+pub trait View {
+    // START PAGE AND MENU
+
+    /// Introductory screen, shown once at startup.
+    fn start_page(&mut self);
+    fn display_menu(&mut self) -> MenuOption;
+    /// Puts the cursor back in place before the program exits.
+    fn reset_cursor(&mut self);
+
+    // JOURNAL
+
+    /// Asks whether to create the journal page of `date`.
+    fn select_create_journal(&mut self, date: &str) -> bool;
+    fn display_journal(&mut self, page: &Journal) -> JournalMessage;
+    /// Month grid starting on `selected`, marking the `days` that have a
+    /// journal page with the number of notes listed on them.
+    fn calendar(
+        &mut self,
+        selected: NaiveDate,
+        days: &BTreeMap<NaiveDate, usize>,
+    ) -> CalendarMessage;
+    /// Read-only review of the journal pages of a week or a month.
+    fn display_rollup(&mut self, rollup: &Rollup) -> RollupMessage;
+    fn edit_journal_description(&mut self, desc: &str, editor: EditorKind) -> String;
+    /// Asks for the title of a note to link from the journal page.
+    fn add_journal_link(&mut self) -> String;
+    /// Asks for a single line to add to the log of the day.
+    fn log_journal_line(&mut self) -> String;
+
+    // NOTES
+
+    /// Asks whether to create a note that doesn't exist yet.
+    fn select_create_note(&mut self, title: &str) -> bool;
+    /// Asks for the title of a note to open or create.
+    fn load_create_note(&mut self) -> String;
+    fn display_note(&mut self, page: &Note, backlinks: &[PageRef]) -> NoteMessage;
+    /// Edits the text of a note, recording the changes in `history`.
+    fn edit_note(&mut self, text: &str, history: &mut History, editor: EditorKind) -> String;
+    /// Asks for the new title of a note.
+    fn rename_note_prompt(&mut self, title: &str) -> String;
+    /// Previews the changes of a rename and asks whether to apply them.
+    fn confirm_rename(&mut self, report: &RenameReport) -> bool;
+    fn rename_done(&mut self, report: &RenameReport);
+
+    // TRAILS
+
+    fn select_create_trail(&mut self) -> CreateTrailMessage;
+    /// Asks for the name of a new trail.
+    fn create_new_trail(&mut self) -> String;
+    fn display_trail(&mut self, page: &Trail) -> TrailMessage;
+    fn edit_trail_description(&mut self, desc: &str, editor: EditorKind) -> String;
+    /// Asks for the note and the description of a new hop.
+    fn add_trail_hop(&mut self) -> (String, String);
+    /// Lists the hops of a trail with the hop at `selected` highlighted.
+    fn edit_hops(&mut self, trail: &Trail, selected: usize) -> HopMessage;
+    /// Edits the description of a hop. Returns `None` if it's cancelled.
+    fn edit_hop_description(&mut self, description: &str) -> Option<String>;
+    /// Shows the hop at position `hop` along with its note, if it exists.
+    fn walk_trail(&mut self, trail: &Trail, hop: usize, note: Option<&Note>) -> WalkMessage;
+    /// Lets the user pick the export options. Returns `false` if the export
+    /// is cancelled.
+    fn export_options(&mut self, name: &str, options: &mut CompileOptions) -> bool;
+    fn export_done(&mut self, paths: &[PathBuf]);
+
+    // LINKS, SEARCH AND VAULT
+
+    /// Lists `list` under `heading` and lets the user pick one entry.
+    fn link_menu(&mut self, heading: &str, list: &[String]) -> LinkMessage;
+    /// Fuzzy finder over `pages`.
+    fn fuzzy_finder(&mut self, heading: &str, pages: &[PageRef]) -> FinderMessage;
+    fn search_prompt(&mut self) -> String;
+    fn search_results(&mut self, query: &str, hits: &[SearchHit]) -> LinkMessage;
+    fn health_check(&mut self, report: &HealthReport) -> LinkMessage;
+
+    // ERRORS
+
+    /// Reports that the `text` (e.g. "note") could not be saved.
+    fn save_error(&mut self, text: &str, error: &FileError);
+    fn display_error(&mut self, message: &str, details: Option<&str>) -> DisplayErrorMessage;
 }
 
-pub fn add_trail_hop() -> (String, String) {
-    let mut stdout = stdout().into_raw_mode().unwrap();
-    let stdin = stdin();
-
-    write!(
-        stdout,
-        "{clear}{cursor}{goto}{red}{bold}CREATE NEW HOP{reset_color}{reset_style}",
-        clear = clear::All,
-        cursor = cursor::Hide,
-        goto = cursor::Goto(
-            terminal_size().unwrap().0 / 2 - 7,
-            terminal_size().unwrap().1 / 2 - 2
-        ),
-        red = color::Fg(color::Red),
-        bold = style::Bold,
-        reset_color = color::Fg(color::Reset),
-        reset_style = style::Reset
-    )
-    .unwrap();
-
-    write!(
-        stdout,
-        "{goto}{bold}Hop name: {reset}",
-        // Goto the cell.
-        goto = cursor::Goto(
-            terminal_size().unwrap().0 / 2 - 13,
-            terminal_size().unwrap().1 / 2 + 2
-        ),
-        bold = style::Bold,
-        reset = style::Reset
-    )
-    .unwrap();
-
-    stdout.flush().unwrap();
-
-    let mut name_buf = String::new();
-    let mut desc_buf = String::new();
-
-    let keys = stdin.keys();
-    let mut current_state = TrailHopState::Name;
-
-    for k in keys.into_iter() {
-        match k.unwrap() {
-            Key::Char(c) => match c {
-                '\n' => match current_state {
-                    TrailHopState::Name => current_state = TrailHopState::Description,
-                    TrailHopState::Description => current_state = TrailHopState::End,
-                    TrailHopState::End => break,
-                },
-                _ => match current_state {
-                    TrailHopState::Name => name_buf.push(c),
-                    TrailHopState::Description => desc_buf.push(c),
-                    TrailHopState::End => {}
-                },
-            },
-            Key::Backspace => {
-                name_buf.pop();
-            }
-            Key::Esc => return (String::new(), String::new()),
-            _ => {}
-        }
-
-        match current_state {
-            TrailHopState::Name => {
-                write!(
-                    stdout,
-                    "{goto}{bold}Hop name: {reset}{name}",
-                    // Goto the cell.
-                    goto = cursor::Goto(
-                        terminal_size().unwrap().0 / 2 - 13,
-                        terminal_size().unwrap().1 / 2 + 2
-                    ),
-                    bold = style::Bold,
-                    reset = style::Reset,
-                    name = name_buf
-                )
-                .unwrap();
-
-                write!(stdout, "{}", clear::AfterCursor).unwrap();
-            }
-            TrailHopState::Description => {
-                write!(
-                    stdout,
-                    "{goto}{bold}Hop description: {reset}{description}",
-                    // Goto the cell.
-                    goto = cursor::Goto(
-                        terminal_size().unwrap().0 / 2 - 13,
-                        terminal_size().unwrap().1 / 2 + 2
-                    ),
-                    bold = style::Bold,
-                    reset = style::Reset,
-                    description = desc_buf
-                )
-                .unwrap();
-            }
-            _ => {}
-        }
-
-        stdout.flush().unwrap();
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_viewport_follows_the_selection() {
+        let mut list = ScrollList::new();
+        assert_eq!(list.viewport(10, 4), 0..4);
+
+        list.scroll(Scroll::PageDown, 10, 4);
+        assert_eq!((list.selected, list.viewport(10, 4)), (4, 1..5));
+        list.scroll(Scroll::Last, 10, 4);
+        assert_eq!((list.selected, list.viewport(10, 4)), (9, 6..10));
+        list.scroll(Scroll::Down, 10, 4);
+        assert_eq!(list.selected, 9);
+        list.scroll(Scroll::PageUp, 10, 4);
+        list.scroll(Scroll::Up, 10, 4);
+        assert_eq!((list.selected, list.viewport(10, 4)), (4, 4..8));
+        list.scroll(Scroll::First, 10, 4);
+        assert_eq!((list.selected, list.viewport(10, 4)), (0, 0..4));
     }
 
-    (name_buf, desc_buf)
-}
+    #[test]
+    fn the_viewport_shrinks_with_the_list() {
+        let mut list = ScrollList::at(8);
+        assert_eq!(list.viewport(10, 4), 5..9);
 
-pub enum DisplayErrorMessage {
-    Menu,
-    Exit,
-}
-
-pub fn display_error(message: &str) -> DisplayErrorMessage {
-    let mut stdout = stdout().into_raw_mode().unwrap();
-    let stdin = stdin();
-
-    let line_number = message.chars().count() as u16 / terminal_size().unwrap().0;
-
-    write!(
-        stdout,
-        "{clear}{red}{goto}{bold}ERROR!{reset_color}{reset_font}",
-        clear = clear::All,
-        goto = cursor::Goto(terminal_size().unwrap().0 / 2 - 3, 1),
-        red = color::Fg(color::Red),
-        bold = style::Bold,
-        reset_color = color::Fg(color::Reset),
-        reset_font = style::Reset,
-    )
-    .unwrap();
-
-    write!(
-        stdout,
-        "{goto}{text}",
-        goto = cursor::Goto(0, 3),
-        text = message
-    )
-    .unwrap();
-
-    write!(
-        stdout,
-        "{goto1}(q) Exit{goto2}(m) Menu",
-        goto1 = cursor::Goto(terminal_size().unwrap().0 / 2 - 4, 5 + line_number),
-        goto2 = cursor::Goto(terminal_size().unwrap().0 / 2 - 4, 7 + line_number)
-    )
-    .unwrap();
-
-    stdout.flush().unwrap();
-
-    for k in stdin.keys() {
-        match k.unwrap() {
-            Key::Char(c) => match c {
-                'm' | 'M' => return DisplayErrorMessage::Menu,
-                'q' | 'Q' => return DisplayErrorMessage::Exit,
-                _ => {}
-            },
-            _ => {}
-        }
+        // Items were removed, or the screen grew
+        assert_eq!((list.viewport(3, 4), list.selected), (0..3, 2));
+        assert_eq!(list.viewport(0, 4), 0..0);
+        assert_eq!(list.viewport(10, 0), 0..1);
     }
-
-    return DisplayErrorMessage::Menu;
 }
@@ -0,0 +1,164 @@
+//! Tests of the command line: the binary is run headless on temporary
+//! vaults, and its output and exit code are checked.
+#![cfg(feature = "tui")]
+
+mod common;
+
+use common::TempVault;
+
+use std::fs;
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+/// Runs the binary on the vault with `args`, writing `stdin` to it.
+fn vannevar(vault: &TempVault, args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_vannevar"))
+        .arg("--vault")
+        .arg(vault.root())
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[test]
+fn new_notes_can_be_shown() {
+    let vault = TempVault::new("cli-new-note");
+
+    let created = vannevar(&vault, &["new-note", "Memex", "A device."], "");
+    assert_eq!(created.status.code(), Some(0), "{}", stderr(&created));
+    assert!(stdout(&created).trim_end().ends_with("notes/Memex"));
+
+    let shown = vannevar(&vault, &["show", "Memex"], "");
+    assert_eq!(shown.status.code(), Some(0), "{}", stderr(&shown));
+    assert_eq!(stdout(&shown), "A device.\n");
+
+    let again = vannevar(&vault, &["new-note", "Memex"], "");
+    assert_eq!(again.status.code(), Some(1));
+    assert_eq!(stderr(&again), "A note titled Memex already exists.\n");
+}
+
+#[test]
+fn text_is_read_from_standard_input() {
+    let vault = TempVault::new("cli-stdin");
+
+    let created = vannevar(&vault, &["new-note", "Bush", "-"], "An engineer.\n\n");
+    assert_eq!(created.status.code(), Some(0), "{}", stderr(&created));
+    let appended = vannevar(
+        &vault,
+        &[
+            "append-journal",
+            "--date",
+            "2024-03-11",
+            "--page",
+            "Bush",
+            "-",
+        ],
+        "Met [Bush].\n",
+    );
+    assert_eq!(appended.status.code(), Some(0), "{}", stderr(&appended));
+
+    assert_eq!(
+        stdout(&vannevar(&vault, &["show", "Bush"], "")),
+        "An engineer.\n"
+    );
+    assert_eq!(
+        stdout(&vannevar(&vault, &["show", "journal/2024-03-11"], "")),
+        "Met [Bush].\n---\n[Bush]\n"
+    );
+    assert_eq!(
+        stdout(&vannevar(&vault, &["list"], "")),
+        "notes/Bush\njournal/2024-03-11\n"
+    );
+}
+
+#[test]
+fn invalid_journal_pages_are_rejected() {
+    let vault = TempVault::new("cli-invalid-pages");
+
+    for page in ["", "  ", "[Memex]", "Memex]", "Vannevar\nBush"] {
+        let args = [
+            "append-journal",
+            "--date",
+            "2024-03-11",
+            "--page",
+            "Bush",
+            "--page",
+            page,
+            "Met Bush.",
+        ];
+        let output = vannevar(&vault, &args, "");
+        assert_eq!(output.status.code(), Some(2), "{:?}", page);
+        assert_eq!(
+            stderr(&output),
+            format!("Not a valid page title: {}\n", page.trim())
+        );
+    }
+    let shown = vannevar(&vault, &["show", "journal/2024-03-11"], "");
+    assert_eq!(shown.status.code(), Some(1));
+}
+
+#[test]
+fn missing_pages_fail() {
+    let vault = TempVault::new("cli-missing");
+
+    let shown = vannevar(&vault, &["show", "trails/Tour"], "");
+
+    assert_eq!(shown.status.code(), Some(1));
+    assert!(stdout(&shown).is_empty());
+    assert_eq!(stderr(&shown), "There's no trail named Tour.\n");
+}
+
+#[test]
+fn vaults_that_cant_be_opened_fail_with_the_reason() {
+    let vault = TempVault::new("cli-no-vault");
+    fs::write(vault.root(), "Not a folder.").unwrap();
+
+    let output = vannevar(&vault, &["list"], "");
+    fs::remove_file(vault.root()).unwrap();
+
+    assert_eq!(output.status.code(), Some(1));
+    let prefix = format!(
+        "Could not open the vault at {}: could not write {}",
+        vault.root().display(),
+        vault.root().join("journal").display()
+    );
+    assert!(stderr(&output).starts_with(&prefix), "{}", stderr(&output));
+}
+
+#[test]
+fn bad_arguments_print_the_usage() {
+    let vault = TempVault::new("cli-usage");
+
+    for args in [
+        &["publish"][..],
+        &["show"],
+        &["new-note", "--draft", "Memex"],
+    ] {
+        let output = vannevar(&vault, args, "");
+        assert_eq!(output.status.code(), Some(2), "{:?}", args);
+        assert!(stdout(&output).is_empty());
+        assert!(
+            stderr(&output).contains("\n\nUsage: vannevar"),
+            "{:?}",
+            args
+        );
+    }
+}
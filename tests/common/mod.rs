@@ -0,0 +1,289 @@
+//! A scripted, in-memory backend of the [View] trait: every screen takes its
+//! answer from a queue written by the test, and records its name so that the
+//! test can check which screens were shown.
+
+// Not every test uses every answer.
+#![allow(dead_code)]
+
+use std::collections::{BTreeMap, VecDeque};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use chrono::NaiveDate;
+use vannevar::controller::{Controller, CurrentPage};
+use vannevar::editor::History;
+use vannevar::view::*;
+use vannevar::{
+    CompileOptions, EditorKind, FileError, HealthReport, Journal, Note, PageRef, RenameReport,
+    Rollup, SearchHit, Trail, Vault,
+};
+
+/// An answer to a screen of the [Script].
+#[derive(Debug)]
+pub enum Answer {
+    Menu(MenuOption),
+    Journal(JournalMessage),
+    Calendar(CalendarMessage),
+    Rollup(RollupMessage),
+    Link(LinkMessage),
+    Finder(FinderMessage),
+    Note(NoteMessage),
+    Trail(TrailMessage),
+    CreateTrail(CreateTrailMessage),
+    Hops(HopMessage),
+    Walk(WalkMessage),
+    Error(DisplayErrorMessage),
+    Yes,
+    No,
+    /// The text typed in a prompt or an editor.
+    Text(String),
+    /// The note and the description of a new hop.
+    Hop(String, String),
+}
+
+pub fn text(s: &str) -> Answer {
+    Answer::Text(String::from(s))
+}
+
+pub fn hop(note: &str, description: &str) -> Answer {
+    Answer::Hop(String::from(note), String::from(description))
+}
+
+#[derive(Default)]
+pub struct Script {
+    answers: VecDeque<Answer>,
+    /// The screens shown so far, in order.
+    pub screens: Vec<String>,
+}
+
+impl Script {
+    pub fn new(answers: Vec<Answer>) -> Script {
+        Script {
+            answers: answers.into(),
+            screens: Vec::new(),
+        }
+    }
+    fn show(&mut self, screen: &str) {
+        self.screens.push(String::from(screen));
+    }
+    fn answer(&mut self, screen: &str) -> Answer {
+        self.show(screen);
+        match self.answers.pop_front() {
+            Some(a) => a,
+            None => panic!("the script has no answer left for the {} screen", screen),
+        }
+    }
+    fn text(&mut self, screen: &str) -> String {
+        match self.answer(screen) {
+            Answer::Text(s) => s,
+            a => unexpected(screen, a),
+        }
+    }
+    fn confirm(&mut self, screen: &str) -> bool {
+        match self.answer(screen) {
+            Answer::Yes => true,
+            Answer::No => false,
+            a => unexpected(screen, a),
+        }
+    }
+    fn link(&mut self, screen: &str) -> LinkMessage {
+        match self.answer(screen) {
+            Answer::Link(m) => m,
+            a => unexpected(screen, a),
+        }
+    }
+}
+
+fn unexpected(screen: &str, answer: Answer) -> ! {
+    panic!("the {} screen can't be answered with {:?}", screen, answer)
+}
+
+impl View for Script {
+    fn start_page(&mut self) {
+        self.show("start page");
+    }
+    fn display_menu(&mut self) -> MenuOption {
+        match self.answer("menu") {
+            Answer::Menu(m) => m,
+            a => unexpected("menu", a),
+        }
+    }
+    fn reset_cursor(&mut self) {}
+    fn select_create_journal(&mut self, _date: &str) -> bool {
+        self.confirm("create journal")
+    }
+    fn display_journal(&mut self, _page: &Journal) -> JournalMessage {
+        match self.answer("journal") {
+            Answer::Journal(m) => m,
+            a => unexpected("journal", a),
+        }
+    }
+    fn calendar(
+        &mut self,
+        _selected: NaiveDate,
+        _days: &BTreeMap<NaiveDate, usize>,
+    ) -> CalendarMessage {
+        match self.answer("calendar") {
+            Answer::Calendar(m) => m,
+            a => unexpected("calendar", a),
+        }
+    }
+    fn display_rollup(&mut self, _rollup: &Rollup) -> RollupMessage {
+        match self.answer("rollup") {
+            Answer::Rollup(m) => m,
+            a => unexpected("rollup", a),
+        }
+    }
+    fn edit_journal_description(&mut self, _desc: &str, _editor: EditorKind) -> String {
+        self.text("journal description")
+    }
+    fn add_journal_link(&mut self) -> String {
+        self.text("journal link")
+    }
+    fn log_journal_line(&mut self) -> String {
+        self.text("journal log")
+    }
+    fn select_create_note(&mut self, _title: &str) -> bool {
+        self.confirm("create note")
+    }
+    fn display_note(&mut self, _page: &Note, _backlinks: &[PageRef]) -> NoteMessage {
+        match self.answer("note") {
+            Answer::Note(m) => m,
+            a => unexpected("note", a),
+        }
+    }
+    fn edit_note(&mut self, _text: &str, _history: &mut History, _editor: EditorKind) -> String {
+        self.text("note editor")
+    }
+    fn load_create_note(&mut self) -> String {
+        self.text("note title")
+    }
+    fn rename_note_prompt(&mut self, _title: &str) -> String {
+        self.text("rename note")
+    }
+    fn confirm_rename(&mut self, _report: &RenameReport) -> bool {
+        self.confirm("confirm rename")
+    }
+    fn rename_done(&mut self, _report: &RenameReport) {
+        self.show("rename done");
+    }
+    fn select_create_trail(&mut self) -> CreateTrailMessage {
+        match self.answer("create trail") {
+            Answer::CreateTrail(m) => m,
+            a => unexpected("create trail", a),
+        }
+    }
+    fn create_new_trail(&mut self) -> String {
+        self.text("trail name")
+    }
+    fn display_trail(&mut self, _page: &Trail) -> TrailMessage {
+        match self.answer("trail") {
+            Answer::Trail(m) => m,
+            a => unexpected("trail", a),
+        }
+    }
+    fn edit_trail_description(&mut self, _desc: &str, _editor: EditorKind) -> String {
+        self.text("trail description")
+    }
+    fn add_trail_hop(&mut self) -> (String, String) {
+        match self.answer("add hop") {
+            Answer::Hop(note, description) => (note, description),
+            a => unexpected("add hop", a),
+        }
+    }
+    fn edit_hops(&mut self, _trail: &Trail, _selected: usize) -> HopMessage {
+        match self.answer("edit hops") {
+            Answer::Hops(m) => m,
+            a => unexpected("edit hops", a),
+        }
+    }
+    fn edit_hop_description(&mut self, _description: &str) -> Option<String> {
+        Some(self.text("hop description"))
+    }
+    fn walk_trail(&mut self, _trail: &Trail, _hop: usize, _note: Option<&Note>) -> WalkMessage {
+        match self.answer("walk") {
+            Answer::Walk(m) => m,
+            a => unexpected("walk", a),
+        }
+    }
+    fn export_options(&mut self, _name: &str, _options: &mut CompileOptions) -> bool {
+        self.confirm("export options")
+    }
+    fn export_done(&mut self, _paths: &[PathBuf]) {
+        self.show("export done");
+    }
+    fn link_menu(&mut self, _heading: &str, _list: &[String]) -> LinkMessage {
+        self.link("links")
+    }
+    fn fuzzy_finder(&mut self, _heading: &str, _pages: &[PageRef]) -> FinderMessage {
+        match self.answer("finder") {
+            Answer::Finder(m) => m,
+            a => unexpected("finder", a),
+        }
+    }
+    fn search_prompt(&mut self) -> String {
+        self.text("search")
+    }
+    fn search_results(&mut self, _query: &str, _hits: &[SearchHit]) -> LinkMessage {
+        self.link("search results")
+    }
+    fn health_check(&mut self, _report: &HealthReport) -> LinkMessage {
+        self.link("health check")
+    }
+    fn save_error(&mut self, _text: &str, _error: &FileError) {
+        self.show("save error");
+    }
+    fn display_error(&mut self, _message: &str, _details: Option<&str>) -> DisplayErrorMessage {
+        match self.answer("error") {
+            Answer::Error(m) => m,
+            a => unexpected("error", a),
+        }
+    }
+}
+
+/// Steps through the controller until the user quits, and returns every
+/// page it went through (the one the user quit from included). Fails if
+/// the script wasn't answered to the end.
+pub fn run(controller: &mut Controller<Script>) -> Vec<CurrentPage> {
+    let mut pages = vec![controller.current_page().clone()];
+    while controller.step() {
+        pages.push(controller.current_page().clone());
+    }
+    assert!(
+        controller.view().answers.is_empty(),
+        "unused answers: {:?}",
+        controller.view().answers
+    );
+    pages
+}
+
+/// A vault in a fresh temporary directory, removed when dropped.
+pub struct TempVault {
+    root: PathBuf,
+}
+
+impl TempVault {
+    pub fn new(name: &str) -> TempVault {
+        let root = env::temp_dir().join(format!("vannevar-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&root);
+        TempVault { root }
+    }
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+    pub fn open(&self) -> Vault {
+        Vault::open(self.root.clone()).unwrap()
+    }
+    /// A controller on this vault, answering its screens from `answers`.
+    pub fn controller(&self, answers: Vec<Answer>) -> Controller<Script> {
+        Controller::new(self.open(), Script::new(answers))
+    }
+}
+
+impl Drop for TempVault {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
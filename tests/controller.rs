@@ -0,0 +1,775 @@
+//! End-to-end runs of the controller, answering its screens from a script
+//! and checking the pages it goes through and what ends up in the vault.
+mod common;
+
+use common::{hop, run, text, Answer, TempVault};
+use vannevar::controller::CurrentPage;
+use vannevar::view::*;
+use vannevar::{
+    journal_date, journal_days, journal_neighbours, load_journal_page, load_note, load_trail,
+    FileError, Folder, Journal, Note, Period, Rollup, Trail,
+};
+
+use std::fs;
+
+#[test]
+fn first_start_creates_todays_journal() {
+    let vault = TempVault::new("first-start");
+    let mut c = vault.controller(vec![
+        Answer::Yes,
+        Answer::Journal(JournalMessage::Menu),
+        Answer::Menu(MenuOption::Quit),
+    ]);
+
+    let pages = run(&mut c);
+
+    assert_eq!(
+        pages,
+        vec![
+            CurrentPage::StartPage,
+            CurrentPage::CreateNewJournal,
+            CurrentPage::JournalView,
+            CurrentPage::MainMenu,
+        ]
+    );
+    let date = &c.model().current_date;
+    assert_eq!(&load_journal_page(&vault.open(), date).unwrap().date, date);
+}
+
+#[test]
+fn declining_the_journal_opens_the_menu() {
+    let vault = TempVault::new("decline-journal");
+    let mut c = vault.controller(vec![Answer::No, Answer::Menu(MenuOption::Quit)]);
+
+    let pages = run(&mut c);
+
+    assert_eq!(pages.last(), Some(&CurrentPage::MainMenu));
+    assert_eq!(
+        c.view().screens,
+        vec!["start page", "create journal", "menu"]
+    );
+    assert!(load_journal_page(&vault.open(), &c.model().current_date).is_err());
+}
+
+#[test]
+fn journal_links_create_notes() {
+    let vault = TempVault::new("journal-links");
+    let mut c = vault.controller(vec![
+        Answer::Yes,
+        Answer::Journal(JournalMessage::EditLinks),
+        text("Memex"),
+        Answer::Journal(JournalMessage::SelectLinks),
+        Answer::Finder(FinderMessage::Select(0)),
+        Answer::Yes,
+        Answer::Note(NoteMessage::Edit),
+        text("A device imagined by [Vannevar Bush]."),
+        Answer::Note(NoteMessage::Menu),
+        Answer::Menu(MenuOption::Quit),
+    ]);
+
+    let pages = run(&mut c);
+
+    assert_eq!(
+        pages[3..],
+        [
+            CurrentPage::JournalAddLink,
+            CurrentPage::JournalView,
+            CurrentPage::SelectLink(
+                vec![String::from("Memex")],
+                Box::new(CurrentPage::JournalView)
+            ),
+            CurrentPage::NoteView,
+            CurrentPage::NoteEdit,
+            CurrentPage::NoteView,
+            CurrentPage::MainMenu,
+        ]
+    );
+    let v = vault.open();
+    let note = load_note(&v, "Memex").unwrap();
+    assert_eq!(note.text, "A device imagined by [Vannevar Bush].");
+    assert_eq!(note.links, vec!["Vannevar Bush"]);
+    let journal = load_journal_page(&v, &c.model().current_date).unwrap();
+    assert_eq!(journal.pages, vec!["Memex"]);
+}
+
+#[test]
+fn trails_are_created_and_walked() {
+    let vault = TempVault::new("trails");
+    Note::from_str("Memex", String::from("A device."))
+        .save(&vault.open())
+        .unwrap();
+    let mut c = vault.controller(vec![
+        Answer::No,
+        Answer::Menu(MenuOption::Trails),
+        Answer::CreateTrail(CreateTrailMessage::CreateTrail),
+        text("Tour"),
+        Answer::Trail(TrailMessage::AddLink),
+        hop("Memex", "Start here"),
+        Answer::Trail(TrailMessage::Walk),
+        Answer::Walk(WalkMessage::Next),
+        Answer::Walk(WalkMessage::Overview),
+        Answer::Trail(TrailMessage::Quit),
+    ]);
+
+    let pages = run(&mut c);
+
+    assert_eq!(
+        pages[3..],
+        [
+            CurrentPage::TrailView,
+            CurrentPage::SelectCreateTrail,
+            CurrentPage::CreateNewTrail,
+            CurrentPage::TrailView,
+            CurrentPage::TrailAddHop,
+            CurrentPage::TrailView,
+            CurrentPage::TrailWalk(0),
+            // There's no hop after the last one
+            CurrentPage::TrailWalk(0),
+            CurrentPage::TrailView,
+        ]
+    );
+    let trail = load_trail(&vault.open(), "Tour").unwrap();
+    assert_eq!(
+        trail.hops,
+        vec![(String::from("Memex"), String::from("Start here"))]
+    );
+}
+
+#[test]
+fn renames_are_previewed_and_applied() {
+    let vault = TempVault::new("rename");
+    Note::from_str("Memex", String::from("A device."))
+        .save(&vault.open())
+        .unwrap();
+    let mut c = vault.controller(vec![
+        Answer::No,
+        Answer::Menu(MenuOption::Find),
+        Answer::Finder(FinderMessage::Select(0)),
+        Answer::Note(NoteMessage::Rename),
+        text("Memory extender"),
+        Answer::Yes,
+        Answer::Note(NoteMessage::Menu),
+        Answer::Menu(MenuOption::Quit),
+    ]);
+
+    let pages = run(&mut c);
+
+    assert!(
+        matches!(pages[6], CurrentPage::NoteRenamePreview(ref r) if r.new == "Memory extender")
+    );
+    assert!(c.view().screens.contains(&String::from("rename done")));
+    assert_eq!(c.model().note.title, "Memory extender");
+    let v = vault.open();
+    assert!(load_note(&v, "Memory extender").is_ok());
+    assert!(load_note(&v, "Memex").is_err_and(|e| e.is_not_found()));
+}
+
+#[test]
+fn search_results_open_the_note() {
+    let vault = TempVault::new("search");
+    Note::from_str("Memex", String::from("Imagined by Vannevar Bush."))
+        .save(&vault.open())
+        .unwrap();
+    let mut c = vault.controller(vec![
+        Answer::No,
+        Answer::Menu(MenuOption::Search),
+        text("bush"),
+        Answer::Link(LinkMessage::GotoLink(0)),
+        Answer::Note(NoteMessage::Menu),
+        Answer::Menu(MenuOption::Quit),
+    ]);
+
+    let pages = run(&mut c);
+
+    assert!(
+        matches!(&pages[4], CurrentPage::SearchResults(q, hits) if q == "bush" && hits.len() == 1)
+    );
+    assert_eq!(pages[5], CurrentPage::NoteView);
+    assert_eq!(c.model().note.title, "Memex");
+}
+
+#[test]
+fn failed_saves_show_the_error_and_go_back() {
+    let vault = TempVault::new("save-error");
+    let mut c = vault.controller(vec![
+        Answer::No,
+        Answer::Menu(MenuOption::Trails),
+        Answer::CreateTrail(CreateTrailMessage::CreateTrail),
+        text("a/b"),
+        Answer::Trail(TrailMessage::MainMenu),
+        Answer::Menu(MenuOption::Quit),
+    ]);
+
+    let pages = run(&mut c);
+
+    assert!(matches!(
+        &pages[7],
+        CurrentPage::SaveError(back, FileError::NameError(name)) if **back == CurrentPage::MainMenu && name == "a/b"
+    ));
+    assert_eq!(pages[8], CurrentPage::MainMenu);
+    assert!(c.view().screens.contains(&String::from("save error")));
+}
+
+#[test]
+fn unreadable_pages_show_an_error_screen() {
+    let vault = TempVault::new("unreadable");
+    let v = vault.open();
+    fs::write(v.path(Folder::Notes, "Broken"), [0x66, 0xff, 0xfe]).unwrap();
+    let mut c = vault.controller(vec![
+        Answer::No,
+        Answer::Menu(MenuOption::Find),
+        Answer::Finder(FinderMessage::Select(0)),
+        Answer::Error(DisplayErrorMessage::Menu),
+        Answer::Menu(MenuOption::Quit),
+    ]);
+
+    let pages = run(&mut c);
+
+    assert!(matches!(
+        &pages[4],
+        CurrentPage::UnexpectedError(message, Some(details)) if message.contains("Broken") && details.contains(":1:2:")
+    ));
+    assert_eq!(pages[5], CurrentPage::MainMenu);
+}
+
+#[test]
+fn quitting_from_a_screen_stops_the_controller() {
+    let vault = TempVault::new("quit");
+    let mut c = vault.controller(vec![Answer::Yes, Answer::Journal(JournalMessage::Exit)]);
+
+    assert!(c.step());
+    assert_eq!(c.current_page(), &CurrentPage::CreateNewJournal);
+    assert!(c.step());
+    assert_eq!(c.current_page(), &CurrentPage::JournalView);
+    assert!(!c.step());
+    assert!(vault.root().join("journal").is_dir());
+}
+
+#[test]
+fn the_calendar_opens_past_pages_read_only() {
+    let vault = TempVault::new("calendar-open");
+    let v = vault.open();
+    let mut page = Journal::for_date("2020-02-29");
+    page.pages.push(String::from("Memex"));
+    page.save(&v).unwrap();
+    let day = journal_date("2020-02-29").unwrap();
+    assert_eq!(journal_days(&v).get(&day), Some(&1));
+    let mut c = vault.controller(vec![
+        Answer::No,
+        Answer::Menu(MenuOption::LoadJournal),
+        Answer::Calendar(CalendarMessage::Open(day)),
+        Answer::Journal(JournalMessage::Exit),
+    ]);
+
+    let pages = run(&mut c);
+
+    assert_eq!(pages[4], CurrentPage::JournalViewReadOnly);
+    assert_eq!(c.view().screens[3], "calendar");
+}
+
+#[test]
+fn the_calendar_creates_missing_pages() {
+    let vault = TempVault::new("calendar-create");
+    let day = journal_date("2021-03-01").unwrap();
+    let mut c = vault.controller(vec![
+        Answer::No,
+        Answer::Menu(MenuOption::LoadJournal),
+        Answer::Calendar(CalendarMessage::Open(day)),
+        Answer::No,
+        Answer::Calendar(CalendarMessage::Open(day)),
+        Answer::Yes,
+        Answer::Journal(JournalMessage::EditDescription),
+        text("Caught up on a skipped day."),
+        Answer::Journal(JournalMessage::Menu),
+        Answer::Menu(MenuOption::Quit),
+    ]);
+
+    let pages = run(&mut c);
+
+    let today = journal_date(&c.model().current_date).unwrap();
+    assert_eq!(
+        pages[3..8],
+        [
+            CurrentPage::Calendar(today),
+            CurrentPage::CreatePastJournal(day),
+            CurrentPage::Calendar(day),
+            CurrentPage::CreatePastJournal(day),
+            CurrentPage::JournalView,
+        ]
+    );
+    let page = load_journal_page(&vault.open(), "2021-03-01").unwrap();
+    assert_eq!(page.description, "Caught up on a skipped day.");
+    assert!(load_journal_page(&vault.open(), &c.model().current_date).is_err());
+}
+
+#[test]
+fn the_journal_steps_through_existing_pages() {
+    let vault = TempVault::new("journal-steps");
+    let v = vault.open();
+    for date in ["2022-05-02", "2022-05-06", "2022-05-09"] {
+        Journal::for_date(date).save(&v).unwrap();
+    }
+    let day = |name| journal_date(name).unwrap();
+    assert_eq!(
+        journal_neighbours(&v, day("2022-05-06")),
+        (Some(day("2022-05-02")), Some(day("2022-05-09")))
+    );
+    let mut c = vault.controller(vec![
+        Answer::No,
+        Answer::Menu(MenuOption::LoadJournal),
+        Answer::Calendar(CalendarMessage::Open(day("2022-05-06"))),
+        Answer::Journal(JournalMessage::Previous),
+        // There's no page before the first one
+        Answer::Journal(JournalMessage::Previous),
+        Answer::Journal(JournalMessage::Next),
+        Answer::Journal(JournalMessage::Next),
+        Answer::Journal(JournalMessage::Today),
+        Answer::Yes,
+        Answer::Journal(JournalMessage::Previous),
+        Answer::Journal(JournalMessage::Today),
+        Answer::Journal(JournalMessage::Exit),
+    ]);
+
+    let pages = run(&mut c);
+
+    let today = day(&c.model().current_date);
+    assert_eq!(
+        pages[5..],
+        [
+            CurrentPage::JournalGoto(day("2022-05-02")),
+            CurrentPage::JournalViewReadOnly,
+            CurrentPage::JournalViewReadOnly,
+            CurrentPage::JournalGoto(day("2022-05-06")),
+            CurrentPage::JournalViewReadOnly,
+            CurrentPage::JournalGoto(day("2022-05-09")),
+            CurrentPage::JournalViewReadOnly,
+            CurrentPage::JournalGoto(today),
+            CurrentPage::CreateNewJournal,
+            CurrentPage::JournalView,
+            CurrentPage::JournalGoto(day("2022-05-09")),
+            CurrentPage::JournalViewReadOnly,
+            CurrentPage::JournalGoto(today),
+            CurrentPage::JournalView,
+        ]
+    );
+}
+
+#[test]
+fn rollups_review_and_export_the_journal() {
+    let vault = TempVault::new("rollup");
+    let v = vault.open();
+    for (date, notes) in [
+        ("2023-01-02", vec!["Memex", "Trails"]),
+        ("2023-01-04", vec!["Memex"]),
+        ("2023-01-09", vec![]),
+    ] {
+        let mut page = Journal::for_date(date);
+        page.description = format!("Worked on {}.", date);
+        page.pages = notes.into_iter().map(String::from).collect();
+        page.save(&v).unwrap();
+    }
+    let day = |name| journal_date(name).unwrap();
+    let week = Rollup::build(&v, Period::Week, day("2023-01-04"));
+    assert_eq!(
+        (week.start, week.end),
+        (day("2023-01-02"), day("2023-01-08"))
+    );
+    assert_eq!(week.days.len(), 2);
+    assert_eq!(week.notes, vec!["Memex", "Trails"]);
+    assert_eq!(
+        Rollup::build(&v, Period::Month, day("2023-01-31"))
+            .days
+            .len(),
+        3
+    );
+
+    let mut c = vault.controller(vec![
+        Answer::No,
+        Answer::Menu(MenuOption::LoadJournal),
+        Answer::Calendar(CalendarMessage::Review(day("2023-01-04"))),
+        Answer::Rollup(RollupMessage::Next),
+        Answer::Rollup(RollupMessage::Month),
+        Answer::Rollup(RollupMessage::Export),
+        Answer::Rollup(RollupMessage::Back),
+        Answer::Calendar(CalendarMessage::Exit),
+    ]);
+
+    let pages = run(&mut c);
+
+    let back = Box::new(CurrentPage::Calendar(day("2023-01-04")));
+    assert_eq!(
+        pages[4..],
+        [
+            CurrentPage::Rollup(Period::Week, day("2023-01-04"), back.clone()),
+            CurrentPage::Rollup(Period::Week, day("2023-01-09"), back.clone()),
+            CurrentPage::Rollup(Period::Month, day("2023-01-09"), back.clone()),
+            CurrentPage::Rollup(Period::Month, day("2023-01-09"), back.clone()),
+            *back,
+        ]
+    );
+    let export = fs::read_to_string(v.export_path("journal-2023-01.md")).unwrap();
+    assert!(export.starts_with("# January 2023\n"));
+    assert!(export.contains("## Monday 9 January\n\nWorked on 2023-01-09."));
+}
+
+#[test]
+fn templates_fill_in_new_pages() {
+    let vault = TempVault::new("templates");
+    let templates = vault.root().join("templates");
+    fs::create_dir_all(&templates).unwrap();
+    fs::write(
+        templates.join("journal"),
+        "# {{weekday}} {{date}}\nYesterday:\nToday:\n",
+    )
+    .unwrap();
+    fs::write(templates.join("note"), "{{title}}, {{ unknown }}\n").unwrap();
+    fs::write(templates.join("trail"), "Started on {{date}}").unwrap();
+    let mut c = vault.controller(vec![
+        Answer::Yes,
+        Answer::Journal(JournalMessage::EditLinks),
+        text("Memex"),
+        Answer::Journal(JournalMessage::SelectLinks),
+        Answer::Finder(FinderMessage::Select(0)),
+        Answer::Yes,
+        Answer::Note(NoteMessage::Menu),
+        Answer::Menu(MenuOption::Trails),
+        Answer::CreateTrail(CreateTrailMessage::CreateTrail),
+        text("Tour"),
+        Answer::Trail(TrailMessage::Quit),
+    ]);
+
+    run(&mut c);
+
+    let v = vault.open();
+    let date = c.model().current_date.clone();
+    let weekday = journal_date(&date).unwrap().format("%A").to_string();
+    let journal = load_journal_page(&v, &date).unwrap();
+    assert_eq!(
+        journal.description,
+        format!("# {} {}\nYesterday:\nToday:", weekday, date)
+    );
+    assert_eq!(c.model().note.text, "Memex, {{ unknown }}");
+    assert_eq!(c.model().trail.description, format!("Started on {}", date));
+}
+
+#[test]
+fn empty_trail_files_open_with_the_template() {
+    let vault = TempVault::new("empty-trail");
+    let v = vault.open();
+    let templates = vault.root().join("templates");
+    fs::create_dir_all(&templates).unwrap();
+    fs::write(templates.join("trail"), "Trail {{title}}").unwrap();
+    fs::create_dir_all(v.path(Folder::Trails, "Tour").parent().unwrap()).unwrap();
+    fs::write(v.path(Folder::Trails, "Tour"), "").unwrap();
+    let mut c = vault.controller(vec![
+        Answer::No,
+        Answer::Menu(MenuOption::LoadCreateTrail),
+        Answer::CreateTrail(CreateTrailMessage::LoadTrail),
+        Answer::Finder(FinderMessage::Select(0)),
+        Answer::Trail(TrailMessage::Quit),
+    ]);
+
+    let pages = run(&mut c);
+
+    assert_eq!(pages.last(), Some(&CurrentPage::TrailView));
+    assert_eq!(c.model().trail.name, "Tour");
+    assert_eq!(c.model().trail.description, "Trail Tour");
+}
+
+#[test]
+fn log_lines_are_added_to_the_journal() {
+    let vault = TempVault::new("journal-log");
+    let mut c = vault.controller(vec![
+        Answer::Yes,
+        Answer::Journal(JournalMessage::Log),
+        text("Started the  centrifuge"),
+        Answer::Journal(JournalMessage::Log),
+        text(""),
+        Answer::Journal(JournalMessage::Menu),
+        Answer::Menu(MenuOption::Quit),
+    ]);
+
+    let pages = run(&mut c);
+
+    assert_eq!(
+        pages[2..6],
+        [
+            CurrentPage::JournalView,
+            CurrentPage::JournalLog,
+            CurrentPage::JournalView,
+            CurrentPage::JournalLog,
+        ]
+    );
+    let journal = load_journal_page(&vault.open(), &c.model().current_date).unwrap();
+    let entries = journal.entries();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].1, "Started the centrifuge");
+}
+
+#[test]
+fn notes_opened_from_past_pages_go_back_to_them() {
+    let vault = TempVault::new("past-page-notes");
+    let v = vault.open();
+    let today = chrono::Local::now()
+        .date_naive()
+        .format("%Y-%m-%d")
+        .to_string();
+    Journal::for_date(&today).save(&v).unwrap();
+    let mut past = Journal::for_date("2022-05-02");
+    past.description = String::from("Read the essay.");
+    past.save(&v).unwrap();
+    Note::from_str("Memex", String::from("A device."))
+        .save(&v)
+        .unwrap();
+    let past_file = fs::read_to_string(v.path(Folder::Journal, "2022-05-02")).unwrap();
+    let mut c = vault.controller(vec![
+        Answer::Journal(JournalMessage::Previous),
+        Answer::Journal(JournalMessage::Find),
+        // Notes come first
+        Answer::Finder(FinderMessage::Select(0)),
+        Answer::Note(NoteMessage::Back),
+        Answer::Journal(JournalMessage::Exit),
+    ]);
+
+    let pages = run(&mut c);
+
+    assert_eq!(
+        pages[1..],
+        [
+            CurrentPage::JournalView,
+            CurrentPage::JournalGoto(journal_date("2022-05-02").unwrap()),
+            CurrentPage::JournalViewReadOnly,
+            CurrentPage::Finder(Box::new(CurrentPage::JournalViewReadOnly)),
+            CurrentPage::NoteView,
+            CurrentPage::JournalViewReadOnly,
+        ]
+    );
+    assert_eq!(c.model().journal_page.date, "2022-05-02");
+    assert!(c.model().journal_page.pages.is_empty());
+    assert_eq!(
+        fs::read_to_string(v.path(Folder::Journal, "2022-05-02")).unwrap(),
+        past_file
+    );
+}
+
+#[test]
+fn links_of_past_pages_go_back_to_them() {
+    let vault = TempVault::new("past-page-links");
+    let v = vault.open();
+    let mut past = Journal::for_date("2022-05-02");
+    past.pages = vec![String::from("Memex"), String::from("Missing")];
+    past.save(&v).unwrap();
+    Note::from_str("Memex", String::from("A device."))
+        .save(&v)
+        .unwrap();
+    let past_file = fs::read_to_string(v.path(Folder::Journal, "2022-05-02")).unwrap();
+    let day = journal_date("2022-05-02").unwrap();
+    let mut c = vault.controller(vec![
+        Answer::No,
+        Answer::Menu(MenuOption::LoadJournal),
+        Answer::Calendar(CalendarMessage::Open(day)),
+        Answer::Journal(JournalMessage::SelectLinks),
+        Answer::Finder(FinderMessage::Back),
+        Answer::Journal(JournalMessage::SelectLinks),
+        Answer::Finder(FinderMessage::Select(1)),
+        Answer::No,
+        Answer::Journal(JournalMessage::SelectLinks),
+        Answer::Finder(FinderMessage::Select(0)),
+        Answer::Note(NoteMessage::Back),
+        Answer::Journal(JournalMessage::Exit),
+    ]);
+
+    let pages = run(&mut c);
+
+    let links = CurrentPage::SelectLink(
+        past.pages.clone(),
+        Box::new(CurrentPage::JournalViewReadOnly),
+    );
+    assert_eq!(
+        pages[4..],
+        [
+            CurrentPage::JournalViewReadOnly,
+            links.clone(),
+            CurrentPage::JournalViewReadOnly,
+            links.clone(),
+            CurrentPage::JournalViewReadOnly,
+            links,
+            CurrentPage::NoteView,
+            CurrentPage::JournalViewReadOnly,
+        ]
+    );
+    assert_eq!(c.model().journal_page.date, "2022-05-02");
+    assert_eq!(
+        fs::read_to_string(v.path(Folder::Journal, "2022-05-02")).unwrap(),
+        past_file
+    );
+}
+
+#[test]
+fn listed_pages_open_from_the_journal_and_the_note() {
+    let vault = TempVault::new("open-lists");
+    Note::from_str("Bush", String::from("Imagined the [Memex]."))
+        .save(&vault.open())
+        .unwrap();
+    let mut c = vault.controller(vec![
+        Answer::Yes,
+        Answer::Journal(JournalMessage::EditLinks),
+        text("Bush"),
+        Answer::Journal(JournalMessage::OpenLink(0)),
+        Answer::Note(NoteMessage::OpenBacklink(0)),
+        Answer::Journal(JournalMessage::Exit),
+    ]);
+
+    let pages = run(&mut c);
+
+    assert_eq!(
+        pages[3..],
+        [
+            CurrentPage::JournalAddLink,
+            CurrentPage::JournalView,
+            CurrentPage::NoteView,
+            CurrentPage::JournalView,
+        ]
+    );
+    assert_eq!(c.model().note.title, "Bush");
+}
+
+#[test]
+fn hops_open_their_notes() {
+    let vault = TempVault::new("open-hops");
+    Note::from_str("Memex", String::from("A device."))
+        .save(&vault.open())
+        .unwrap();
+    let mut c = vault.controller(vec![
+        Answer::No,
+        Answer::Menu(MenuOption::Trails),
+        Answer::CreateTrail(CreateTrailMessage::CreateTrail),
+        text("Tour"),
+        Answer::Trail(TrailMessage::AddLink),
+        hop("Memex", "Start here"),
+        Answer::Trail(TrailMessage::OpenHop(0)),
+        Answer::Note(NoteMessage::Menu),
+        Answer::Menu(MenuOption::Quit),
+    ]);
+
+    let pages = run(&mut c);
+
+    assert_eq!(
+        pages[8..],
+        [
+            CurrentPage::TrailView,
+            CurrentPage::NoteView,
+            CurrentPage::MainMenu,
+        ]
+    );
+    assert_eq!(c.model().note.title, "Memex");
+}
+
+#[test]
+fn the_menu_opens_or_creates_notes() {
+    let vault = TempVault::new("menu-notes");
+    Note::from_str("Memex", String::from("A device."))
+        .save(&vault.open())
+        .unwrap();
+    let mut c = vault.controller(vec![
+        Answer::No,
+        Answer::Menu(MenuOption::LoadCreateNote),
+        text("Memex"),
+        Answer::Note(NoteMessage::Menu),
+        Answer::Menu(MenuOption::LoadCreateNote),
+        text("Bush"),
+        Answer::Yes,
+        Answer::Note(NoteMessage::Menu),
+        Answer::Menu(MenuOption::LoadCreateNote),
+        text("Rapid selector"),
+        Answer::No,
+        Answer::Menu(MenuOption::Quit),
+    ]);
+
+    let pages = run(&mut c);
+
+    assert_eq!(
+        pages[3..],
+        [
+            CurrentPage::LoadCreateNote,
+            CurrentPage::NoteView,
+            CurrentPage::MainMenu,
+            CurrentPage::LoadCreateNote,
+            CurrentPage::NoteView,
+            CurrentPage::MainMenu,
+            CurrentPage::LoadCreateNote,
+            CurrentPage::MainMenu,
+        ]
+    );
+    let v = vault.open();
+    assert!(load_note(&v, "Bush").is_ok());
+    assert!(load_note(&v, "Rapid selector").is_err());
+}
+
+#[test]
+fn the_menu_reopens_the_current_note() {
+    let vault = TempVault::new("menu-current-note");
+    Note::from_str("Memex", String::from("A device."))
+        .save(&vault.open())
+        .unwrap();
+    let mut c = vault.controller(vec![
+        Answer::No,
+        // There's no current note yet
+        Answer::Menu(MenuOption::Notes),
+        text("Memex"),
+        Answer::Note(NoteMessage::Menu),
+        Answer::Menu(MenuOption::Notes),
+        Answer::Note(NoteMessage::Menu),
+        Answer::Menu(MenuOption::Quit),
+    ]);
+
+    let pages = run(&mut c);
+
+    assert_eq!(
+        pages[3..],
+        [
+            CurrentPage::LoadCreateNote,
+            CurrentPage::NoteView,
+            CurrentPage::MainMenu,
+            CurrentPage::NoteView,
+            CurrentPage::MainMenu,
+        ]
+    );
+    assert_eq!(c.model().note.title, "Memex");
+}
+
+#[test]
+fn the_menu_creates_or_loads_trails() {
+    let vault = TempVault::new("menu-trails");
+    Trail::from_template(&vault.open(), "Tour")
+        .save(&vault.open())
+        .unwrap();
+    let mut c = vault.controller(vec![
+        Answer::No,
+        Answer::Menu(MenuOption::LoadCreateTrail),
+        Answer::CreateTrail(CreateTrailMessage::LoadTrail),
+        Answer::Finder(FinderMessage::Select(0)),
+        Answer::Trail(TrailMessage::MainMenu),
+        // A trail is open, but the menu still offers to create a new one
+        Answer::Menu(MenuOption::LoadCreateTrail),
+        Answer::CreateTrail(CreateTrailMessage::CreateTrail),
+        text("Detour"),
+        Answer::Trail(TrailMessage::Quit),
+    ]);
+
+    let pages = run(&mut c);
+
+    assert_eq!(
+        pages[3..],
+        [
+            CurrentPage::SelectCreateTrail,
+            CurrentPage::LoadTrail,
+            CurrentPage::TrailView,
+            CurrentPage::MainMenu,
+            CurrentPage::SelectCreateTrail,
+            CurrentPage::CreateNewTrail,
+            CurrentPage::TrailView,
+        ]
+    );
+    assert_eq!(c.model().trail.name, "Detour");
+}
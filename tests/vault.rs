@@ -0,0 +1,479 @@
+//! Tests of the storage layer on temporary vaults: reading and writing pages
+//! in both document formats, upgrading old vaults, renaming notes and
+//! searching.
+mod common;
+
+use common::TempVault;
+use vannevar::{
+    load_journal_page, load_note, load_trail, migrate, reindex, rename_note, search, FileError,
+    Folder, Header, Journal, Note, PageRef, RenameError, Resolver, Trail, Vault,
+};
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+/// Writes a file of the vault, creating its folder if needed.
+fn write(vault: &Vault, folder: Folder, name: &str, text: &str) {
+    fs::create_dir_all(vault.folder(folder)).unwrap();
+    fs::write(vault.path(folder, name), text).unwrap();
+}
+
+/// Sets the last modification time of a file, in seconds from the epoch.
+fn set_modified(path: &Path, seconds: u64) {
+    let file = fs::File::options().write(true).open(path).unwrap();
+    file.set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(seconds))
+        .unwrap();
+}
+
+/// The last modification time of a file, in the format of the headers.
+fn modified(path: &Path) -> String {
+    let time = fs::metadata(path).unwrap().modified().unwrap();
+    chrono::DateTime::<chrono::Local>::from(time)
+        .naive_local()
+        .format("%Y-%m-%dT%H:%M:%S")
+        .to_string()
+}
+
+/// The contents of every page of the vault, by path.
+fn snapshot(vault: &Vault) -> BTreeMap<std::path::PathBuf, String> {
+    let mut files = BTreeMap::new();
+    for folder in Folder::ALL {
+        for entry in fs::read_dir(vault.folder(folder)).unwrap() {
+            let path = entry.unwrap().path();
+            files.insert(path.clone(), fs::read_to_string(path).unwrap());
+        }
+    }
+    files
+}
+
+/// A vault where a note, a journal page and a trail all link to `Memex`.
+fn linked_vault(name: &str) -> TempVault {
+    let vault = TempVault::new(name);
+    let v = vault.open();
+    Note::from_str("Memex", String::from("A device."))
+        .save(&v)
+        .unwrap();
+    Note::from_str(
+        "Bush",
+        String::from("He imagined the [Memex], then wrote about the [Memex]."),
+    )
+    .save(&v)
+    .unwrap();
+    let mut journal = Journal::for_date("2024-03-11");
+    journal.description = String::from("Read about the [Memex].");
+    journal.pages = vec![String::from("Memex")];
+    journal.save(&v).unwrap();
+    let mut trail = Trail {
+        name: String::from("Tour"),
+        ..Trail::new()
+    };
+    trail.insert_hop(0, "Memex", "Start here");
+    trail.save(&v).unwrap();
+    vault
+}
+
+/// Writes a note with the given header id and title.
+fn write_note(vault: &Vault, file: &str, id: &str, title: &str) {
+    let header = Header {
+        id: String::from(id),
+        title: String::from(title),
+        ..Header::new(Folder::Notes)
+    };
+    write(vault, Folder::Notes, file, &(header.to_str() + "A note."));
+}
+
+/// The only backup folder written so far.
+fn backup_folder(vault: &Vault) -> std::path::PathBuf {
+    let backups: Vec<_> = fs::read_dir(vault.meta_path("backup", ""))
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .collect();
+    assert_eq!(backups.len(), 1, "backups: {:?}", backups);
+    backups[0].clone()
+}
+
+#[test]
+fn version_0_pages_are_read_as_bodies() {
+    let vault = TempVault::new("v0-load");
+    let v = vault.open();
+    write(
+        &v,
+        Folder::Notes,
+        "Memex",
+        "A device imagined by [Vannevar Bush].",
+    );
+    write(
+        &v,
+        Folder::Journal,
+        "2024-03-11",
+        "Read the essay.\n---\n[Memex]\n",
+    );
+
+    let note = load_note(&v, "Memex").unwrap();
+    let journal = load_journal_page(&v, "2024-03-11").unwrap();
+
+    assert_eq!(note.title, "Memex");
+    assert_eq!(note.text, "A device imagined by [Vannevar Bush].");
+    assert_eq!(note.links, vec!["Vannevar Bush"]);
+    assert!(note.header.id.is_empty());
+    assert_eq!(journal.description, "Read the essay.");
+    assert_eq!(journal.pages, vec!["Memex"]);
+}
+
+#[test]
+fn saving_a_version_0_page_backs_it_up_first() {
+    let vault = TempVault::new("v0-save");
+    let v = vault.open();
+    let original = "A device imagined by [Vannevar Bush].";
+    write(&v, Folder::Notes, "Memex", original);
+    let path = v.path(Folder::Notes, "Memex");
+    set_modified(&path, 1_700_000_000);
+    let created = modified(&path);
+
+    let mut note = load_note(&v, "Memex").unwrap();
+    note.text.push_str(" Never built.");
+    note.save(&v).unwrap();
+
+    let saved = load_note(&v, "Memex").unwrap();
+    assert_eq!(
+        saved.text,
+        "A device imagined by [Vannevar Bush]. Never built."
+    );
+    assert_eq!(saved.header.created, created);
+    assert_eq!(saved.header.id, created.replace(['-', 'T', ':'], ""));
+    let backup = backup_folder(&v).join("notes").join("Memex");
+    assert_eq!(fs::read_to_string(backup).unwrap(), original);
+}
+
+#[test]
+fn unreadable_pages_are_not_written_over() {
+    let vault = TempVault::new("save-unreadable");
+    let v = vault.open();
+    let latin = b"Caf\xe9 au lait";
+    fs::create_dir_all(v.path(Folder::Notes, "Folder")).unwrap();
+    fs::write(v.path(Folder::Notes, "Latin-1"), latin).unwrap();
+
+    // The files changed since the notes were loaded
+    let save = |file: &str| {
+        let mut note = Note::from_str(file, String::from("Rewritten."));
+        note.file = String::from(file);
+        note.save(&v)
+    };
+    let saved = save("Latin-1");
+    assert!(
+        matches!(saved, Err(FileError::FormatError(..))),
+        "{:?}",
+        saved
+    );
+    let saved = save("Folder");
+    assert!(
+        matches!(saved, Err(FileError::ReadError(..))),
+        "{:?}",
+        saved
+    );
+
+    assert_eq!(fs::read(v.path(Folder::Notes, "Latin-1")).unwrap(), latin);
+    assert!(!v.meta_path("backup", "").exists());
+}
+
+#[test]
+fn migrate_upgrades_version_0_pages_in_place() {
+    let vault = TempVault::new("migrate");
+    let v = vault.open();
+    let note = "A device imagined by [Vannevar Bush].";
+    let journal = "Read the essay.\n---\n[Memex]\n";
+    let trail = "A tour.\n---\n[Memex]\nno description\n";
+    write(&v, Folder::Notes, "Memex", note);
+    write(&v, Folder::Journal, "2024-03-11", journal);
+    write(&v, Folder::Trails, "Tour", trail);
+    set_modified(&v.path(Folder::Notes, "Memex"), 1_700_000_000);
+    let created = modified(&v.path(Folder::Notes, "Memex"));
+
+    let report = migrate(&v).unwrap();
+
+    assert_eq!(
+        report.migrated,
+        vec![
+            PageRef::new(Folder::Journal, "2024-03-11"),
+            PageRef::new(Folder::Notes, "Memex"),
+        ]
+    );
+    assert_eq!(report.failed.len(), 1);
+    assert_eq!(report.failed[0].0, PageRef::new(Folder::Trails, "Tour"));
+    assert_eq!(report.backup, backup_folder(&v));
+    for (folder, name, text) in [("journal", "2024-03-11", journal), ("notes", "Memex", note)] {
+        let copy = report.backup.join(folder).join(name);
+        assert_eq!(fs::read_to_string(copy).unwrap(), text);
+    }
+    assert!(!report.backup.join("trails").exists());
+    assert_eq!(
+        fs::read_to_string(v.path(Folder::Trails, "Tour")).unwrap(),
+        trail
+    );
+
+    let migrated = load_note(&v, "Memex").unwrap();
+    assert_eq!(migrated.text, note);
+    assert_eq!(migrated.header.created, created);
+    assert_eq!(migrated.header.modified, created);
+    assert_eq!(
+        load_journal_page(&v, "2024-03-11").unwrap().pages,
+        vec!["Memex"]
+    );
+
+    // Upgraded pages are neither migrated nor backed up again
+    assert!(migrate(&v).unwrap().migrated.is_empty());
+    let mut edited = migrated;
+    edited.text = String::from("Rewritten.");
+    edited.save(&v).unwrap();
+    assert_eq!(backup_folder(&v), report.backup);
+}
+
+#[test]
+fn migrate_reports_why_pages_could_not_be_read() {
+    let vault = TempVault::new("migrate-errors");
+    let v = vault.open();
+    fs::create_dir_all(v.path(Folder::Notes, "Folder")).unwrap();
+    fs::write(v.path(Folder::Notes, "Latin-1"), b"First line\nCaf\xe9").unwrap();
+
+    let mut report = migrate(&v).unwrap();
+    report.failed.sort_by(|a, b| a.0.name.cmp(&b.0.name));
+
+    assert!(report.migrated.is_empty());
+    let [(folder, unreadable), (latin, invalid)] = &report.failed[..] else {
+        panic!("failed: {:?}", report.failed);
+    };
+    assert_eq!(folder, &PageRef::new(Folder::Notes, "Folder"));
+    assert!(
+        matches!(unreadable, FileError::ReadError(_, kind) if *kind != io::ErrorKind::InvalidData)
+    );
+    assert_eq!(latin, &PageRef::new(Folder::Notes, "Latin-1"));
+    match invalid {
+        FileError::FormatError(_, e) => assert_eq!((e.line, e.column), (2, 4)),
+        e => panic!("expected a format error, found {:?}", e),
+    }
+}
+
+#[test]
+fn dry_run_renames_only_report_the_changes() {
+    let vault = linked_vault("rename-dry-run");
+    let v = vault.open();
+    let before = snapshot(&v);
+
+    let mut report = rename_note(&v, "Memex", "Memory extender", true).unwrap();
+    report.pages.sort_by_key(|a| a.0.label());
+
+    assert_eq!(report.old, "Memex");
+    assert_eq!(report.destination, "Memory extender");
+    assert_eq!(
+        report.pages,
+        vec![
+            (PageRef::new(Folder::Journal, "2024-03-11"), 2),
+            (PageRef::new(Folder::Notes, "Bush"), 2),
+            (PageRef::new(Folder::Trails, "Tour"), 1),
+        ]
+    );
+    assert_eq!(report.links(), 5);
+    assert_eq!(report.files(), 4);
+    assert_eq!(snapshot(&v), before);
+}
+
+#[test]
+fn renames_rewrite_the_links_of_every_page() {
+    let vault = linked_vault("rename");
+    let v = vault.open();
+    let id = load_note(&v, "Memex").unwrap().header.id;
+
+    rename_note(&v, "Memex", "Memory extender", false).unwrap();
+
+    let note = load_note(&v, "Memory extender").unwrap();
+    assert_eq!(note.file, "Memory extender");
+    assert_eq!(note.header.id, id);
+    assert!(!v.path(Folder::Notes, "Memex").exists());
+    assert_eq!(
+        load_note(&v, "Bush").unwrap().text,
+        "He imagined the [Memory extender], then wrote about the [Memory extender]."
+    );
+    let journal = load_journal_page(&v, "2024-03-11").unwrap();
+    assert_eq!(journal.description, "Read about the [Memory extender].");
+    assert_eq!(journal.pages, vec!["Memory extender"]);
+    let trail = load_trail(&v, "Tour").unwrap();
+    assert_eq!(trail.hops[0].0, "Memory extender");
+    assert!(fs::read_dir(v.meta_path("rename", ""))
+        .unwrap()
+        .next()
+        .is_none());
+}
+
+#[test]
+fn renames_to_invalid_or_taken_titles_are_rejected() {
+    let vault = linked_vault("rename-rejected");
+    let v = vault.open();
+    let before = snapshot(&v);
+
+    for (file, title, error) in [
+        ("Memex", "  ", RenameError::InvalidTitle),
+        ("Memex", "The [Memex]", RenameError::InvalidTitle),
+        ("Memex", "Bush", RenameError::AlreadyExists),
+        ("Missing", "Found", RenameError::NotFound),
+    ] {
+        assert_eq!(rename_note(&v, file, title, false), Err(error), "{}", title);
+    }
+    assert_eq!(snapshot(&v), before);
+}
+
+#[test]
+fn renames_skip_the_pages_that_cant_be_read() {
+    let vault = linked_vault("rename-skipped");
+    let v = vault.open();
+    let malformed = "---\nversion: 1\nmood: happy\n---\nSee the [Memex].";
+    let latin = b"Caf\xe9 near the [Memex]";
+    write(&v, Folder::Journal, "2024-03-12", malformed);
+    fs::write(v.path(Folder::Notes, "Latin-1"), latin).unwrap();
+
+    let mut report = rename_note(&v, "Memex", "Memory extender", false).unwrap();
+    report.skipped.sort_by(|a, b| a.0.name.cmp(&b.0.name));
+
+    let skipped: Vec<_> = report.skipped.iter().map(|(p, _)| p.clone()).collect();
+    assert_eq!(
+        skipped,
+        vec![
+            PageRef::new(Folder::Journal, "2024-03-12"),
+            PageRef::new(Folder::Notes, "Latin-1"),
+        ]
+    );
+    assert!(report
+        .skipped
+        .iter()
+        .all(|(_, e)| matches!(e, FileError::FormatError(..))));
+    assert_eq!(report.links(), 5);
+    assert_eq!(
+        fs::read_to_string(v.path(Folder::Journal, "2024-03-12")).unwrap(),
+        malformed
+    );
+    assert_eq!(fs::read(v.path(Folder::Notes, "Latin-1")).unwrap(), latin);
+}
+
+#[test]
+fn failed_renames_restore_every_file() {
+    let vault = linked_vault("rename-rollback");
+    let v = vault.open();
+    let before = snapshot(&v);
+    // Journal pages are moved in place before the note, whose new file name
+    // is too long for the file system
+    let title = "Memex ".repeat(60);
+
+    let result = rename_note(&v, "Memex", title.trim(), false);
+
+    assert!(
+        matches!(
+            result,
+            Err(RenameError::FileError(FileError::WriteError(..)))
+        ),
+        "{:?}",
+        result
+    );
+    assert_eq!(snapshot(&v), before);
+    assert!(fs::read_dir(v.meta_path("rename", ""))
+        .unwrap()
+        .next()
+        .is_none());
+}
+
+/// The pages matching `query`, best first.
+fn found(vault: &Vault, query: &str) -> Vec<PageRef> {
+    search(vault, query).into_iter().map(|h| h.page).collect()
+}
+
+#[test]
+fn saved_pages_are_searched_through_the_index() {
+    let vault = linked_vault("search");
+    let v = vault.open();
+
+    assert_eq!(found(&v, "memex").len(), 4);
+    assert_eq!(
+        found(&v, "\"wrote about\""),
+        vec![PageRef::new(Folder::Notes, "Bush")]
+    );
+
+    let mut note = load_note(&v, "Memex").unwrap();
+    note.text = String::from("A desk with microfilm.");
+    note.save(&v).unwrap();
+
+    assert_eq!(
+        found(&v, "microfilm"),
+        vec![PageRef::new(Folder::Notes, "Memex")]
+    );
+    assert!(found(&v, "device").is_empty());
+
+    // Files edited outside of the program are picked up by a new index
+    write(&v, Folder::Notes, "Bush", "An engineer.");
+    assert_eq!(reindex(&v).unwrap(), 4);
+    assert_eq!(
+        found(&v, "engineer"),
+        vec![PageRef::new(Folder::Notes, "Bush")]
+    );
+    assert!(found(&v, "\"wrote about\"").is_empty());
+}
+
+#[test]
+fn new_pages_are_given_unused_ids() {
+    let vault = TempVault::new("ids");
+    let v = vault.open();
+    Note::from_str("Bush", String::from("An engineer."))
+        .save(&v)
+        .unwrap();
+    // Pages copied in from elsewhere, which the search index doesn't know
+    // about yet, take the ids of the next few seconds
+    let now = chrono::Local::now().naive_local();
+    let taken: Vec<_> = (0..5)
+        .map(|s| {
+            (now + chrono::Duration::seconds(s))
+                .format("%Y%m%d%H%M%S")
+                .to_string()
+        })
+        .collect();
+    for id in &taken {
+        write_note(&v, id, id, id);
+    }
+
+    let mut note = Note::from_str("Memex", String::from("A device."));
+    note.save(&v).unwrap();
+
+    assert!(!note.header.id.is_empty());
+    assert!(!taken.contains(&note.header.id), "{}", note.header.id);
+    assert!(search(&v, &taken[0]).is_empty());
+}
+
+#[test]
+fn links_resolve_to_ids_then_titles_then_file_names() {
+    let vault = TempVault::new("resolver");
+    let v = vault.open();
+    write_note(&v, "Memex", "20240311093012", "Memex");
+    // A title that looks like the id of another note
+    write_note(&v, "Number", "20240311093013", "20240311093012");
+    // A title that is the file name of another note
+    write_note(&v, "Extender", "20240311093014", "Bush");
+    write_note(&v, "Bush", "20240311093015", "Vannevar Bush");
+    // Two notes with the same title
+    write_note(&v, "Trail B", "20240311093016", "Trail");
+    write_note(&v, "Trail A", "20240311093017", "Trail");
+    write(&v, Folder::Notes, "Old note", "Written before headers.");
+
+    let resolver = Resolver::build(&v);
+
+    assert_eq!(resolver.resolve("20240311093012"), Some("Memex"));
+    assert_eq!(resolver.resolve("20240311093015"), Some("Bush"));
+    assert_eq!(resolver.resolve("Bush"), Some("Extender"));
+    assert_eq!(resolver.resolve("Vannevar Bush"), Some("Bush"));
+    assert_eq!(resolver.resolve("Number"), Some("Number"));
+    assert_eq!(resolver.resolve("Trail"), Some("Trail A"));
+    assert_eq!(resolver.resolve("Trail B"), Some("Trail B"));
+    assert_eq!(resolver.resolve("Old note"), Some("Old note"));
+    assert_eq!(resolver.resolve("Missing"), None);
+    assert_eq!(resolver.title("20240311093013"), "20240311093012");
+    assert_eq!(resolver.title("Extender"), "Bush");
+    assert_eq!(resolver.title("Missing"), "Missing");
+}